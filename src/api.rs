@@ -1,6 +1,7 @@
 use anyhow::Result;
 
 use zwift_race_finder::errors::*;
+use zwift_race_finder::event_analysis::apply_description_courses;
 use zwift_race_finder::models::*;
 
 pub async fn fetch_events() -> Result<Vec<ZwiftEvent>> {
@@ -51,7 +52,7 @@ pub async fn fetch_events() -> Result<Vec<ZwiftEvent>> {
         return Err(anyhow::anyhow!("API returned status: {}", status));
     }
 
-    let events: Vec<ZwiftEvent> = response.json().await.map_err(|e| {
+    let mut events: Vec<ZwiftEvent> = response.json().await.map_err(|e| {
         UserError::new(
             "Failed to parse Zwift API response",
             "The API returned data in an unexpected format",
@@ -62,5 +63,8 @@ pub async fn fetch_events() -> Result<Vec<ZwiftEvent>> {
         e
     })?;
 
+    // Racing Score events often only give per-category laps in the description
+    events.iter_mut().for_each(apply_description_courses);
+
     Ok(events)
 }
@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use std::path::PathBuf;
 use zwift_race_finder::{
-    database::{Database, RouteData},
+    database::{Database, RouteProvenance, RouteSource},
     zwift_offline_client::{load_routes_from_file, load_events_from_file},
 };
 
@@ -75,12 +75,12 @@ fn main() -> Result<()> {
         imported_route_ids.insert(route.route_id);
         
         // Skip routes with no distance data (shouldn't happen with proper extraction)
-        if route.distance_km == 0.0 && route.distance_m == 0.0 {
+        let Some(route_data) = route.to_route_data() else {
             println!("WARNING: Skipping route with no distance data: {} ({})", route.name, route.route_id);
             println!("         This indicates incomplete data extraction. Use wad_unpack.exe for full data.");
             skipped += 1;
             continue;
-        }
+        };
         
         // Count route types
         if route.event_only {
@@ -90,9 +90,7 @@ fn main() -> Result<()> {
         }
         
         // Check if route already exists
-        // Convert signed to unsigned, wrapping around for negative values
-        let route_id_u32 = route.route_id as u32;
-        let existing = db.get_route(route_id_u32)?;
+        let existing = db.get_route(route_data.route_id)?;
         
         if existing.is_some() && !args.update {
             if args.dry_run {
@@ -102,28 +100,6 @@ fn main() -> Result<()> {
             continue;
         }
         
-        // Convert to RouteData
-        let route_data = RouteData {
-            route_id: route_id_u32,
-            name: route.name.clone(),
-            distance_km: if route.distance_without_lead_in_km > 0.0 {
-                route.distance_without_lead_in_km
-            } else {
-                // For older data without separated lead-in
-                route.distance_km - route.lead_in_distance_km
-            },
-            elevation_m: route.elevation_gain as u32,
-            world: route.world_name.clone(),
-            surface: route.surface.clone(),
-            lead_in_distance_km: route.lead_in_distance_km,
-            lead_in_elevation_m: 0, // Not available yet
-            lead_in_distance_free_ride_km: None,
-            lead_in_elevation_free_ride_m: None,
-            lead_in_distance_meetups_km: None,
-            lead_in_elevation_meetups_m: None,
-            slug: None, // Can be added later from WhatsOnZwift
-        };
-        
         if args.dry_run {
             if existing.is_some() {
                 if args.update {
@@ -140,12 +116,13 @@ fn main() -> Result<()> {
                 imported += 1;
             }
         } else {
-            if existing.is_some() {
-                // For now, skip existing routes as we don't have an update method
-                println!("Skipping existing route: {} ({})", route.name, route.route_id);
+            let provenance = RouteProvenance::verified_now(RouteSource::ZwiftOffline);
+            if !db.add_route(&route_data, &provenance)? {
+                println!("Keeping higher-confidence data for route: {} ({})", route.name, route.route_id);
                 skipped += 1;
+            } else if existing.is_some() {
+                updated += 1;
             } else {
-                db.add_route(&route_data)?;
                 imported += 1;
             }
         }
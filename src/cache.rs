@@ -1,6 +1,7 @@
 //! Cache-related functionality for storing user stats
 
 use crate::models::{CachedStats, UserStats};
+use crate::profile;
 use anyhow::Result;
 use chrono::Utc;
 use std::fs;
@@ -10,10 +11,19 @@ pub fn get_cache_file() -> Result<PathBuf> {
     let mut cache_dir = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
     cache_dir.push("zwift-race-finder");
     fs::create_dir_all(&cache_dir)?;
-    cache_dir.push("user_stats.json");
+    cache_dir.push(stats_file_name(profile::active()));
     Ok(cache_dir)
 }
 
+/// Each profile caches its own stats; the default keeps the original file name
+fn stats_file_name(profile: &str) -> String {
+    if profile::is_default(profile) {
+        "user_stats.json".to_string()
+    } else {
+        format!("user_stats-{profile}.json")
+    }
+}
+
 pub fn load_cached_stats() -> Result<Option<UserStats>> {
     let cache_file = get_cache_file()?;
 
@@ -57,6 +67,12 @@ mod tests {
         assert!(cache_file.to_string_lossy().contains("user_stats.json"));
     }
 
+    #[test]
+    fn test_stats_file_name_per_profile() {
+        assert_eq!(stats_file_name("default"), "user_stats.json");
+        assert_eq!(stats_file_name("alice"), "user_stats-alice.json");
+    }
+
     #[test]
     fn test_load_and_save_cached_stats() {
         // Create a temporary directory for testing
@@ -81,6 +97,7 @@ mod tests {
             zwift_score: 250,
             category: "C".to_string(),
             username: "TestUser".to_string(),
+            estimate: None,
         };
 
         // Save stats
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Utc;
 use colored::*;
 use std::io::Write;
@@ -6,10 +6,35 @@ use std::io::Write;
 use crate::api::fetch_events;
 use crate::database;
 use crate::database::Database;
-use crate::route_discovery;
+use std::path::{Path, PathBuf};
+use zwift_race_finder::category::get_category_from_score;
+use zwift_race_finder::config::FullConfig;
 use zwift_race_finder::constants::*;
+use zwift_race_finder::credentials::{self, Credential, CredentialSource};
+use zwift_race_finder::description_mappings::{self, MappingChange, PlannedMapping};
 use zwift_race_finder::estimation::*;
+use zwift_race_finder::event_analysis::find_user_subgroup;
+use zwift_race_finder::event_display::{print_event, print_events_table};
+use zwift_race_finder::event_filtering::{
+    estimate_event_duration, filter_by_event_type, filter_by_sport, filter_by_time,
+};
+use zwift_race_finder::event_results;
+use zwift_race_finder::fit_import;
 use zwift_race_finder::formatting::*;
+use zwift_race_finder::lead_in;
+use zwift_race_finder::models::ZwiftEvent;
+use zwift_race_finder::profile;
+use zwift_race_finder::route_bundle::{self, ConflictStrategy, RouteBundle, SectionCounts};
+use zwift_race_finder::route_discovery;
+use zwift_race_finder::route_matcher::{self, MatchOutcome, RouteCandidate};
+use zwift_race_finder::route_names;
+use zwift_race_finder::score_estimate;
+use zwift_race_finder::strava_import;
+use zwift_race_finder::team::{self, MemberSpec, TeamFit, TeamMember};
+use zwift_race_finder::zwift_api::{RiderProfile, ZwiftApiClient};
+use zwift_race_finder::zwift_offline_client::ZwiftOfflineClient;
+use zwift_race_finder::zwift_offline_sync::{self, RouteDiff};
+use zwift_race_finder::zwiftpower_import;
 
 pub fn show_unknown_routes() -> Result<()> {
     let db = Database::new()?;
@@ -23,6 +48,7 @@ pub fn show_unknown_routes() -> Result<()> {
         println!("{:<12} {:<8} {}", "Route ID", "Seen", "Event Name");
         println!("{}", "-".repeat(60));
 
+        let mut suggested = 0;
         for (route_id, event_name, times_seen) in routes {
             println!(
                 "{:<12} {:<8} {}",
@@ -30,8 +56,26 @@ pub fn show_unknown_routes() -> Result<()> {
                 times_seen,
                 event_name
             );
+            let query = route_names::unknown_route_query(&event_name);
+            if let Some(likely) = db.find_routes_by_name(query)?.first() {
+                println!(
+                    "{:<21} ↳ likely {} (ID: {}, {:.0}% name match)",
+                    "",
+                    likely.route_name.green(),
+                    likely.route_id,
+                    likely.score * 100.0
+                );
+                suggested += 1;
+            }
         }
 
+        if suggested > 0 {
+            println!(
+                "\n{}: Likely routes are probably the same route under another ID.",
+                "Tip".yellow()
+            );
+            println!("'zwift-race-finder discover' records confident matches as route aliases.");
+        }
         println!(
             "\n{}: Research these routes on ZwiftHacks or Zwift Insider",
             "Tip".yellow()
@@ -41,154 +85,247 @@ pub fn show_unknown_routes() -> Result<()> {
     Ok(())
 }
 
-pub async fn discover_unknown_routes() -> Result<()> {
+/// How long `--discover-routes` works before stopping; `discover --resume` runs until the queue is done
+const DISCOVERY_SESSION_MINS: u64 = 2;
+
+pub async fn discover_unknown_routes(resume: bool, limit: Option<usize>) -> Result<()> {
     let db = Database::new()?;
-    let mut unknown = db.get_unknown_routes()?;
+    let queued = db.sync_discovery_queue()?;
+    if queued > 0 {
+        println!("📥 Queued {} newly seen unknown routes", queued);
+    }
 
-    if unknown.is_empty() {
-        println!("No unknown routes to discover!");
+    if db.get_due_discoveries(1)?.is_empty() {
+        println!("No unknown routes are due for discovery.");
+        println!("Run 'zwift-race-finder discover status' to see the queue.");
         return Ok(());
     }
 
-    // Sort by frequency (times_seen) to prioritize high-value routes
-    unknown.sort_by(|a, b| b.2.cmp(&a.2));
-
-    let total_count = unknown.len();
-    println!(
-        "🔍 Starting route discovery for {} unknown routes...",
-        total_count
-    );
+    println!("🔍 Working through the route discovery queue...");
     println!("📋 Prioritizing high-frequency events first\n");
 
-    // Process in batches to avoid timeouts
+    // Looked up in batches, with a pause between them to go easy on the sites
     const BATCH_SIZE: usize = 20;
-    const BATCH_TIMEOUT_MINS: u64 = 2;
 
-    let discovery = route_discovery::RouteDiscovery::new()?;
+    let config = FullConfig::load().unwrap_or_default();
+    let discovery = route_discovery::RouteDiscovery::from_config(&config.config.discovery)?;
+    let session_start = std::time::Instant::now();
+    let out_of_time = || !resume && session_start.elapsed().as_secs() > DISCOVERY_SESSION_MINS * 60;
     let mut total_discovered = 0;
     let mut total_failed = 0;
-    let mut total_skipped = 0;
-
-    // Process routes in batches
-    for (batch_num, chunk) in unknown.chunks(BATCH_SIZE).enumerate() {
-        let batch_start = std::time::Instant::now();
-        println!(
-            "\n📦 Batch {} of {} ({} routes):",
-            batch_num + 1,
-            (unknown.len() + BATCH_SIZE - 1) / BATCH_SIZE,
-            chunk.len()
-        );
+    let mut proposed_aliases: Vec<PlannedMapping> = Vec::new();
 
-        let mut batch_discovered = 0;
-        let mut batch_failed = 0;
-
-        for (route_id, event_name, times_seen) in chunk {
-            // Check if we're approaching timeout
-            if batch_start.elapsed().as_secs() > BATCH_TIMEOUT_MINS * 60 - 30 {
-                println!("\n⏰ Approaching timeout limit, saving progress...");
-                break;
-            }
+    'batches: loop {
+        let remaining = limit.map_or(BATCH_SIZE, |limit| {
+            BATCH_SIZE
+                .min(limit.saturating_sub(total_discovered + proposed_aliases.len() + total_failed))
+        });
+        // Proposed aliases stay due until confirmed, so they are skipped here
+        let proposed: std::collections::HashSet<u32> = proposed_aliases
+            .iter()
+            .map(|p| p.change.route_id())
+            .collect();
+        let batch: Vec<_> = db
+            .get_due_discoveries(remaining + proposed.len())?
+            .into_iter()
+            .filter(|entry| !proposed.contains(&entry.route_id))
+            .take(remaining)
+            .collect();
+        if batch.is_empty() {
+            break;
+        }
+        if total_discovered + total_failed > 0 {
+            println!("\n⏸️  Pausing 5 seconds before next batch...");
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+        }
 
-            // Check if we should attempt discovery (not tried recently)
-            if !db.should_attempt_discovery(*route_id)? {
-                println!("⏭️  Skipping {} (recently attempted)", event_name);
-                total_skipped += 1;
-                continue;
+        for entry in batch {
+            if out_of_time() {
+                println!("\n⏰ Session time is up; the rest stays queued.");
+                break 'batches;
             }
 
             print!(
                 "🔎 [{:3}x] Searching for '{}' (ID: {})... ",
-                times_seen, event_name, route_id
+                entry.priority, entry.event_name, entry.route_id
             );
             std::io::stdout().flush()?;
 
-            // Record the attempt
-            db.record_discovery_attempt(*route_id, event_name)?;
+            // A known route under another ID only needs an alias, written once confirmed
+            let query = route_names::unknown_route_query(&entry.event_name);
+            if let Some(known) = route_names::confident_match(db.find_routes_by_name(query)?) {
+                println!(
+                    "🔗 Known route {} (ID: {}), alias proposed",
+                    known.route_name, known.route_id
+                );
+                proposed_aliases.push(PlannedMapping {
+                    event_name: entry.event_name,
+                    change: MappingChange::Alias {
+                        alias_route_id: entry.route_id,
+                        canonical_route_id: known.route_id,
+                        route_name: known.route_name,
+                        score: known.score,
+                    },
+                });
+                continue;
+            }
 
             // Try to discover route data
-            match discovery.discover_route(event_name).await {
+            match discovery.discover_route(&entry.event_name).await {
                 Ok(discovered) => {
                     // Use the discovered route_id if it's valid, otherwise use the original
                     let final_route_id = if discovered.route_id != 9999 {
                         discovered.route_id
                     } else {
-                        *route_id
+                        entry.route_id
                     };
 
                     // Save to database
-                    db.save_discovered_route(
+                    let saved = db.save_discovered_route(
                         final_route_id,
                         discovered.distance_km,
                         discovered.elevation_m,
                         &discovered.world,
                         &discovered.surface,
                         &discovered.name,
+                        discovered.source,
                     )?;
+                    db.record_discovery_success(entry.route_id)?;
 
                     println!(
-                        "✅ Found! {}km, {}m elevation, ID: {}",
-                        discovered.distance_km, discovered.elevation_m, final_route_id
+                        "✅ Found! {}km, {}m elevation, ID: {} ({})",
+                        discovered.distance_km,
+                        discovered.elevation_m,
+                        final_route_id,
+                        discovered.source
                     );
-                    batch_discovered += 1;
+                    if !saved {
+                        println!(
+                            "   {} existing route data has higher confidence, not overwritten",
+                            "ℹ️".dimmed()
+                        );
+                    }
+                    if !discovered.segments.is_empty()
+                        && (saved || db.get_route_segments(final_route_id)?.is_empty())
+                    {
+                        db.replace_route_segments(final_route_id, &discovered.segments)?;
+                        println!("   {} segments saved", discovered.segments.len());
+                    }
+                    total_discovered += 1;
                 }
                 Err(e) => {
-                    println!("❌ Failed: {}", e);
-                    batch_failed += 1;
+                    let next_attempt_at =
+                        db.record_discovery_failure(entry.route_id, &e.to_string())?;
+                    println!("❌ Failed: {} (next try after {} UTC)", e, next_attempt_at);
+                    total_failed += 1;
                 }
             }
 
             // Small delay to be polite to external services
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
         }
+    }
+
+    let total_aliased = if proposed_aliases.is_empty() {
+        0
+    } else {
+        println!("\n{}", "Aliases inferred from event names:".green().bold());
+        confirm_and_apply_mappings(
+            &db,
+            description_mappings::DISCOVERY_AUDIT_SOURCE,
+            &proposed_aliases,
+        )?
+    };
+
+    let due = db.get_due_discoveries(usize::MAX)?.len();
+    println!("\n📊 Discovery Summary:");
+    println!("  ✅ Successfully discovered: {}", total_discovered);
+    println!("  🔗 Aliased to known routes: {}", total_aliased);
+    println!("  ❌ Failed to discover: {}", total_failed);
+    println!("  ⏳ Still due: {}", due);
+    if due > 0 {
+        println!("\n💡 Run 'zwift-race-finder discover --resume' to keep going.");
+    } else if total_discovered + total_aliased > 0 {
+        println!("\n💡 Tip: Run the tool normally to see the newly discovered routes in action!");
+    }
 
-        total_discovered += batch_discovered;
-        total_failed += batch_failed;
+    Ok(())
+}
+
+pub fn show_discovery_status() -> Result<()> {
+    let db = Database::new()?;
+    db.sync_discovery_queue()?;
+    let queue = db.get_discovery_queue()?;
 
-        // Batch summary
+    if queue.is_empty() {
         println!(
-            "\nBatch {} complete: {} found, {} failed",
-            batch_num + 1,
-            batch_discovered,
-            batch_failed
+            "The route discovery queue is empty. Unknown routes are queued when events use them."
         );
+        return Ok(());
+    }
+
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let (found, pending): (Vec<_>, Vec<_>) = queue.iter().partition(|entry| entry.found);
+    let (due, waiting): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|entry| entry.next_attempt_at <= now);
+
+    println!("\n{}", "Route Discovery Queue".bold());
+    println!("{}", "=".repeat(60));
+    println!("  Due now:  {}", due.len());
+    match waiting.iter().map(|entry| &entry.next_attempt_at).min() {
+        Some(next) => println!("  Waiting:  {} (next due {} UTC)", waiting.len(), next),
+        None => println!("  Waiting:  0"),
+    }
+    println!("  Found:    {}", found.len());
 
-        // Check if we should continue
-        if batch_start.elapsed().as_secs() > BATCH_TIMEOUT_MINS * 60 - 30 {
+    if !waiting.is_empty() {
+        println!("\n{}", "Waiting after failed lookups:".yellow());
+        println!(
+            "{:<12} {:<6} {:<9} {:<20} {}",
+            "Route ID", "Seen", "Attempts", "Next try (UTC)", "Event Name"
+        );
+        println!("{}", "-".repeat(80));
+        for entry in &waiting {
             println!(
-                "\n⏰ Timeout reached. {} routes remaining for next run.",
-                total_count - (batch_num + 1) * BATCH_SIZE
+                "{:<12} {:<6} {:<9} {:<20} {}",
+                entry.route_id.to_string().yellow(),
+                entry.priority,
+                entry.attempts,
+                entry.next_attempt_at,
+                entry.event_name
             );
-            break;
-        }
-
-        // Pause between batches
-        if batch_num + 1 < (unknown.len() + BATCH_SIZE - 1) / BATCH_SIZE {
-            println!("\n⏸️  Pausing 5 seconds before next batch...");
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            if let Some(error) = &entry.last_error {
+                println!("{:<12} {}", "", error.dimmed());
+            }
         }
     }
 
-    println!("\n📊 Discovery Summary:");
-    println!("  ✅ Successfully discovered: {}", total_discovered);
-    println!("  ❌ Failed to discover: {}", total_failed);
-    println!("  ⏭️  Skipped (recent attempts): {}", total_skipped);
-    println!(
-        "  ⏳ Remaining to process: {}",
-        total_count.saturating_sub(total_discovered + total_failed + total_skipped)
-    );
-
-    if total_discovered > 0 {
-        println!("\n💡 Tip: Run the tool normally to see the newly discovered routes in action!");
+    if !due.is_empty() {
+        println!(
+            "\n{}: Run 'zwift-race-finder discover --resume' to look up the {} due routes",
+            "Tip".yellow(),
+            due.len()
+        );
     }
-
     Ok(())
 }
 
-pub fn record_race_result(input: &str) -> Result<()> {
-    // Parse format: "route_id,minutes,event_name[,zwift_score]"
+const RECORD_RESULT_FORMAT: &str =
+    "Format: --record-result 'route_id,minutes,event_name[,zwift_score][,key=value...]' \
+     (keys: position, field, cat, avg, np, weight, event, date, source, dnf)";
+
+/// Parse the `--record-result` argument into a race result and the score given with it
+///
+/// Positional fields come first; optional details follow as `key=value`
+/// pairs, e.g. `3379779247,45,3R Volcano Flat,212,position=14,field=58,cat=C,np=231`.
+/// `position=dnf` (or a bare `dnf`) marks a did-not-finish. Only the trailing
+/// `key=value` parts are details, so the event name may contain `,` and `=`.
+/// Without a score the result's `zwift_score` is left for the caller to fill in.
+pub fn parse_race_result_input(input: &str) -> Result<(database::RaceResult, Option<u32>)> {
     let parts: Vec<&str> = input.split(',').collect();
     if parts.len() < 3 {
-        anyhow::bail!("Format: --record-result 'route_id,minutes,event_name[,zwift_score]'");
+        anyhow::bail!(RECORD_RESULT_FORMAT);
     }
 
     let route_id: u32 = parts[0]
@@ -200,60 +337,95 @@ pub fn record_race_result(input: &str) -> Result<()> {
         .parse()
         .map_err(|_| anyhow::anyhow!("Invalid minutes"))?;
 
-    // Check if zwift_score is provided at position 3 (before event name)
-    let (event_name, zwift_score_override) =
-        if parts.len() >= 4 && parts[3].trim().parse::<u32>().is_ok() {
-            // Format: route_id,minutes,event_name,zwift_score
-            let event_name = parts[2].trim().to_string();
-            let zwift_score = parts[3].trim().parse::<u32>().unwrap();
-            (event_name, Some(zwift_score))
-        } else {
-            // Format: route_id,minutes,event_name (may contain commas)
-            let event_name = parts[2..].join(",").trim().to_string();
-            (event_name, None)
-        };
+    // Details are the run of key=value parts at the end; the event name
+    // always keeps at least its first part
+    let mut rest = &parts[2..];
+    let mut details = Vec::new();
+    while rest.len() > 1 && is_result_detail(rest[rest.len() - 1]) {
+        details.push(rest[rest.len() - 1]);
+        rest = &rest[..rest.len() - 1];
+    }
+    details.reverse();
+
+    // An optional zwift_score follows the event name
+    let zwift_score_override = match rest {
+        [.., last] if rest.len() > 1 => last.trim().parse::<u32>().ok(),
+        _ => None,
+    };
+    if zwift_score_override.is_some() {
+        rest = &rest[..rest.len() - 1];
+    }
+    let event_name = rest.join(",").trim().to_string();
+
+    let mut result = database::RaceResult {
+        route_id,
+        event_name,
+        actual_minutes: minutes,
+        zwift_score: zwift_score_override.unwrap_or_default(),
+        race_date: Utc::now().format("%Y-%m-%d").to_string(),
+        source: Some("manual".to_string()),
+        ..Default::default()
+    };
+
+    for detail in details {
+        let (key, value) = detail.split_once('=').unwrap_or((detail, ""));
+        let (key, value) = (key.trim().to_lowercase(), value.trim());
+        let invalid = || anyhow::anyhow!("Invalid value for {key}: '{value}'");
+        match key.as_str() {
+            "dnf" => result.dnf = value.is_empty() || matches!(value, "1" | "true" | "yes"),
+            "position" | "pos" if value.eq_ignore_ascii_case("dnf") => result.dnf = true,
+            "position" | "pos" => result.position = Some(value.parse().map_err(|_| invalid())?),
+            "field" | "field_size" => {
+                result.field_size = Some(value.parse().map_err(|_| invalid())?)
+            }
+            "cat" | "category" => result.category = Some(value.to_uppercase()),
+            "avg" | "avg_power" => result.avg_power_w = Some(value.parse().map_err(|_| invalid())?),
+            "np" | "np_power" => result.np_power_w = Some(value.parse().map_err(|_| invalid())?),
+            "weight" => result.weight_kg = Some(value.parse().map_err(|_| invalid())?),
+            "event" | "event_id" => result.event_id = Some(value.parse().map_err(|_| invalid())?),
+            "date" => {
+                chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid())?;
+                result.race_date = value.to_string();
+            }
+            "source" => result.source = Some(value.to_string()),
+            _ => anyhow::bail!("Unknown result field '{key}'. {RECORD_RESULT_FORMAT}"),
+        }
+    }
 
+    Ok((result, zwift_score_override))
+}
+
+/// Whether a `--record-result` part is a `key=value` detail or a bare `dnf`
+fn is_result_detail(part: &str) -> bool {
+    let part = part.trim();
+    if part.eq_ignore_ascii_case("dnf") {
+        return true;
+    }
+    part.split_once('=').is_some_and(|(key, _)| {
+        let key = key.trim();
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+pub async fn record_race_result(input: &str) -> Result<()> {
+    let (mut result, zwift_score) = parse_race_result_input(input)?;
     let db = Database::new()?;
+    result.zwift_score = recorded_result_score(&db, &result.race_date, zwift_score).await?;
+    let route_id = result.route_id;
+    let minutes = result.actual_minutes;
+    let event_name = result.event_name.clone();
+    let zwift_score = result.zwift_score;
 
-    // Ensure route exists so race_result FK is satisfied
+    // Unknown routes are queued for discovery rather than stubbed with a 0 km route
     if db.get_route(route_id)?.is_none() {
         println!(
-            "{}: Route {} not found in database — creating stub entry",
+            "{}: Route {} not found in database — estimates won't use this result until it is",
             "Warning".yellow(),
             route_id
         );
-        let stub = database::RouteData {
-            route_id,
-            distance_km: 0.0,
-            elevation_m: 0,
-            name: event_name.clone(),
-            world: "Unknown".to_string(),
-            surface: "road".to_string(),
-            lead_in_distance_km: 0.0,
-            lead_in_elevation_m: 0,
-            lead_in_distance_free_ride_km: None,
-            lead_in_elevation_free_ride_m: None,
-            lead_in_distance_meetups_km: None,
-            lead_in_elevation_meetups_m: None,
-            slug: None,
-        };
-        db.add_route(&stub)?;
         db.record_unknown_route(route_id, &event_name, "RACE")?;
     }
 
-    // Get zwift_score from override or default
-    let zwift_score = zwift_score_override.unwrap_or(195);
-
-    let result = database::RaceResult {
-        id: None,
-        route_id,
-        event_name: event_name.clone(),
-        actual_minutes: minutes,
-        zwift_score,
-        race_date: Utc::now().format("%Y-%m-%d").to_string(),
-        notes: None,
-    };
-
     db.add_race_result(&result)?;
 
     println!(
@@ -264,8 +436,33 @@ pub fn record_race_result(input: &str) -> Result<()> {
     println!("  Event: {}", event_name);
     println!("  Time: {}", format_duration(minutes));
     println!("  Zwift Score: {}", zwift_score);
+    if result.dnf {
+        println!("  Finish: {}", "DNF".red());
+    } else if let Some(position) = result.position {
+        match result.field_size {
+            Some(field) => println!("  Position: {}/{}", position, field),
+            None => println!("  Position: {}", position),
+        }
+    }
+    if let Some(category) = &result.category {
+        println!("  Category: {}", category);
+    }
+    if let (Some(np), Some(weight)) = (result.np_power_w, result.weight_kg) {
+        println!("  NP: {}W ({:.2} W/kg)", np, f64::from(np) / weight);
+    } else if let Some(np) = result.np_power_w {
+        println!("  NP: {}W", np);
+    }
+    if !result.usable_for_calibration() {
+        println!(
+            "  {} excluded from calibration (DNF or raced outside own category)",
+            "Note:".yellow()
+        );
+    }
 
     // Show comparison with estimate if route is known
+    if result.dnf {
+        return Ok(());
+    }
     if let Some(estimated) = estimate_duration_from_route_id(route_id, zwift_score) {
         let diff = (estimated as i32 - minutes as i32).abs();
         let accuracy = PERCENT_MULTIPLIER - (diff as f64 / minutes as f64 * PERCENT_MULTIPLIER);
@@ -279,7 +476,166 @@ pub fn record_race_result(input: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn analyze_event_descriptions() -> Result<()> {
+/// Events offered by the `record` picker
+const RECORD_PICKER_EVENTS: usize = 20;
+
+/// Record a finish time against an archived event, picking the event interactively if needed
+///
+/// Route, laps, category, date and the score at the time come from the
+/// event and the rider's history; only the finish time is asked for.
+pub async fn record_event_result(
+    event_id: Option<u64>,
+    time: Option<&str>,
+    zwift_score: Option<u32>,
+    dry_run: bool,
+) -> Result<()> {
+    let db = Database::new()?;
+    let event = match event_id {
+        Some(event_id) => db.get_archived_event(event_id)?.with_context(|| {
+            format!(
+                "Event {} is not in the archive; events are archived when a search shows them",
+                event_id
+            )
+        })?,
+        None => pick_archived_event(&db)?,
+    };
+
+    let race_date = event.event_start.format("%Y-%m-%d").to_string();
+    let zwift_score = recorded_result_score(&db, &race_date, zwift_score).await?;
+    let subgroup = if event.event_sub_groups.len() == 1 {
+        event.event_sub_groups.first()
+    } else {
+        find_user_subgroup(&event, zwift_score)
+    };
+    let mut captured = event_results::event_result(&event, subgroup, zwift_score)?;
+    captured.result.source = Some("manual".to_string());
+    let route_id = captured.result.route_id;
+    let route = db.get_route(route_id)?;
+
+    println!("🏁 {}", event.name.bold());
+    println!("  Date: {}", race_date);
+    println!(
+        "  Route: {} x{}",
+        route
+            .as_ref()
+            .map_or_else(|| format!("{} (unknown)", route_id), |r| r.name.clone()),
+        captured.laps
+    );
+    if let Some(route) = &route {
+        let distance_km = route.lead_in_distance_km + route.distance_km * f64::from(captured.laps);
+        println!("  Distance: {:.1} km", distance_km);
+    }
+    if let Some(subgroup) = subgroup {
+        println!("  Pen: {}", subgroup.name);
+    }
+    println!("  Zwift Score: {}", zwift_score);
+
+    let time = match time {
+        Some(time) => time.to_string(),
+        None => {
+            eprint!("Finish time (h:mm:ss, mm:ss or minutes): ");
+            std::io::stderr().flush()?;
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line
+        }
+    };
+    captured.result.actual_minutes = event_results::parse_finish_time(&time)
+        .with_context(|| format!("Invalid finish time '{}'", time.trim()))?;
+    let result = &captured.result;
+
+    if db.has_race_result(result)? {
+        println!("Already recorded.");
+        return Ok(());
+    }
+    if dry_run {
+        println!(
+            "Would record {}. This was a dry run - no changes were made.",
+            format_duration(result.actual_minutes)
+        );
+        return Ok(());
+    }
+    if route.is_none() {
+        db.record_unknown_route(route_id, &result.event_name, &event.event_type)?;
+        println!(
+            "{} Route {} is not in the database yet; estimates won't use this result until it is",
+            "⚠️".yellow(),
+            route_id
+        );
+    }
+    record_matched_result(&db, result, captured.laps)?;
+    println!(
+        "✅ Recorded {} for {}",
+        format_duration(result.actual_minutes),
+        result.event_name
+    );
+    if let Some(estimated) = estimate_event_duration(&event, zwift_score) {
+        println!("  Estimated: {}", format_duration(estimated));
+    }
+    Ok(())
+}
+
+/// List recently started archived events and read the rider's choice
+fn pick_archived_event(db: &Database) -> Result<ZwiftEvent> {
+    let mut events = db.get_recent_archived_events(RECORD_PICKER_EVENTS)?;
+    if events.is_empty() {
+        anyhow::bail!(
+            "No past events in the archive; events are archived when a search shows them"
+        );
+    }
+    println!("Recent events:");
+    for (i, event) in events.iter().enumerate() {
+        let local: chrono::DateTime<chrono::Local> = event.event_start.into();
+        println!(
+            "  {:>2}) {}  {}",
+            i + 1,
+            local.format("%a %d %b %H:%M").to_string().cyan(),
+            event.name
+        );
+    }
+    eprint!("Event number: ");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let choice: usize = line
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n| (1..=events.len()).contains(n))
+        .with_context(|| format!("No event numbered '{}'", line.trim()))?;
+    Ok(events.swap_remove(choice - 1))
+}
+
+/// Score for a single recorded result: the one given, else the race day's
+async fn recorded_result_score(
+    db: &Database,
+    race_date: &str,
+    zwift_score: Option<u32>,
+) -> Result<u32> {
+    if let Some(score) = zwift_score {
+        return Ok(score);
+    }
+    // The Zwift profile is only asked for when the history has no score that day
+    let rider = match db.get_score_on(race_date)? {
+        Some(_) => None,
+        None => fetch_rider_profile().await?,
+    };
+    RaceDayScores::new(db, rider.as_ref()).score_on(race_date)
+}
+
+/// The rider's Zwift profile, when a Zwift API token is configured
+async fn fetch_rider_profile() -> Result<Option<RiderProfile>> {
+    let config = FullConfig::load().unwrap_or_default();
+    match config.secrets.zwift_api_token.as_deref() {
+        Some(token) => {
+            let client = ZwiftApiClient::new(config.config.zwift_api.base_url(), token)?;
+            Ok(Some(client.fetch_profile().await?))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn analyze_event_descriptions(apply: bool) -> Result<()> {
     println!(
         "\n{}",
         "Analyzing Event Descriptions for Route Names..."
@@ -348,15 +704,68 @@ pub async fn analyze_event_descriptions() -> Result<()> {
         }
     }
 
+    if apply {
+        return apply_description_mappings(&events);
+    }
+
     println!(
         "\n{}: Use this information to create route mappings",
         "Next Step".yellow()
     );
-    println!("Look up the actual route names on Zwift Insider or ZwiftHacks");
+    println!("Run again with --apply to save the routes that can be matched,");
+    println!("or look up the actual route names on Zwift Insider or ZwiftHacks");
+
+    Ok(())
+}
+
+/// Show the mappings the descriptions support and write them once confirmed
+fn apply_description_mappings(events: &[ZwiftEvent]) -> Result<()> {
+    let db = Database::new()?;
+    let planned = description_mappings::plan_mappings(&db, events)?;
+    if planned.is_empty() {
+        println!("\nNothing to apply: no parsed route matches a known route or has a distance.");
+        return Ok(());
+    }
 
+    println!("\n{}", "Mappings to apply:".green().bold());
+    confirm_and_apply_mappings(&db, description_mappings::AUDIT_SOURCE, &planned)?;
     Ok(())
 }
 
+/// List planned mappings, ask before writing them and record them under `source`
+///
+/// Returns how many changes were written; none when the user declines.
+fn confirm_and_apply_mappings(
+    db: &Database,
+    source: &str,
+    planned: &[PlannedMapping],
+) -> Result<usize> {
+    for mapping in planned {
+        println!("  {} {}", "•".cyan(), mapping.change);
+        println!("    {}", format!("from '{}'", mapping.event_name).dimmed());
+    }
+
+    eprint!("\nApply {} changes? [y/N] ", planned.len());
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    if !matches!(line.trim().to_lowercase().as_str(), "y" | "yes") {
+        println!("Nothing written.");
+        return Ok(0);
+    }
+
+    let applied = description_mappings::apply_mappings(db, source, planned)?;
+    println!("✅ Applied {} changes", applied);
+    if applied < planned.len() {
+        println!(
+            "   {} routes already had more trusted data and were left alone",
+            planned.len() - applied
+        );
+    }
+    println!("   Review them with 'zwift-race-finder db audit'");
+    Ok(applied)
+}
+
 pub fn mark_route_complete(route_id: u32) -> Result<()> {
     let db = Database::new()?;
 
@@ -429,3 +838,1223 @@ pub fn show_route_progress() -> Result<()> {
 
     Ok(())
 }
+
+/// List the events the rider has signed up for, with estimated durations
+pub async fn show_my_events(zwift_score: Option<u32>, verbose: bool) -> Result<()> {
+    let config = FullConfig::load().unwrap_or_default();
+    let Some(token) = config.secrets.zwift_api_token.as_deref() else {
+        anyhow::bail!(
+            "No Zwift API token. Set {} or run: zwift-race-finder credentials set {}",
+            Credential::ZwiftApiToken.env_var(),
+            Credential::ZwiftApiToken.key()
+        );
+    };
+    let client = ZwiftApiClient::new(config.config.zwift_api.base_url(), token)?;
+    let rider = client.fetch_profile().await?;
+
+    println!("🚴 {}", rider.name().bold());
+    if let Some(score) = rider.racing_score() {
+        println!(
+            "  Racing Score {} (Cat {})",
+            score.to_string().yellow(),
+            rider
+                .category()
+                .unwrap_or_else(|| get_category_from_score(score))
+        );
+    }
+    if let Some(weight_kg) = rider.weight_kg() {
+        println!("  Weight {:.1} kg", weight_kg);
+    }
+    if let Some(ftp) = rider.ftp {
+        println!("  FTP {} W", ftp);
+    }
+    println!();
+
+    let zwift_score = zwift_score
+        .or_else(|| rider.racing_score())
+        .or_else(|| config.default_zwift_score())
+        .unwrap_or(195);
+    let mut events = client.fetch_signups(rider.id).await?;
+    if let Ok(db) = Database::new() {
+        let _ = db.archive_events(&events);
+    }
+    if events.is_empty() {
+        println!("No upcoming event signups.");
+        return Ok(());
+    }
+    events.sort_by_key(|event| event.event_start);
+
+    println!(
+        "Signed up for {} upcoming events:",
+        events.len().to_string().green().bold()
+    );
+    if verbose {
+        for event in &events {
+            print_event(event, zwift_score);
+        }
+    } else {
+        print_events_table(&events, zwift_score);
+    }
+    Ok(())
+}
+
+pub fn show_score_history(limit: usize) -> Result<()> {
+    let db = Database::new()?;
+    let history = db.get_score_history(limit)?;
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        println!("No racing score history yet.");
+        println!("Scores are recorded each time your ZwiftPower profile is fetched.");
+        return Ok(());
+    };
+
+    println!("📈 {}", "Racing Score History".bold());
+    println!();
+
+    // Bars start below the lowest reading so small changes stay visible
+    let min = history.iter().map(|e| e.zwift_score).min().unwrap_or(0);
+    let max = history.iter().map(|e| e.zwift_score).max().unwrap_or(0);
+    let floor = min.saturating_sub((max - min).max(10) / 2);
+    let bar_width: usize = 30;
+    for entry in &history {
+        let filled =
+            bar_width * (entry.zwift_score - floor) as usize / (max - floor).max(1) as usize;
+        let bar = "█".repeat(filled) + &"░".repeat(bar_width - filled);
+        println!(
+            "  {}  {:>4} {:<2}  {}",
+            entry.recorded_at.get(..10).unwrap_or(&entry.recorded_at),
+            entry.zwift_score,
+            entry.category.as_deref().unwrap_or(""),
+            bar.bright_green()
+        );
+    }
+    println!();
+
+    if history.len() > 1 {
+        let change = i64::from(last.zwift_score) - i64::from(first.zwift_score);
+        let change = match change.cmp(&0) {
+            std::cmp::Ordering::Greater => format!("+{change}").green(),
+            std::cmp::Ordering::Less => change.to_string().red(),
+            std::cmp::Ordering::Equal => "no change".normal(),
+        };
+        println!(
+            "Trend: {} over {} readings (range {}-{})",
+            change,
+            history.len(),
+            min,
+            max
+        );
+    }
+    if let Some(weight_kg) = last.weight_kg {
+        match last.ftp_w {
+            Some(ftp_w) => println!(
+                "Latest: {:.1} kg, FTP {} W ({:.1} W/kg)",
+                weight_kg,
+                ftp_w,
+                f64::from(ftp_w) / weight_kg
+            ),
+            None => println!("Latest: {:.1} kg", weight_kg),
+        }
+    }
+    Ok(())
+}
+
+/// Racing score for a team member given by profile name
+///
+/// Uses the profile's latest recorded ZwiftPower score, then the score in its
+/// own config section; `config` is the file as written, so the active
+/// profile is read the same way as its teammates.
+fn profile_zwift_score(name: &str, config: &zwift_race_finder::config::Config) -> Result<u32> {
+    let history = Database::new()?.with_rider(name).get_score_history(1)?;
+    if let Some(entry) = history.last() {
+        return Ok(entry.zwift_score);
+    }
+    config
+        .profile_defaults(name)
+        .and_then(|defaults| defaults.zwift_score)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No racing score for profile '{}'; give one as {}=SCORE",
+                name,
+                name
+            )
+        })
+}
+
+pub async fn find_team_events(
+    specs: &[MemberSpec],
+    allow_adjacent: bool,
+    event_type: &str,
+    days: u32,
+    duration: Option<u32>,
+    tolerance: u32,
+) -> Result<()> {
+    let config = zwift_race_finder::config::Config::load_file().unwrap_or_default();
+    let members = specs
+        .iter()
+        .map(|spec| match spec {
+            MemberSpec::Profile(name) => Ok(TeamMember {
+                name: name.clone(),
+                zwift_score: profile_zwift_score(name, &config)?,
+            }),
+            MemberSpec::Score { name, zwift_score } => Ok(TeamMember {
+                name: name.clone(),
+                zwift_score: *zwift_score,
+            }),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("👥 {}", "Team".bold());
+    for member in &members {
+        println!(
+            "  {:<16} {:>4} (Cat {})",
+            member.name,
+            member.zwift_score,
+            get_category_from_score(member.zwift_score)
+        );
+    }
+    println!();
+
+    let mut events = fetch_events().await?;
+    if let Ok(db) = Database::new() {
+        let _ = db.archive_events(&events);
+    }
+    let now = Utc::now();
+    filter_by_sport(&mut events);
+    filter_by_time(
+        &mut events,
+        now,
+        now + chrono::Duration::days(i64::from(days)),
+    );
+    filter_by_event_type(&mut events, event_type);
+
+    let fits: Vec<TeamFit> = events
+        .iter()
+        .filter_map(|event| team::fit_team(event, &members, allow_adjacent))
+        .filter(|fit| {
+            // Every rider's estimate has to fit the wanted duration
+            duration.is_none_or(|target| {
+                fit.placements.iter().all(|p| {
+                    p.estimated_minutes
+                        .is_some_and(|minutes| minutes.abs_diff(target) <= tolerance)
+                })
+            })
+        })
+        .collect();
+
+    if fits.is_empty() {
+        println!("No upcoming events where the whole team fits.");
+        if !allow_adjacent {
+            println!(
+                "Try {} to allow pens one category apart.",
+                "--adjacent".yellow()
+            );
+        }
+        return Ok(());
+    }
+
+    println!("Found {} events the team can ride together:\n", fits.len());
+    for fit in &fits {
+        let local: chrono::DateTime<chrono::Local> = fit.event.event_start.into();
+        println!(
+            "{} {}",
+            local.format("%a %d %b %H:%M").to_string().cyan(),
+            fit.event.name.bold()
+        );
+        if let Some(route) = &fit.event.route {
+            println!("  Route: {}", route);
+        }
+        for placement in &fit.placements {
+            let offset = (placement.start - fit.event.event_start).num_minutes();
+            let finish = placement.finish().map_or("?".to_string(), |t| {
+                let local: chrono::DateTime<chrono::Local> = t.into();
+                local.format("%H:%M").to_string()
+            });
+            println!(
+                "  {:<16} {:<24} start +{:<3} est. {:<10} finish {}",
+                placement.member.name,
+                placement.subgroup.name,
+                format!("{}m", offset),
+                placement
+                    .estimated_minutes
+                    .map_or("?".to_string(), format_duration),
+                finish.green()
+            );
+        }
+        let finish_spread = fit.finish_spread().map_or("unknown".to_string(), |d| {
+            format!("{} min", d.num_minutes())
+        });
+        println!(
+            "  {} start spread {} min, finish spread {}{}",
+            "↔".dimmed(),
+            fit.start_spread().num_minutes(),
+            finish_spread,
+            if fit.same_pen() { ", same pen" } else { "" }
+        );
+        println!();
+    }
+    Ok(())
+}
+
+pub fn set_credential(credential: Credential, value: Option<&str>) -> Result<()> {
+    let store = credentials::default_store()?;
+    let value = match value {
+        Some(value) => value.to_string(),
+        None => {
+            eprint!("{}: ", credential.key());
+            std::io::stderr().flush()?;
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line.trim().to_string()
+        }
+    };
+    if value.is_empty() {
+        anyhow::bail!("No value given for {}", credential.key());
+    }
+
+    let profile = profile::active();
+    store.set(&credential.store_key(profile), &value)?;
+    println!(
+        "✅ Stored {} in {}",
+        credential.store_key(profile).green(),
+        store.describe()
+    );
+    if profile::is_default(profile) && std::env::var(credential.env_var()).is_ok() {
+        println!(
+            "   {} {} is set and overrides the stored value",
+            "Note:".yellow(),
+            credential.env_var()
+        );
+    }
+    Ok(())
+}
+
+pub fn show_credentials(credential: Option<Credential>, show: bool) -> Result<()> {
+    // Environment values can still be shown when no store is available
+    let store = match credentials::default_store() {
+        Ok(store) => Some(store),
+        Err(e) => {
+            println!("{} {}", "Store unavailable:".yellow(), e);
+            None
+        }
+    };
+    let selected = credential.map_or(Credential::ALL.to_vec(), |c| vec![c]);
+    for credential in selected {
+        let resolved = credentials::resolve(credential, profile::active(), store.as_deref())?;
+        let Some((value, source)) = resolved else {
+            println!("{:<24} {}", credential.key(), "not set".dimmed());
+            continue;
+        };
+        let value = if credential.is_secret() && !show {
+            credentials::mask(&value)
+        } else {
+            value
+        };
+        let source = match source {
+            CredentialSource::Environment => format!("from {}", credential.env_var()),
+            CredentialSource::Store => format!(
+                "from {}",
+                store.as_ref().map_or(String::new(), |s| s.describe())
+            ),
+        };
+        println!("{:<24} {} {}", credential.key(), value, source.dimmed());
+    }
+    Ok(())
+}
+
+pub fn clear_credentials(credential: Option<Credential>) -> Result<()> {
+    let store = credentials::default_store()?;
+    let selected = credential.map_or(Credential::ALL.to_vec(), |c| vec![c]);
+    for credential in selected {
+        let key = credential.store_key(profile::active());
+        if store.delete(&key)? {
+            println!("🗑️  Removed {} from {}", key, store.describe());
+        } else {
+            println!("{} was not stored", key);
+        }
+    }
+    Ok(())
+}
+
+pub fn export_route_bundle(path: &Path, include_results: bool) -> Result<()> {
+    let db = Database::new()?;
+    let bundle = RouteBundle::from_database(&db, include_results)?;
+    bundle.write_to_file(path)?;
+
+    println!(
+        "✅ Exported route bundle to {}",
+        path.display().to_string().green()
+    );
+    println!("  Routes: {}", bundle.routes.len());
+    println!("  Aliases: {}", bundle.aliases.len());
+    println!("  Multi-lap patterns: {}", bundle.multi_lap_patterns.len());
+    println!("  Route segments: {}", bundle.segments.len());
+    if include_results {
+        println!("  Race results: {}", bundle.race_results.len());
+    }
+
+    Ok(())
+}
+
+pub fn import_route_bundle(path: &Path, strategy: ConflictStrategy, dry_run: bool) -> Result<()> {
+    let bundle = RouteBundle::read_from_file(path)?;
+    println!(
+        "📦 Bundle {} (format v{}, exported {})",
+        path.display(),
+        bundle.format_version,
+        bundle.exported_at
+    );
+
+    let db = Database::new()?;
+    let summary = route_bundle::import_bundle(&db, &bundle, strategy, dry_run)?;
+
+    if dry_run {
+        println!("\n{}", "=== DRY RUN MODE ===".yellow());
+    }
+    println!(
+        "\n{:<20} {:>7} {:>8} {:>6} {:>10}",
+        "Section", "Added", "Updated", "Kept", "Unchanged"
+    );
+    println!("{}", "-".repeat(55));
+    let rows: [(&str, &SectionCounts); 5] = [
+        ("Routes", &summary.routes),
+        ("Aliases", &summary.aliases),
+        ("Multi-lap patterns", &summary.multi_lap_patterns),
+        ("Segments (routes)", &summary.segments),
+        ("Race results", &summary.race_results),
+    ];
+    for (section, counts) in rows {
+        println!(
+            "{:<20} {:>7} {:>8} {:>6} {:>10}",
+            section, counts.added, counts.updated, counts.kept, counts.unchanged
+        );
+    }
+
+    if dry_run {
+        println!("\nThis was a dry run - no changes were made.");
+    } else if summary.routes.kept
+        + summary.aliases.kept
+        + summary.multi_lap_patterns.kept
+        + summary.segments.kept
+        > 0
+    {
+        println!(
+            "\n💡 {} Some entries differ and were kept; use --conflict theirs or newest to replace them",
+            "Tip:".yellow()
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn sync_zwift_offline(url: &str, dry_run: bool) -> Result<()> {
+    let client = ZwiftOfflineClient::new(url)?;
+    let snapshot = zwift_offline_sync::fetch_snapshot(&client)
+        .await
+        .with_context(|| format!("Failed to sync from zwift-offline at {}", url))?;
+    println!(
+        "🔄 {}: {} routes, {} start lines, {} events",
+        url,
+        snapshot.routes.len(),
+        snapshot.start_lines.len(),
+        snapshot.events.len()
+    );
+    if snapshot.incomplete_routes > 0 {
+        println!(
+            "   {} routes without distance data were ignored",
+            snapshot.incomplete_routes
+        );
+    }
+
+    let db = Database::new()?;
+    let diff = zwift_offline_sync::diff_routes(&db, &snapshot.routes)?;
+    print_route_diff(&diff);
+
+    let orphaned = snapshot.events_with_unknown_routes();
+    if !orphaned.is_empty() {
+        println!(
+            "\n{} events reference routes the server did not list:",
+            orphaned.len()
+        );
+        for event in orphaned.iter().take(5) {
+            println!("  - {} (route_id: {})", event.name, event.route);
+        }
+    }
+
+    if dry_run {
+        println!("\n{}", "=== DRY RUN MODE ===".yellow());
+        println!("This was a dry run - no changes were made.");
+        return Ok(());
+    }
+
+    let summary = zwift_offline_sync::apply_snapshot(&db, &snapshot)?;
+    println!(
+        "\n✅ Wrote {} routes and {} start lines",
+        summary.routes_written, summary.start_lines
+    );
+    if summary.routes_kept > 0 {
+        println!(
+            "   Kept {} routes with higher-confidence local data",
+            summary.routes_kept
+        );
+    }
+
+    Ok(())
+}
+
+fn print_route_diff(diff: &RouteDiff) {
+    if diff.is_empty() {
+        println!("\nRoutes are up to date ({} unchanged)", diff.unchanged);
+        return;
+    }
+
+    println!("\n{}", "Route changes:".bold());
+    for route in &diff.added {
+        println!(
+            "  {} {} ({}) — {:.2} km, lead-in {:.2} km, {} m, {}",
+            "+".green(),
+            route.name,
+            route.route_id,
+            route.distance_km,
+            route.lead_in_distance_km,
+            route.elevation_m,
+            route.surface
+        );
+    }
+    for change in &diff.changed {
+        let fields: Vec<String> = change
+            .changes
+            .iter()
+            .map(|field| format!("{} {} → {}", field.field, field.local, field.server))
+            .collect();
+        println!(
+            "  {} {} ({}): {}{}",
+            "~".yellow(),
+            change.route.name,
+            change.route.route_id,
+            fields.join(", "),
+            if change.kept {
+                " (kept: local data is more trusted)".dimmed().to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+    for route in &diff.removed {
+        println!(
+            "  {} {} ({}) — no longer listed, kept locally",
+            "-".red(),
+            route.name,
+            route.route_id
+        );
+    }
+    println!(
+        "\nNew: {}  Changed: {}  Removed: {}  Unchanged: {}",
+        diff.added.len(),
+        diff.changed.len(),
+        diff.removed.len(),
+        diff.unchanged
+    );
+}
+
+pub fn add_start_line(
+    route_id: u32,
+    start_location: u32,
+    road: u32,
+    road_time: u32,
+    forward: Option<bool>,
+) -> Result<()> {
+    let db = Database::new()?;
+    let Some(route) = db.get_route(route_id)? else {
+        anyhow::bail!("Route {} is not in the database", route_id);
+    };
+    db.save_start_lines(&[database::StartLine {
+        route_id: route.route_id,
+        start_location,
+        name: route.name.clone(),
+        road,
+        road_time,
+        forward,
+    }])?;
+    println!(
+        "✅ Start location {} of {} is on road {} at {}",
+        start_location,
+        route.name.green(),
+        road,
+        road_time
+    );
+
+    let lead_in = lead_in::lead_in_for_start_location(&db, &route, Some(start_location))?;
+    if start_location != lead_in::DEFAULT_START_LOCATION {
+        let default_start = db.get_start_line(route.route_id, lead_in::DEFAULT_START_LOCATION)?;
+        if default_start.is_none() {
+            println!(
+                "💡 {} Run 'sync zwift-offline' or add start location {} to compare against the default start",
+                "Tip:".yellow(),
+                lead_in::DEFAULT_START_LOCATION
+            );
+        } else if default_start
+            .as_ref()
+            .is_some_and(|start| start.road != road)
+        {
+            println!("   The default start is on another road; the stored lead-in stays in use");
+        } else if forward.is_none() && default_start.is_some_and(|start| start.forward.is_none()) {
+            println!(
+                "💡 {} Add --direction forward or reverse so the pen's position can be turned into a lead-in",
+                "Tip:".yellow()
+            );
+        } else if db.get_road_length(&route.world, road)?.is_none() {
+            println!(
+                "💡 {} Add the road length with 'db road-length \"{}\" {} <km>' to use this start line",
+                "Tip:".yellow(),
+                route.world,
+                road
+            );
+        } else {
+            println!(
+                "   Lead-in: {:.2} km (default start: {:.2} km)",
+                lead_in, route.lead_in_distance_km
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn set_road_length(world: &str, road: u32, length_km: f64) -> Result<()> {
+    if length_km <= 0.0 {
+        anyhow::bail!("Road length must be positive");
+    }
+    let db = Database::new()?;
+    db.set_road_length(world, road, length_km)?;
+    println!(
+        "✅ Road {} in {} is {:.2} km long",
+        road,
+        world.green(),
+        length_km
+    );
+    Ok(())
+}
+
+pub fn show_mapping_audit(limit: usize) -> Result<()> {
+    let db = Database::new()?;
+    let entries = db.get_mapping_audit(limit)?;
+    if entries.is_empty() {
+        println!("No route mappings have been written by tools yet.");
+        return Ok(());
+    }
+
+    println!(
+        "\n{}",
+        "Route Mapping Audit (newest first):".yellow().bold()
+    );
+    println!("{}", "=".repeat(80));
+    for entry in entries {
+        println!(
+            "{} {:<10} {}",
+            entry.applied_at.dimmed(),
+            entry.action.cyan(),
+            entry.details.as_deref().unwrap_or("")
+        );
+        if let Some(event_name) = &entry.event_name {
+            println!("{:<31}from '{}' ({})", "", event_name, entry.source);
+        }
+    }
+    Ok(())
+}
+
+/// The rider's racing score on each race date, for results recorded without one
+///
+/// Prefers the score history as of that day, then the score on the Zwift
+/// profile, then an estimate from recorded results. The estimate is worked
+/// out once, so a bulk import doesn't recompute it for every result.
+struct RaceDayScores<'a> {
+    db: &'a Database,
+    profile_score: Option<u32>,
+    estimate: std::cell::OnceCell<Option<score_estimate::ScoreEstimate>>,
+}
+
+impl<'a> RaceDayScores<'a> {
+    fn new(db: &'a Database, rider: Option<&RiderProfile>) -> Self {
+        Self {
+            db,
+            profile_score: rider.and_then(RiderProfile::racing_score),
+            estimate: std::cell::OnceCell::new(),
+        }
+    }
+
+    fn score_on(&self, race_date: &str) -> Result<u32> {
+        if let Some(score) = self.db.get_score_on(race_date)? {
+            return Ok(score);
+        }
+        if let Some(score) = self.profile_score {
+            return Ok(score);
+        }
+        let estimate = self.estimate.get_or_init(|| {
+            let estimate = score_estimate::estimate_from_db(self.db);
+            if let Some(estimate) = &estimate {
+                println!(
+                    "{} No score recorded for {}; using the {} estimate of {} ({} confidence)",
+                    "ℹ️".blue(),
+                    race_date,
+                    estimate.category,
+                    estimate.zwift_score,
+                    estimate.confidence
+                );
+            }
+            estimate
+        });
+        match estimate {
+            Some(estimate) => Ok(estimate.zwift_score),
+            None => anyhow::bail!(
+                "No racing score known for {}; pass --zwift-score with the score you raced at",
+                race_date
+            ),
+        }
+    }
+
+    /// Fill in the score of a result unless one was given for the whole import
+    fn apply(&self, result: &mut database::RaceResult, zwift_score: Option<u32>) -> Result<()> {
+        result.zwift_score = match zwift_score {
+            Some(score) => score,
+            None => self.score_on(&result.race_date)?,
+        };
+        Ok(())
+    }
+}
+
+pub fn import_fit_files(
+    files: &[PathBuf],
+    route_id: Option<u32>,
+    event_name: Option<&str>,
+    zwift_score: Option<u32>,
+    dry_run: bool,
+) -> Result<()> {
+    let db = Database::new()?;
+    if let Some(route_id) = route_id {
+        if db.get_route(route_id)?.is_none() {
+            anyhow::bail!("Route {} not found in database", route_id);
+        }
+    }
+    let routes = db.get_all_routes()?;
+    // Bulk imports don't ask the Zwift API: its score is today's, not the race day's
+    let scores = RaceDayScores::new(&db, None);
+
+    if dry_run {
+        println!("{}", "=== DRY RUN MODE ===".yellow());
+    }
+
+    let (mut imported, mut skipped) = (0, 0);
+    for path in files {
+        let activity = match fit_import::read_fit_file(path) {
+            Ok(activity) => activity,
+            Err(e) => {
+                println!("❌ {}: {:#}", path.display(), e);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        if !activity.is_virtual_ride() {
+            println!(
+                "⏭️  {}: not a virtual ride ({}), skipped",
+                path.display(),
+                activity
+                    .sub_sport
+                    .as_deref()
+                    .or(activity.sport.as_deref())
+                    .unwrap_or("unknown")
+            );
+            skipped += 1;
+            continue;
+        }
+
+        let matched = fit_import::match_route_by_distance(&routes, activity.distance_km());
+        let Some(route_id) = route_id.or(matched.map(|route| route.route_id)) else {
+            println!(
+                "⏭️  {}: no route matches {:.1} km, use --route-id",
+                path.display(),
+                activity.distance_km()
+            );
+            skipped += 1;
+            continue;
+        };
+
+        let name = event_name.map_or_else(
+            || {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            },
+            str::to_string,
+        );
+        let mut result = activity.to_race_result(route_id, &name, 0);
+        if db.has_race_result(&result)? {
+            println!("⏭️  {}: already recorded", path.display());
+            skipped += 1;
+            continue;
+        }
+        scores.apply(&mut result, zwift_score)?;
+
+        println!(
+            "✅ {}: route {} on {}, {}, {:.1} km, {}m, {}",
+            path.display(),
+            route_id,
+            result.race_date,
+            format_duration(result.actual_minutes),
+            activity.distance_km(),
+            activity.ascent_m.unwrap_or(0),
+            match (activity.avg_power_w, activity.np_power_w) {
+                (Some(avg), Some(np)) => format!("{}W avg / {}W NP", avg, np),
+                (Some(avg), None) => format!("{}W avg", avg),
+                _ => "no power".to_string(),
+            }
+        );
+        if !dry_run {
+            db.add_race_result(&result)?;
+        }
+        imported += 1;
+    }
+
+    println!("\nImported: {}  Skipped: {}", imported, skipped);
+    if dry_run {
+        println!("This was a dry run - no changes were made.");
+    }
+
+    Ok(())
+}
+
+/// Record an imported result with the laps ridden
+///
+/// Laps stay on the result; multi-lap patterns are only written through the
+/// reviewed `--analyze-descriptions --apply` path.
+fn record_matched_result(db: &Database, result: &database::RaceResult, laps: u32) -> Result<()> {
+    db.add_race_result(&database::RaceResult {
+        laps: Some(laps),
+        ..result.clone()
+    })
+}
+
+pub fn import_strava_activities(
+    path: &Path,
+    zwift_score: Option<u32>,
+    include_non_races: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let activities = strava_import::load_activities(path)?;
+    let db = Database::new()?;
+    let routes = db.get_all_routes()?;
+    let scores = RaceDayScores::new(&db, None);
+
+    println!("🚴 {} activities in {}", activities.len(), path.display());
+    if dry_run {
+        println!("{}", "=== DRY RUN MODE ===".yellow());
+    }
+
+    let (mut imported, mut queued, mut skipped) = (0, 0, 0);
+    for activity in &activities {
+        let external_id = activity.id.to_string();
+        if !activity.is_virtual_ride() || !(include_non_races || activity.is_race()) {
+            skipped += 1;
+            continue;
+        }
+        if db.has_review_entry("strava", &external_id)? {
+            skipped += 1;
+            continue;
+        }
+
+        let mut result = activity.to_race_result(0);
+        scores.apply(&mut result, zwift_score)?;
+        let parsed = activity.parsed_name();
+        let profile = activity.profile(&parsed);
+        let candidates = route_matcher::score_routes(&routes, &profile);
+        let (candidates, reason) = match route_matcher::decide(candidates, &profile) {
+            MatchOutcome::Matched(candidate) => {
+                result.route_id = candidate.route_id;
+                if db.has_race_result(&result)? {
+                    skipped += 1;
+                    continue;
+                }
+                println!(
+                    "✅ {} → {} x{} ({:.0}%)",
+                    activity.name,
+                    candidate.route_name.green(),
+                    candidate.laps,
+                    candidate.score * PERCENT_MULTIPLIER
+                );
+                if !dry_run {
+                    record_matched_result(&db, &result, candidate.laps)?;
+                }
+                imported += 1;
+                continue;
+            }
+            MatchOutcome::Ambiguous(candidates) => (candidates, "ambiguous"),
+            MatchOutcome::NoMatch => (Vec::new(), "no matching route"),
+        };
+
+        println!(
+            "❓ {} ({:.1} km): {}",
+            activity.name,
+            activity.distance_km(),
+            reason.yellow()
+        );
+        if !dry_run {
+            let top: Vec<&RouteCandidate> = candidates.iter().take(5).collect();
+            db.queue_for_review(&database::ReviewItem {
+                source: "strava".to_string(),
+                external_id,
+                result,
+                distance_km: activity.distance_km(),
+                elevation_m: activity.total_elevation_gain.map(|m| m.round() as u32),
+                candidates: serde_json::to_string(&top)?,
+                reason: reason.to_string(),
+                ..Default::default()
+            })?;
+        }
+        queued += 1;
+    }
+
+    println!(
+        "\nImported: {}  Queued for review: {}  Skipped: {}",
+        imported, queued, skipped
+    );
+    if dry_run {
+        println!("This was a dry run - no changes were made.");
+    } else if queued > 0 {
+        println!(
+            "\n💡 {} Run 'import review' to pick routes for queued activities",
+            "Tip:".yellow()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn import_zwiftpower_files(
+    files: &[PathBuf],
+    zwift_score: Option<u32>,
+    dry_run: bool,
+) -> Result<()> {
+    let db = Database::new()?;
+    let routes = db.get_all_routes()?;
+    let scores = RaceDayScores::new(&db, None);
+    if dry_run {
+        println!("{}", "=== DRY RUN MODE ===".yellow());
+    }
+
+    let (mut imported, mut queued, mut skipped, mut untimed) = (0, 0, 0, 0);
+    for path in files {
+        let results = zwiftpower_import::load_results(path)?;
+        println!("🏁 {} races in {}", results.len(), path.display());
+
+        for race in &results {
+            let external_id = race.event_id.map_or_else(
+                || format!("{} {}", race.date, race.event_name),
+                |id| id.to_string(),
+            );
+            let Some(mut result) = race.to_race_result(9999, 0) else {
+                untimed += 1;
+                continue;
+            };
+            if db.has_review_entry("zwiftpower", &external_id)? {
+                skipped += 1;
+                continue;
+            }
+            scores.apply(&mut result, zwift_score)?;
+
+            let route = zwiftpower_import::resolve_event_route(
+                &db,
+                &race.event_name,
+                race.route_name.as_deref(),
+            )?;
+            if let Some((route_id, laps, _)) = route {
+                result.route_id = route_id;
+                if db.has_race_result(&result)? {
+                    skipped += 1;
+                    continue;
+                }
+                println!(
+                    "✅ {} {} → route {} x{}, {}{}",
+                    race.date,
+                    race.event_name,
+                    route_id.to_string().green(),
+                    laps,
+                    format_duration(result.actual_minutes),
+                    race.position
+                        .map(|pos| format!(
+                            ", #{} in {}",
+                            pos,
+                            race.category.as_deref().unwrap_or("?")
+                        ))
+                        .unwrap_or_default()
+                );
+                if !dry_run {
+                    record_matched_result(&db, &result, laps)?;
+                }
+                imported += 1;
+                continue;
+            }
+
+            // Unknown event: offer routes that fit the distance, if ZwiftPower gave one
+            let candidates = race.distance_km.map_or_else(Vec::new, |distance_km| {
+                let profile = route_matcher::ActivityProfile {
+                    distance_km,
+                    elevation_m: None,
+                    name: &race.event_name,
+                    stated_route: None,
+                };
+                route_matcher::score_routes(&routes, &profile)
+            });
+            println!(
+                "❓ {} {}: {}",
+                race.date,
+                race.event_name,
+                "no route for this event".yellow()
+            );
+            if !dry_run {
+                let top: Vec<&RouteCandidate> = candidates.iter().take(5).collect();
+                db.queue_for_review(&database::ReviewItem {
+                    source: "zwiftpower".to_string(),
+                    external_id,
+                    result,
+                    distance_km: race.distance_km.unwrap_or(0.0),
+                    candidates: serde_json::to_string(&top)?,
+                    reason: "unknown event".to_string(),
+                    ..Default::default()
+                })?;
+            }
+            queued += 1;
+        }
+    }
+
+    println!(
+        "\nImported: {}  Queued for review: {}  Skipped: {}  No finish time: {}",
+        imported, queued, skipped, untimed
+    );
+    if untimed > 0 {
+        println!(
+            "\n💡 {} Races without a finish time can't calibrate estimates. \
+             Save the results JSON or the profile page itself rather than the extractor output.",
+            "Tip:".yellow()
+        );
+    }
+    if dry_run {
+        println!("This was a dry run - no changes were made.");
+    } else if queued > 0 {
+        println!(
+            "\n💡 {} Run 'import review' to pick routes for queued races",
+            "Tip:".yellow()
+        );
+    }
+
+    Ok(())
+}
+
+fn review_candidates(item: &database::ReviewItem) -> Vec<RouteCandidate> {
+    serde_json::from_str(&item.candidates).unwrap_or_default()
+}
+
+/// Record the rider's finish in a past event from the event's results
+pub async fn import_event_result(
+    event_id: u64,
+    file: Option<&Path>,
+    rider_id: Option<u64>,
+    zwift_score: Option<u32>,
+    dry_run: bool,
+) -> Result<()> {
+    let db = Database::new()?;
+    let config = FullConfig::load().unwrap_or_default();
+    let client = config
+        .secrets
+        .zwift_api_token
+        .as_deref()
+        .map(|token| ZwiftApiClient::new(config.config.zwift_api.base_url(), token))
+        .transpose()?;
+    // The profile is only needed when it has to supply the rider ID or score
+    let rider = match &client {
+        Some(client) if rider_id.is_none() || zwift_score.is_none() => {
+            Some(client.fetch_profile().await?)
+        }
+        _ => None,
+    };
+
+    let event = match (db.get_archived_event(event_id)?, &client) {
+        (Some(event), _) => event,
+        (None, Some(client)) => {
+            let event = client.fetch_event(event_id).await?;
+            db.archive_events(std::slice::from_ref(&event))?;
+            event
+        }
+        (None, None) => anyhow::bail!(
+            "Event {} is not in the archive. Events are archived when a search shows them; \
+             set {} to fetch it from the Zwift API",
+            event_id,
+            Credential::ZwiftApiToken.env_var()
+        ),
+    };
+
+    let rider_id = rider_id
+        .or_else(|| rider.as_ref().map(|rider| rider.id))
+        .or_else(|| {
+            config
+                .secrets
+                .zwiftpower_profile_id
+                .as_deref()
+                .and_then(|id| id.trim().parse().ok())
+        })
+        .context("Unknown Zwift profile ID; pass --rider-id")?;
+
+    let entries = match (file, &client) {
+        (Some(path), _) => event_results::load_entries(path)?,
+        (None, Some(client)) => {
+            let mut entries = Vec::new();
+            for subgroup in &event.event_sub_groups {
+                entries.extend(client.fetch_subgroup_results(subgroup.id).await?);
+            }
+            entries
+        }
+        (None, None) => anyhow::bail!(
+            "No results to read; pass --file with a saved results payload or set {}",
+            Credential::ZwiftApiToken.env_var()
+        ),
+    };
+    let finish = event_results::find_rider_finish(&entries, rider_id).with_context(|| {
+        format!(
+            "Rider {} has no finish in the results of '{}'",
+            rider_id, event.name
+        )
+    })?;
+
+    let race_date = event.event_start.format("%Y-%m-%d").to_string();
+    let zwift_score = match zwift_score {
+        Some(score) => score,
+        None => RaceDayScores::new(&db, rider.as_ref()).score_on(&race_date)?,
+    };
+    let captured = event_results::capture_result(&event, &finish, zwift_score)?;
+    let result = &captured.result;
+
+    println!(
+        "🏁 {} {} → route {} x{}, {}, #{} of {}{}",
+        result.race_date,
+        result.event_name,
+        result.route_id.to_string().green(),
+        captured.laps,
+        format_duration(result.actual_minutes),
+        finish.position,
+        finish.field_size,
+        result
+            .category
+            .as_deref()
+            .map(|cat| format!(" in {}", cat))
+            .unwrap_or_default()
+    );
+    if db.get_route(result.route_id)?.is_none() {
+        println!(
+            "{} Route {} is not in the database yet; estimates won't use this result until it is",
+            "⚠️".yellow(),
+            result.route_id
+        );
+    }
+    if db.has_race_result(result)? {
+        println!("Already recorded.");
+        return Ok(());
+    }
+    if dry_run {
+        println!("This was a dry run - no changes were made.");
+        return Ok(());
+    }
+    record_matched_result(&db, result, captured.laps)?;
+    println!("✅ Result recorded");
+    Ok(())
+}
+
+pub fn show_review_queue() -> Result<()> {
+    let db = Database::new()?;
+    let queue = db.get_review_queue()?;
+    if queue.is_empty() {
+        println!("No imported activities waiting for review.");
+        return Ok(());
+    }
+
+    println!("\n{}", "Activities waiting for review:".yellow().bold());
+    for item in &queue {
+        println!(
+            "\n[{}] {} {} — {:.1} km{}, {} ({} {})",
+            item.id.to_string().yellow(),
+            item.result.race_date,
+            item.result.event_name,
+            item.distance_km,
+            item.elevation_m
+                .map(|m| format!(", {}m", m))
+                .unwrap_or_default(),
+            format_duration(item.result.actual_minutes),
+            item.source,
+            item.reason
+        );
+        for candidate in review_candidates(item) {
+            println!(
+                "    {:<12} {:<30} x{:<3} {:.0}%",
+                candidate.route_id,
+                candidate.route_name,
+                candidate.laps,
+                candidate.score * PERCENT_MULTIPLIER
+            );
+        }
+    }
+
+    println!(
+        "\n💡 {} 'import resolve <id> [--route-id <route>] [--laps <n>]' records a result; \
+         '--dismiss' drops it",
+        "Tip:".yellow()
+    );
+    Ok(())
+}
+
+pub fn resolve_review_item(
+    id: i64,
+    route_id: Option<u32>,
+    laps: Option<u32>,
+    dismiss: bool,
+) -> Result<()> {
+    let db = Database::new()?;
+    let item = db
+        .get_review_queue()?
+        .into_iter()
+        .find(|item| item.id == id)
+        .ok_or_else(|| anyhow::anyhow!("No review entry with ID {}", id))?;
+
+    if dismiss {
+        db.close_review_item(id, "dismissed")?;
+        println!("🗑️  Dismissed '{}'", item.result.event_name);
+        return Ok(());
+    }
+
+    let candidates = review_candidates(&item);
+    let route_id = match route_id.or_else(|| candidates.first().map(|c| c.route_id)) {
+        Some(route_id) => route_id,
+        None => anyhow::bail!("Entry {} has no candidate routes; pass --route-id", id),
+    };
+    let route = db
+        .get_route(route_id)?
+        .ok_or_else(|| anyhow::anyhow!("Route {} not found in database", route_id))?;
+    let laps = laps
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|c| c.route_id == route_id)
+                .map(|c| c.laps)
+        })
+        .unwrap_or(1);
+
+    let mut result = item.result.clone();
+    result.route_id = route_id;
+    record_matched_result(&db, &result, laps)?;
+    db.close_review_item(id, "recorded")?;
+
+    println!(
+        "✅ Recorded '{}' on {} x{} ({})",
+        result.event_name,
+        route.name.green(),
+        laps,
+        format_duration(result.actual_minutes)
+    );
+    Ok(())
+}
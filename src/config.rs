@@ -1,7 +1,11 @@
 //! Configuration management for Zwift Race Finder using TOML
 
+use crate::category::get_category_from_score;
+use crate::credentials::{self, Credential};
+use crate::profile;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -20,6 +24,24 @@ pub struct Config {
     /// Display settings
     #[serde(default)]
     pub display: Display,
+    /// Authenticated Zwift API settings
+    #[serde(default)]
+    pub zwift_api: ZwiftApiConfig,
+    /// Route discovery settings
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    /// Named rider profiles (`[profiles.<name>]`), selected with `--profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// Settings for one named rider profile
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ProfileConfig {
+    /// This rider's settings; unset fields stay unset rather than coming from `[defaults]`
+    pub defaults: Option<Defaults>,
+    /// This rider's ZwiftPower profile ID
+    pub zwiftpower_profile_id: Option<String>,
 }
 
 /// Default rider settings
@@ -64,6 +86,35 @@ pub struct Display {
     pub debug: Option<bool>,
 }
 
+/// Authenticated Zwift API settings; the token itself is a credential
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ZwiftApiConfig {
+    /// API base URL, e.g. a local stand-in server (default: Zwift's public host)
+    pub base_url: Option<String>,
+}
+
+impl ZwiftApiConfig {
+    /// Configured base URL, or Zwift's own
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        self.base_url
+            .as_deref()
+            .unwrap_or(crate::zwift_api::DEFAULT_BASE_URL)
+    }
+}
+
+/// Route discovery settings
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct DiscoveryConfig {
+    /// Directory of saved route pages (`<route-slug>.html`), searched before the web
+    pub pages_dir: Option<PathBuf>,
+    /// Only read saved pages and cached responses, never the network
+    #[serde(default)]
+    pub offline: bool,
+    /// Days a fetched page is reused before fetching it again (default: 7)
+    pub cache_days: Option<u32>,
+}
+
 /// Secrets loaded from environment or secure storage
 #[derive(Debug, Clone, Default)]
 pub struct Secrets {
@@ -71,13 +122,17 @@ pub struct Secrets {
     pub zwiftpower_profile_id: Option<String>,
     /// ZwiftPower session ID
     pub zwiftpower_session_id: Option<String>,
+    /// Zwift API access token
+    pub zwift_api_token: Option<String>,
 }
 
 impl Default for Defaults {
     fn default() -> Self {
         Defaults {
-            zwift_score: Some(195),
-            category: Some("D".to_string()),
+            // Left unset so the score can come from ZwiftPower, the Zwift API
+            // or recorded results before the fixed fallback
+            zwift_score: None,
+            category: None,
             height_m: Some(1.82),  // Jack's height
             weight_kg: Some(86.0), // Typical weight from race data
             ftp_watts: None,       // Will be set via config or calculated
@@ -107,12 +162,24 @@ impl Default for Display {
 impl Config {
     /// Load configuration from files and environment
     pub fn load() -> Result<Self> {
+        let mut config = Config::load_file()?;
+
+        let profile = profile::active();
+        config.select_profile(profile);
+
+        // Apply environment variable overrides
+        config.apply_env_overrides(profile, |name| std::env::var(name).ok());
+
+        Ok(config)
+    }
+
+    /// Load the config file as written, without selecting a profile or reading the environment
+    pub fn load_file() -> Result<Self> {
         // Priority order for config:
-        // 1. Environment variables (highest priority)
-        // 2. Local config.toml
-        // 3. ~/.config/zwift-race-finder/config.toml
-        // 4. ~/.local/share/zwift-race-finder/config.toml (survives updates)
-        // 5. Default values
+        // 1. Local config.toml
+        // 2. ~/.config/zwift-race-finder/config.toml
+        // 3. ~/.local/share/zwift-race-finder/config.toml (survives updates)
+        // 4. Default values
 
         let config_paths = vec![
             PathBuf::from("config.toml"),
@@ -142,60 +209,97 @@ impl Config {
             }
         }
 
-        // Apply environment variable overrides
-        config.apply_env_overrides();
-
         Ok(config)
     }
 
+    /// A profile's own rider settings as written: `[defaults]` for the default profile
+    ///
+    /// Only meaningful on a config from [`Config::load_file`], before a
+    /// profile is selected.
+    pub fn profile_defaults(&self, name: &str) -> Option<&Defaults> {
+        if profile::is_default(name) {
+            return Some(&self.defaults);
+        }
+        self.profiles.get(name).and_then(|p| p.defaults.as_ref())
+    }
+
+    /// Use a named profile's rider settings in place of `[defaults]`
+    ///
+    /// `[defaults]` and the built-in defaults describe the default profile's
+    /// rider, so another profile only has what its own section sets. Without
+    /// a category, the category follows the profile's score.
+    pub fn select_profile(&mut self, name: &str) {
+        if profile::is_default(name) {
+            return;
+        }
+        let mut defaults = self
+            .profiles
+            .get(name)
+            .and_then(|p| p.defaults.clone())
+            .unwrap_or(Defaults {
+                zwift_score: None,
+                category: None,
+                height_m: None,
+                weight_kg: None,
+                ftp_watts: None,
+            });
+        if defaults.category.is_none() {
+            defaults.category = defaults
+                .zwift_score
+                .map(|score| get_category_from_score(score).to_string());
+        }
+        self.defaults = defaults;
+    }
+
     /// Apply environment variable overrides to config
-    fn apply_env_overrides(&mut self) {
-        // Override defaults
-        if let Ok(score) = std::env::var("ZWIFT_SCORE") {
-            if let Ok(score) = score.parse::<u32>() {
+    ///
+    /// Rider settings (`ZWIFT_SCORE`, `ZWIFT_CATEGORY`, `ZWIFT_WEIGHT_KG`,
+    /// `ZWIFT_HEIGHT_M`, `ZWIFT_FTP_WATTS`) describe the default profile's
+    /// rider, so a named profile keeps its own; preferences apply to every
+    /// profile. Variables are read through `env`.
+    fn apply_env_overrides(&mut self, profile: &str, env: impl Fn(&str) -> Option<String>) {
+        if profile::is_default(profile) {
+            if let Some(score) = env("ZWIFT_SCORE").and_then(|s| s.parse::<u32>().ok()) {
                 self.defaults.zwift_score = Some(score);
             }
-        }
 
-        if let Ok(cat) = std::env::var("ZWIFT_CATEGORY") {
-            self.defaults.category = Some(cat);
-        }
+            if let Some(cat) = env("ZWIFT_CATEGORY") {
+                self.defaults.category = Some(cat);
+            }
 
-        if let Ok(weight) = std::env::var("ZWIFT_WEIGHT_KG") {
-            if let Ok(weight) = weight.parse::<f32>() {
+            if let Some(weight) = env("ZWIFT_WEIGHT_KG").and_then(|w| w.parse::<f32>().ok()) {
                 self.defaults.weight_kg = Some(weight);
             }
-        }
 
-        if let Ok(height) = std::env::var("ZWIFT_HEIGHT_M") {
-            if let Ok(height) = height.parse::<f32>() {
+            if let Some(height) = env("ZWIFT_HEIGHT_M").and_then(|h| h.parse::<f32>().ok()) {
                 self.defaults.height_m = Some(height);
             }
-        }
 
-        if let Ok(ftp) = std::env::var("ZWIFT_FTP_WATTS") {
-            if let Ok(ftp) = ftp.parse::<u32>() {
+            if let Some(ftp) = env("ZWIFT_FTP_WATTS").and_then(|f| f.parse::<u32>().ok()) {
                 self.defaults.ftp_watts = Some(ftp);
             }
         }
 
         // Override preferences
-        if let Ok(duration) = std::env::var("ZWIFT_DEFAULT_DURATION") {
-            if let Ok(duration) = duration.parse::<u32>() {
-                self.preferences.default_duration = Some(duration);
-            }
+        if let Some(duration) = env("ZWIFT_DEFAULT_DURATION").and_then(|d| d.parse::<u32>().ok()) {
+            self.preferences.default_duration = Some(duration);
         }
 
-        if let Ok(tolerance) = std::env::var("ZWIFT_DEFAULT_TOLERANCE") {
-            if let Ok(tolerance) = tolerance.parse::<u32>() {
-                self.preferences.default_tolerance = Some(tolerance);
-            }
+        if let Some(tolerance) = env("ZWIFT_DEFAULT_TOLERANCE").and_then(|t| t.parse::<u32>().ok())
+        {
+            self.preferences.default_tolerance = Some(tolerance);
         }
 
-        if let Ok(days) = std::env::var("ZWIFT_DEFAULT_DAYS") {
-            if let Ok(days) = days.parse::<u32>() {
-                self.preferences.default_days = Some(days);
-            }
+        if let Some(days) = env("ZWIFT_DEFAULT_DAYS").and_then(|d| d.parse::<u32>().ok()) {
+            self.preferences.default_days = Some(days);
+        }
+
+        if let Some(url) = env("ZWIFT_API_URL") {
+            self.zwift_api.base_url = Some(url);
+        }
+
+        if let Some(dir) = env("ZWIFT_DISCOVERY_PAGES") {
+            self.discovery.pages_dir = Some(PathBuf::from(dir));
         }
     }
 
@@ -249,12 +353,32 @@ impl Config {
 }
 
 impl Secrets {
-    /// Load secrets from environment variables
+    /// Load secrets from environment variables, then the credential store
+    ///
+    /// Environment variables (set via direnv/ak or equivalent) always win. The
+    /// store is only opened when they don't cover everything, and a store that
+    /// isn't available (no keyring, no passphrase) is skipped.
     pub fn load() -> Self {
-        // Load secrets from environment variables (set via direnv/ak or equivalent)
+        let profile = profile::active();
+        let needs_store = !profile::is_default(profile)
+            || Credential::ALL
+                .iter()
+                .any(|credential| std::env::var(credential.env_var()).is_err());
+        let store = if needs_store {
+            credentials::default_store().ok()
+        } else {
+            None
+        };
+        let get = |credential| {
+            credentials::resolve(credential, profile, store.as_deref())
+                .ok()
+                .flatten()
+                .map(|(value, _)| value)
+        };
         Secrets {
-            zwiftpower_profile_id: std::env::var("ZWIFTPOWER_PROFILE_ID").ok(),
-            zwiftpower_session_id: std::env::var("ZWIFTPOWER_SESSION_ID").ok(),
+            zwiftpower_profile_id: get(Credential::ZwiftPowerProfileId),
+            zwiftpower_session_id: get(Credential::ZwiftPowerSessionId),
+            zwift_api_token: get(Credential::ZwiftApiToken),
         }
     }
 }
@@ -279,10 +403,16 @@ impl Default for FullConfig {
 impl FullConfig {
     /// Load full configuration including secrets
     pub fn load() -> Result<Self> {
-        Ok(FullConfig {
-            config: Config::load()?,
-            secrets: Secrets::load(),
-        })
+        let config = Config::load()?;
+        let mut secrets = Secrets::load();
+        // A profile's ZwiftPower ID isn't secret, so it may live in config.toml
+        if secrets.zwiftpower_profile_id.is_none() {
+            secrets.zwiftpower_profile_id = config
+                .profiles
+                .get(profile::active())
+                .and_then(|p| p.zwiftpower_profile_id.clone());
+        }
+        Ok(FullConfig { config, secrets })
     }
 
     // Compatibility methods
@@ -332,3 +462,86 @@ impl FullConfig {
         self.config.defaults.ftp_watts
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "ZWIFT_SCORE" => Some("150".to_string()),
+            "ZWIFT_DEFAULT_DAYS" => Some("3".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_named_profile_wins_over_rider_env_overrides() {
+        let mut config: Config = toml::from_str(
+            "[defaults]
+            zwift_score = 220
+
+            [profiles.alice.defaults]
+            zwift_score = 310",
+        )
+        .unwrap();
+        let mut default_profile = config.clone();
+
+        config.select_profile("alice");
+        config.apply_env_overrides("alice", env);
+        assert_eq!(config.defaults.zwift_score, Some(310));
+        // Preferences are shared by every profile
+        assert_eq!(config.preferences.default_days, Some(3));
+
+        default_profile.apply_env_overrides(profile::DEFAULT_PROFILE, env);
+        assert_eq!(default_profile.defaults.zwift_score, Some(150));
+    }
+
+    #[test]
+    fn test_named_profile_does_not_inherit_built_in_defaults() {
+        let mut config: Config = toml::from_str(
+            "[profiles.alice.defaults]
+            zwift_score = 310
+
+            [profiles.bob]
+            zwiftpower_profile_id = \"424242\"",
+        )
+        .unwrap();
+        let mut bob = config.clone();
+
+        config.select_profile("alice");
+        assert_eq!(config.defaults.zwift_score, Some(310));
+        assert_eq!(config.defaults.category.as_deref(), Some("B"));
+        assert_eq!(config.defaults.weight_kg, None);
+
+        bob.select_profile("bob");
+        assert_eq!(bob.defaults.zwift_score, None);
+        assert_eq!(bob.defaults.category, None);
+    }
+
+    #[test]
+    fn test_profile_defaults_read_each_profiles_own_section() {
+        let mut config: Config = toml::from_str(
+            "[defaults]
+            zwift_score = 220
+
+            [profiles.alice.defaults]
+            zwift_score = 310
+
+            [profiles.bob]
+            zwiftpower_profile_id = \"424242\"",
+        )
+        .unwrap();
+        let score =
+            |config: &Config, name| config.profile_defaults(name).and_then(|d| d.zwift_score);
+
+        assert_eq!(score(&config, profile::DEFAULT_PROFILE), Some(220));
+        assert_eq!(score(&config, "alice"), Some(310));
+        assert_eq!(score(&config, "bob"), None);
+        assert_eq!(score(&config, "carol"), None);
+
+        // With no score in the file, the default profile has none either
+        config.defaults = Defaults::default();
+        assert_eq!(score(&config, profile::DEFAULT_PROFILE), None);
+    }
+}
@@ -0,0 +1,531 @@
+//! Credential storage for ZwiftPower, the Zwift API and other accounts
+//!
+//! Credentials are looked up in the environment first (so direnv/ak setups
+//! keep working), then in a [`CredentialStore`]:
+//! - the OS keyring (Keychain, Credential Manager, the Linux Secret Service)
+//!   when built with the `keyring-storage` feature,
+//! - otherwise an encrypted file in the data directory, unlocked with the
+//!   passphrase in `ZRF_CREDENTIALS_PASSPHRASE`.
+//!
+//! `ZRF_CREDENTIAL_STORE=file` selects the encrypted file even when the
+//! keyring is available, which is how headless machines and tests use it.
+//!
+//! Named rider profiles keep their own credentials under `<profile>/<name>` in
+//! the same store; the environment variables only apply to the default profile.
+
+use crate::profile;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{AeadInPlace, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce, Tag};
+use anyhow::{anyhow, bail, Context, Result};
+use sha2::Sha256;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Service name credentials are stored under
+pub const SERVICE: &str = "zwift-race-finder";
+
+/// Environment variable selecting the store: `keyring` or `file`
+pub const STORE_ENV: &str = "ZRF_CREDENTIAL_STORE";
+/// Environment variable holding the encrypted file's passphrase
+pub const PASSPHRASE_ENV: &str = "ZRF_CREDENTIALS_PASSPHRASE";
+/// Environment variable overriding the encrypted file's location
+pub const FILE_ENV: &str = "ZRF_CREDENTIALS_FILE";
+
+/// A credential the tool knows how to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Credential {
+    ZwiftPowerProfileId,
+    ZwiftPowerSessionId,
+    ZwiftApiToken,
+}
+
+impl Credential {
+    pub const ALL: [Credential; 3] = [
+        Credential::ZwiftPowerProfileId,
+        Credential::ZwiftPowerSessionId,
+        Credential::ZwiftApiToken,
+    ];
+
+    /// Name used on the command line and in the store
+    #[must_use]
+    pub fn key(self) -> &'static str {
+        match self {
+            Credential::ZwiftPowerProfileId => "zwiftpower-profile-id",
+            Credential::ZwiftPowerSessionId => "zwiftpower-session-id",
+            Credential::ZwiftApiToken => "zwift-api-token",
+        }
+    }
+
+    /// Key in the store for `profile`'s copy of this credential
+    #[must_use]
+    pub fn store_key(self, profile: &str) -> String {
+        if profile::is_default(profile) {
+            self.key().to_string()
+        } else {
+            format!("{profile}/{}", self.key())
+        }
+    }
+
+    /// Environment variable that overrides the stored value
+    #[must_use]
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Credential::ZwiftPowerProfileId => "ZWIFTPOWER_PROFILE_ID",
+            Credential::ZwiftPowerSessionId => "ZWIFTPOWER_SESSION_ID",
+            Credential::ZwiftApiToken => "ZWIFT_API_TOKEN",
+        }
+    }
+
+    /// Whether the value should be masked when displayed
+    #[must_use]
+    pub fn is_secret(self) -> bool {
+        !matches!(self, Credential::ZwiftPowerProfileId)
+    }
+}
+
+impl FromStr for Credential {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.to_lowercase().replace('_', "-");
+        Credential::ALL
+            .into_iter()
+            .find(|credential| credential.key() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Credential::ALL.iter().map(|c| c.key()).collect();
+                format!(
+                    "unknown credential '{s}' (expected one of {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Somewhere credentials can be kept between runs
+pub trait CredentialStore {
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&self, key: &str, value: &str) -> Result<()>;
+    /// Remove a credential, returning whether it existed
+    fn delete(&self, key: &str) -> Result<bool>;
+    /// Where the credentials live, for messages
+    fn describe(&self) -> String;
+}
+
+/// Credentials in the operating system's keyring
+#[cfg(feature = "keyring-storage")]
+pub struct KeyringStore;
+
+#[cfg(feature = "keyring-storage")]
+impl CredentialStore for KeyringStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        match keyring::Entry::new(SERVICE, key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        keyring::Entry::new(SERVICE, key)?
+            .set_password(value)
+            .with_context(|| {
+                format!("Failed to write to the OS keyring; set {STORE_ENV}=file to use an encrypted file instead")
+            })?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        match keyring::Entry::new(SERVICE, key)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        "OS keyring".to_string()
+    }
+}
+
+/// Header identifying an encrypted credentials file (format version 1)
+const FILE_MAGIC: &[u8] = b"ZRFCRED1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 200_000;
+
+/// Credentials in an AES-256-GCM encrypted file
+///
+/// The key is derived from a passphrase with PBKDF2-SHA256 and a random salt;
+/// every write uses a fresh salt and nonce. The file is written with owner-only
+/// permissions. It is decrypted once per store; later reads use the cached
+/// credentials.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase: String,
+    cache: RefCell<Option<BTreeMap<String, String>>>,
+}
+
+impl EncryptedFileStore {
+    #[must_use]
+    pub fn new(path: PathBuf, passphrase: String) -> Self {
+        Self {
+            path,
+            passphrase,
+            cache: RefCell::new(None),
+        }
+    }
+
+    /// Default location: `credentials.enc` in the data directory
+    pub fn default_path() -> Result<PathBuf> {
+        if let Ok(path) = std::env::var(FILE_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+        let dir = dirs::data_dir()
+            .ok_or_else(|| anyhow!("Could not determine data directory"))?
+            .join("zwift-race-finder");
+        Ok(dir.join("credentials.enc"))
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, String>> {
+        if let Some(credentials) = self.cache.borrow().as_ref() {
+            return Ok(credentials.clone());
+        }
+        let credentials = self.decrypt()?;
+        *self.cache.borrow_mut() = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    fn decrypt(&self) -> Result<BTreeMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let data = fs::read(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let body = data
+            .strip_prefix(FILE_MAGIC)
+            .filter(|body| body.len() >= SALT_LEN + NONCE_LEN + TAG_LEN)
+            .ok_or_else(|| anyhow!("{} is not a credentials file", self.path.display()))?;
+        let (salt, rest) = body.split_at(SALT_LEN);
+        let (nonce, rest) = rest.split_at(NONCE_LEN);
+        let (tag, ciphertext) = rest.split_at(TAG_LEN);
+
+        let cipher = Aes256Gcm::new(&derive_key(&self.passphrase, salt).into());
+        let mut plaintext = ciphertext.to_vec();
+        cipher
+            .decrypt_in_place_detached(
+                Nonce::from_slice(nonce),
+                FILE_MAGIC,
+                &mut plaintext,
+                Tag::from_slice(tag),
+            )
+            .map_err(|_| anyhow!("Wrong passphrase or corrupted credentials file"))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn save(&self, credentials: &BTreeMap<String, String>) -> Result<()> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let cipher = Aes256Gcm::new(&derive_key(&self.passphrase, &salt).into());
+
+        let mut ciphertext = serde_json::to_vec(credentials)?;
+        let tag = cipher
+            .encrypt_in_place_detached(Nonce::from_slice(&nonce), FILE_MAGIC, &mut ciphertext)
+            .map_err(|_| anyhow!("Failed to encrypt credentials"))?;
+
+        let mut data = FILE_MAGIC.to_vec();
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&tag);
+        data.extend_from_slice(&ciphertext);
+        write_private(&self.path, &data)?;
+        *self.cache.borrow_mut() = Some(credentials.clone());
+        Ok(())
+    }
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.load()?.remove(key))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        let mut credentials = self.load()?;
+        credentials.insert(key.to_string(), value.to_string());
+        self.save(&credentials)
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        let mut credentials = self.load()?;
+        let existed = credentials.remove(key).is_some();
+        if existed {
+            self.save(&credentials)?;
+        }
+        Ok(existed)
+    }
+
+    fn describe(&self) -> String {
+        format!("encrypted file {}", self.path.display())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key);
+    key
+}
+
+/// Write a file readable only by its owner
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    // The mode above only applies to new files
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    std::io::Write::write_all(&mut file, data)?;
+    Ok(())
+}
+
+/// The store selected by `ZRF_CREDENTIAL_STORE`, defaulting to the keyring when available
+pub fn default_store() -> Result<Box<dyn CredentialStore>> {
+    let choice = std::env::var(STORE_ENV).ok();
+    match choice.as_deref() {
+        Some("file") => file_store(),
+        Some("keyring") => keyring_store(),
+        Some(other) => bail!("Unknown {STORE_ENV} '{other}' (expected keyring or file)"),
+        None if cfg!(feature = "keyring-storage") => keyring_store(),
+        None => file_store(),
+    }
+}
+
+#[cfg(feature = "keyring-storage")]
+fn keyring_store() -> Result<Box<dyn CredentialStore>> {
+    Ok(Box::new(KeyringStore))
+}
+
+#[cfg(not(feature = "keyring-storage"))]
+fn keyring_store() -> Result<Box<dyn CredentialStore>> {
+    bail!("This build has no keyring support; rebuild with --features keyring-storage")
+}
+
+fn file_store() -> Result<Box<dyn CredentialStore>> {
+    let passphrase = std::env::var(PASSPHRASE_ENV)
+        .ok()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow!("Set {PASSPHRASE_ENV} to use the encrypted credentials file"))?;
+    Ok(Box::new(EncryptedFileStore::new(
+        EncryptedFileStore::default_path()?,
+        passphrase,
+    )))
+}
+
+/// Where a credential's value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialSource {
+    Environment,
+    Store,
+}
+
+/// Look `profile`'s credential up in the environment, then in `store`
+///
+/// Only the default profile reads the environment, so a shell set up for one
+/// rider doesn't leak into another rider's profile.
+pub fn resolve(
+    credential: Credential,
+    profile: &str,
+    store: Option<&dyn CredentialStore>,
+) -> Result<Option<(String, CredentialSource)>> {
+    resolve_with_env(credential, profile, store, |name| std::env::var(name).ok())
+}
+
+/// [`resolve`] with environment variables read through `env`
+pub fn resolve_with_env(
+    credential: Credential,
+    profile: &str,
+    store: Option<&dyn CredentialStore>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Option<(String, CredentialSource)>> {
+    if profile::is_default(profile) {
+        if let Some(value) = env(credential.env_var()).filter(|v| !v.is_empty()) {
+            return Ok(Some((value, CredentialSource::Environment)));
+        }
+    }
+    let Some(store) = store else {
+        return Ok(None);
+    };
+    Ok(store
+        .get(&credential.store_key(profile))?
+        .map(|value| (value, CredentialSource::Store)))
+}
+
+/// Value with all but the last four characters hidden
+#[must_use]
+pub fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    let visible: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", "*".repeat(chars.len() - 4), visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let store = EncryptedFileStore::new(path.clone(), "correct horse".to_string());
+
+        assert_eq!(store.get("zwiftpower-session-id").unwrap(), None);
+        store.set("zwiftpower-session-id", "abc123session").unwrap();
+        store.set("zwiftpower-profile-id", "1106548").unwrap();
+        assert_eq!(
+            store.get("zwiftpower-session-id").unwrap().as_deref(),
+            Some("abc123session")
+        );
+
+        // Nothing readable on disk
+        let raw = fs::read(&path).unwrap();
+        assert!(!raw.windows(13).any(|w| w == b"abc123session"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+
+        let wrong = EncryptedFileStore::new(path.clone(), "wrong".to_string());
+        assert!(wrong.get("zwiftpower-session-id").is_err());
+
+        assert!(store.delete("zwiftpower-session-id").unwrap());
+        assert!(!store.delete("zwiftpower-session-id").unwrap());
+        assert_eq!(store.get("zwiftpower-session-id").unwrap(), None);
+        assert_eq!(
+            store.get("zwiftpower-profile-id").unwrap().as_deref(),
+            Some("1106548")
+        );
+    }
+
+    #[test]
+    fn test_encrypted_file_is_decrypted_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        EncryptedFileStore::new(path.clone(), "pass".to_string())
+            .set("zwift-api-token", "token")
+            .unwrap();
+
+        let store = EncryptedFileStore::new(path.clone(), "pass".to_string());
+        assert_eq!(
+            store.get("zwift-api-token").unwrap().as_deref(),
+            Some("token")
+        );
+        // Later reads come from the cache, not the file
+        fs::write(&path, b"garbage").unwrap();
+        assert_eq!(
+            store.get("zwift-api-token").unwrap().as_deref(),
+            Some("token")
+        );
+        assert!(EncryptedFileStore::new(path, "pass".to_string())
+            .get("zwift-api-token")
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_tightens_existing_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        fs::write(&path, b"old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, b"new").unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+
+    #[test]
+    fn test_environment_overrides_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedFileStore::new(dir.path().join("c.enc"), "pass".to_string());
+        store.set("zwiftpower-session-id", "from-store").unwrap();
+        store
+            .set("alice/zwiftpower-session-id", "alice-session")
+            .unwrap();
+
+        let no_env = |_: &str| None;
+        let resolved = resolve_with_env(
+            Credential::ZwiftPowerSessionId,
+            "default",
+            Some(&store),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            Some(("from-store".to_string(), CredentialSource::Store))
+        );
+
+        let env = |name: &str| (name == "ZWIFTPOWER_SESSION_ID").then(|| "from-env".to_string());
+        let resolved = resolve_with_env(
+            Credential::ZwiftPowerSessionId,
+            "default",
+            Some(&store),
+            env,
+        )
+        .unwrap();
+        // A named profile ignores the environment and reads its own key
+        let alice =
+            resolve_with_env(Credential::ZwiftPowerSessionId, "alice", Some(&store), env).unwrap();
+        assert_eq!(
+            resolved,
+            Some(("from-env".to_string(), CredentialSource::Environment))
+        );
+        assert_eq!(
+            alice,
+            Some(("alice-session".to_string(), CredentialSource::Store))
+        );
+    }
+
+    #[test]
+    fn test_credential_names_and_masking() {
+        assert_eq!(
+            "ZWIFTPOWER_SESSION_ID".parse::<Credential>(),
+            Ok(Credential::ZwiftPowerSessionId)
+        );
+        assert_eq!(
+            "zwift-api-token".parse::<Credential>(),
+            Ok(Credential::ZwiftApiToken)
+        );
+        assert!("password".parse::<Credential>().is_err());
+        assert_eq!(mask("abcdefgh"), "****efgh");
+        assert_eq!(mask("abc"), "***");
+    }
+}
@@ -5,10 +5,20 @@
 use anyhow::Result;
 use colored::Colorize;
 use rusqlite::{params, Connection, OptionalExtension};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::models::ZwiftEvent;
+use crate::profile;
+use crate::route_names::{self, NameMatch};
+
+/// Archived events are kept this many days after their start
+pub const EVENT_ARCHIVE_DAYS: i64 = 30;
+
+/// A multi-lap event pattern as (event_name_pattern, route_id, lap_count, notes)
+pub type MultiLapPattern = (String, u32, u32, Option<String>);
 
 /// Route data including distance, elevation, and lead-in information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RouteData {
     /// Unique route identifier
     #[allow(dead_code)]
@@ -45,7 +55,7 @@ pub struct RouteData {
 }
 
 /// Race result with actual completion time
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RaceResult {
     /// Database ID
     #[allow(dead_code)]
@@ -62,10 +72,121 @@ pub struct RaceResult {
     pub race_date: String,
     /// Optional notes
     pub notes: Option<String>,
+    /// Finishing position within the category
+    pub position: Option<u32>,
+    /// Number of finishers in the category
+    pub field_size: Option<u32>,
+    /// Category actually raced (A, B, C, D, E)
+    pub category: Option<String>,
+    /// Average power in watts
+    pub avg_power_w: Option<u32>,
+    /// Normalized power in watts
+    pub np_power_w: Option<u32>,
+    /// Rider weight at race time in kilograms
+    pub weight_kg: Option<f64>,
+    /// Zwift event ID
+    pub event_id: Option<u64>,
+    /// Where the result came from (manual, fit, strava, zwiftpower, ...)
+    pub source: Option<String>,
+    /// Did not finish; `actual_minutes` is then time ridden, not a race time
+    pub dnf: bool,
+    /// Laps of the route ridden, when the importer knew it
+    pub laps: Option<u32>,
+    /// Profile that raced; `None` records it for the database's rider
+    pub rider: Option<String>,
+}
+
+impl RaceResult {
+    /// Whether the raced category matches the one implied by the racing score
+    ///
+    /// Results without a recorded category are assumed to be in category.
+    /// Category E races are open, so they match a D rider as they do in the
+    /// event listing.
+    #[must_use]
+    pub fn raced_own_category(&self) -> bool {
+        let Some(raced) = self
+            .category
+            .as_deref()
+            .and_then(|c| c.trim().chars().next())
+        else {
+            return true;
+        };
+        let expected = crate::category::get_category_from_score(self.zwift_score);
+        let raced = raced.to_ascii_uppercase();
+        expected.starts_with(raced) || (expected == "D" && raced == 'E')
+    }
+
+    /// Whether this result should be used to calibrate duration estimates
+    ///
+    /// Excludes DNFs and results from a category other than the rider's own,
+    /// whose times say little about the rider's usual race speed.
+    #[must_use]
+    pub fn usable_for_calibration(&self) -> bool {
+        !self.dnf && self.raced_own_category()
+    }
+}
+
+/// Columns selected by `race_result_from_row`, in order
+const RACE_RESULT_COLUMNS: &str =
+    "id, route_id, event_name, actual_minutes, zwift_score, race_date, notes,
+     position, field_size, category, avg_power_w, np_power_w, weight_kg, event_id, source, dnf,
+     rider, laps";
+
+/// Build a `RaceResult` from a row selected with `RACE_RESULT_COLUMNS`
+fn race_result_from_row(row: &rusqlite::Row) -> rusqlite::Result<RaceResult> {
+    // Handle zwift_score as either integer or real
+    let zwift_score = match row.get::<_, u32>(4) {
+        Ok(val) => val,
+        Err(_) => row.get::<_, f64>(4)?.round() as u32,
+    };
+
+    Ok(RaceResult {
+        id: row.get(0)?,
+        route_id: row.get(1)?,
+        event_name: row.get(2)?,
+        actual_minutes: row.get(3)?,
+        zwift_score,
+        race_date: row.get(5)?,
+        notes: row.get(6)?,
+        position: row.get(7)?,
+        field_size: row.get(8)?,
+        category: row.get(9)?,
+        avg_power_w: row.get(10)?,
+        np_power_w: row.get(11)?,
+        weight_kg: row.get(12)?,
+        event_id: row
+            .get::<_, Option<i64>>(13)?
+            .and_then(|id| u64::try_from(id).ok()),
+        source: row.get(14)?,
+        dnf: row.get(15)?,
+        rider: row.get(16)?,
+        laps: row.get(17)?,
+    })
+}
+
+/// An imported activity whose route must be confirmed by hand
+#[derive(Debug, Clone, Default)]
+pub struct ReviewItem {
+    /// Database ID
+    pub id: i64,
+    /// Where the activity came from (e.g. "strava")
+    pub source: String,
+    /// Activity ID at the source, unique per source
+    pub external_id: String,
+    /// Result to record once a route is chosen (`route_id` is not set yet)
+    pub result: RaceResult,
+    /// Ridden distance in kilometers
+    pub distance_km: f64,
+    /// Ridden elevation gain in meters
+    pub elevation_m: Option<u32>,
+    /// Candidate routes as JSON, best first
+    pub candidates: String,
+    /// Why the activity could not be matched automatically
+    pub reason: String,
 }
 
 /// Rider physical stats for physics calculations
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub struct RiderStats {
     /// Height in meters
@@ -78,9 +199,277 @@ pub struct RiderStats {
     pub ftp_watts: Option<u32>,
 }
 
+/// Routes below this confidence are flagged as unreliable in output
+pub const LOW_CONFIDENCE_THRESHOLD: f64 = 0.5;
+
+/// Where a route row's numbers came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteSource {
+    /// Built-in seed data shipped with the binary
+    Seed,
+    /// Game data exported by a zwift-offline server
+    ZwiftOffline,
+    /// Scraped from whatsonzwift.com
+    WhatsOnZwift,
+    /// Scraped from zwiftinsider.com
+    ZwiftInsider,
+    /// Placeholder created by `--record-result` for an unknown route
+    Stub,
+    /// Rows that predate provenance tracking, or were inserted by SQL scripts
+    Unknown,
+}
+
+impl RouteSource {
+    /// Name stored in the `routes.source` column
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteSource::Seed => "seed",
+            RouteSource::ZwiftOffline => "zwift-offline",
+            RouteSource::WhatsOnZwift => "whatsonzwift",
+            RouteSource::ZwiftInsider => "zwiftinsider",
+            RouteSource::Stub => "stub",
+            RouteSource::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a stored source name; anything unrecognised is `Unknown`
+    #[must_use]
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "seed" => RouteSource::Seed,
+            "zwift-offline" => RouteSource::ZwiftOffline,
+            "whatsonzwift" => RouteSource::WhatsOnZwift,
+            "zwiftinsider" => RouteSource::ZwiftInsider,
+            "stub" => RouteSource::Stub,
+            _ => RouteSource::Unknown,
+        }
+    }
+
+    /// Confidence (0.0-1.0) assigned to data from this source
+    ///
+    /// Game data beats scraped community sites, which beat the hard-coded
+    /// seed list. Stubs carry no real data at all.
+    #[must_use]
+    pub fn default_confidence(self) -> f64 {
+        match self {
+            RouteSource::ZwiftOffline => 0.95,
+            RouteSource::WhatsOnZwift => 0.8,
+            RouteSource::ZwiftInsider => 0.7,
+            RouteSource::Seed => 0.6,
+            RouteSource::Unknown => 0.5,
+            RouteSource::Stub => 0.0,
+        }
+    }
+}
+
+impl std::fmt::Display for RouteSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Provenance of a route row: its source, how much we trust it, and when it
+/// was last checked against that source
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteProvenance {
+    pub source: RouteSource,
+    /// Confidence from 0.0 (placeholder) to 1.0 (authoritative)
+    pub confidence: f64,
+    /// When the data was last verified (`YYYY-MM-DD HH:MM:SS`, UTC)
+    pub verified_at: Option<String>,
+}
+
+impl RouteProvenance {
+    /// Provenance for data that was not checked against its source
+    #[must_use]
+    pub fn unverified(source: RouteSource) -> Self {
+        RouteProvenance {
+            source,
+            confidence: source.default_confidence(),
+            verified_at: None,
+        }
+    }
+
+    /// Provenance for data fetched from its source just now
+    #[must_use]
+    pub fn verified_now(source: RouteSource) -> Self {
+        RouteProvenance {
+            verified_at: Some(chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+            ..Self::unverified(source)
+        }
+    }
+
+    /// Whether this data should be flagged as unreliable
+    ///
+    /// Rows of unknown origin are flagged whatever their stored confidence.
+    #[must_use]
+    pub fn is_low_confidence(&self) -> bool {
+        self.source == RouteSource::Unknown || self.confidence < LOW_CONFIDENCE_THRESHOLD
+    }
+}
+
+/// Kind of timed segment on a route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentType {
+    /// King of the Mountain (polka dot jersey) climb
+    Kom,
+    /// Sprint (green jersey) segment
+    Sprint,
+    /// Notable climb without a KOM banner
+    Climb,
+}
+
+impl SegmentType {
+    /// Name stored in the `route_segments.segment_type` column
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SegmentType::Kom => "kom",
+            SegmentType::Sprint => "sprint",
+            SegmentType::Climb => "climb",
+        }
+    }
+
+    /// Parse a segment type from a stored name or a segment label
+    ///
+    /// Accepts the stored names as well as labels such as "KOM", "QOM",
+    /// "Sprint" or "Climb" (case-insensitive).
+    #[must_use]
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.trim().to_lowercase();
+        if label.contains("kom") || label.contains("qom") {
+            Some(SegmentType::Kom)
+        } else if label.contains("sprint") {
+            Some(SegmentType::Sprint)
+        } else if label.contains("climb") {
+            Some(SegmentType::Climb)
+        } else {
+            None
+        }
+    }
+}
+
+/// A climb or sprint segment on a route, positioned within one lap
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteSegment {
+    pub segment_type: SegmentType,
+    pub name: String,
+    /// Distance from the start of the lap (excluding lead-in) in kilometers
+    pub start_km: f64,
+    /// Segment length in kilometers
+    pub length_km: f64,
+    /// Average gradient in percent, if known
+    pub avg_gradient: Option<f64>,
+}
+
+/// Where a route's start line sits on the road network (from zwift-offline)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartLine {
+    pub route_id: u32,
+    /// Event start location (pen); the route's default start is
+    /// [`crate::lead_in::DEFAULT_START_LOCATION`]
+    pub start_location: u32,
+    pub name: String,
+    /// Road the start line is on
+    pub road: u32,
+    /// Position along the road in Zwift road-time units
+    pub road_time: u32,
+    /// Whether the route rides towards increasing road time from here;
+    /// `None` when unknown
+    pub forward: Option<bool>,
+}
+
+/// Racing score and rider details from one profile fetch
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreHistoryEntry {
+    /// When the profile was read (YYYY-MM-DD HH:MM:SS, UTC)
+    pub recorded_at: String,
+    pub zwift_score: u32,
+    pub category: Option<String>,
+    pub weight_kg: Option<f64>,
+    pub ftp_w: Option<u32>,
+    /// Where the score came from (zwiftpower, ...)
+    pub source: String,
+}
+
+/// An unknown route in the discovery queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryQueueEntry {
+    pub route_id: u32,
+    pub event_name: String,
+    /// Times the route has been seen in events; busier routes are tried first
+    pub priority: u32,
+    pub attempts: u32,
+    /// Earliest time of the next attempt (YYYY-MM-DD HH:MM:SS, UTC)
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+    /// `true` once route data has been found
+    pub found: bool,
+}
+
+/// A route mapping written by a tool, as kept in the `mapping_audit` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappingAuditEntry {
+    pub id: i64,
+    /// When the mapping was written (YYYY-MM-DD HH:MM:SS, UTC)
+    pub applied_at: String,
+    /// What wrote it, e.g. `analyze-descriptions`
+    pub source: String,
+    /// `alias`, `multi-lap` or `route`
+    pub action: String,
+    /// Route the mapping is for (the alias ID for aliases)
+    pub route_id: u32,
+    /// Route it points at, for aliases and multi-lap patterns
+    pub target_route_id: Option<u32>,
+    /// Event the mapping was inferred from
+    pub event_name: Option<String>,
+    pub details: Option<String>,
+}
+
+/// Wait after the first failed lookup; it doubles with each further failure
+pub const DISCOVERY_BACKOFF_BASE_MINUTES: i64 = 10;
+
+/// Longest wait between lookups of the same route
+pub const DISCOVERY_BACKOFF_MAX_MINUTES: i64 = 7 * 24 * 60;
+
+/// Minutes to wait before retrying a route that has failed `attempts` times
+#[must_use]
+pub fn discovery_backoff_minutes(attempts: u32) -> i64 {
+    let doublings = attempts.saturating_sub(1).min(20);
+    (DISCOVERY_BACKOFF_BASE_MINUTES << doublings).min(DISCOVERY_BACKOFF_MAX_MINUTES)
+}
+
+fn discovery_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<DiscoveryQueueEntry> {
+    Ok(DiscoveryQueueEntry {
+        route_id: row.get(0)?,
+        event_name: row.get(1)?,
+        priority: row.get(2)?,
+        attempts: row.get(3)?,
+        next_attempt_at: row.get(4)?,
+        last_error: row.get(5)?,
+        found: row.get::<_, String>(6)? == "found",
+    })
+}
+
+fn start_line_from_row(row: &rusqlite::Row) -> rusqlite::Result<StartLine> {
+    Ok(StartLine {
+        route_id: row.get(0)?,
+        start_location: row.get(1)?,
+        name: row.get(2)?,
+        road: row.get(3)?,
+        road_time: row.get(4)?,
+        forward: row.get(5)?,
+    })
+}
+
 /// Database connection and operations
+///
+/// Routes are shared; results, completion, rider stats, score history and
+/// the review queue belong to the rider the connection was opened for.
 pub struct Database {
     conn: Connection,
+    rider: String,
 }
 
 impl Database {
@@ -101,13 +490,57 @@ impl Database {
             e
         })?;
 
-        let db = Database { conn };
+        let db = Database {
+            conn,
+            rider: profile::active().to_string(),
+        };
         db.create_tables()?;
         db.seed_initial_data()?;
 
         Ok(db)
     }
 
+    /// Open (or create) a database at a specific path
+    ///
+    /// Used by tests and tools that work on a database other than the
+    /// user's default `races.db`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        let db = Database {
+            conn,
+            rider: profile::active().to_string(),
+        };
+        db.create_tables()?;
+        db.seed_initial_data()?;
+        Ok(db)
+    }
+
+    /// Use this connection for another rider's data
+    #[must_use]
+    pub fn with_rider(mut self, rider: &str) -> Self {
+        self.rider = rider.to_string();
+        self
+    }
+
+    /// Rider whose results, completion and stats this connection reads and writes
+    #[must_use]
+    pub fn rider(&self) -> &str {
+        &self.rider
+    }
+
+    /// Run `f` in one transaction, or in the caller's if one is already open
+    ///
+    /// Nothing `f` wrote is kept if it fails.
+    pub fn in_transaction<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        if !self.conn.is_autocommit() {
+            return f(self);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let value = f(self)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
     fn create_tables(&self) -> Result<()> {
         // Routes table
         self.conn.execute(
@@ -125,11 +558,17 @@ impl Database {
                 lead_in_distance_meetups_km REAL,
                 lead_in_elevation_meetups_m INTEGER,
                 slug TEXT,
+                source TEXT NOT NULL DEFAULT 'unknown',
+                confidence REAL NOT NULL DEFAULT 0.5,
+                verified_at TIMESTAMP,
                 created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
                 updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
             )",
             [],
         )?;
+        self.migrate_route_provenance()?;
+
+        self.create_route_segments_table()?;
 
         // Race results table for regression testing
         self.conn.execute(
@@ -141,19 +580,49 @@ impl Database {
                 zwift_score INTEGER NOT NULL,
                 race_date TIMESTAMP NOT NULL,
                 notes TEXT,
+                position INTEGER,
+                field_size INTEGER,
+                category TEXT,
+                avg_power_w INTEGER,
+                np_power_w INTEGER,
+                weight_kg REAL,
+                event_id INTEGER,
+                source TEXT,
+                dnf INTEGER NOT NULL DEFAULT 0,
+                rider TEXT NOT NULL DEFAULT 'default',
+                laps INTEGER,
                 created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
                 FOREIGN KEY (route_id) REFERENCES routes(route_id)
             )",
             [],
         )?;
+        self.add_missing_columns(
+            "race_results",
+            &[
+                ("position", "INTEGER"),
+                ("field_size", "INTEGER"),
+                ("category", "TEXT"),
+                ("avg_power_w", "INTEGER"),
+                ("np_power_w", "INTEGER"),
+                ("weight_kg", "REAL"),
+                ("event_id", "INTEGER"),
+                ("source", "TEXT"),
+                ("dnf", "INTEGER NOT NULL DEFAULT 0"),
+                ("rider", "TEXT NOT NULL DEFAULT 'default'"),
+                ("laps", "INTEGER"),
+            ],
+        )?;
 
-        // Route completion tracking
+        // Route completion tracking, per rider
+        self.migrate_route_completion_riders()?;
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS route_completion (
-                route_id INTEGER PRIMARY KEY,
+                rider TEXT NOT NULL DEFAULT 'default',
+                route_id INTEGER NOT NULL,
                 completed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
                 actual_time_minutes INTEGER,
                 notes TEXT,
+                PRIMARY KEY (rider, route_id),
                 FOREIGN KEY (route_id) REFERENCES routes(route_id)
             )",
             [],
@@ -171,31 +640,29 @@ impl Database {
             [],
         )?;
 
-        // Rider stats table for physics calculations
+        // Rider stats table for physics calculations, one row per rider
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS rider_stats (
                 id INTEGER PRIMARY KEY,
                 height_m REAL DEFAULT 1.82,
                 weight_kg REAL,
                 ftp_watts INTEGER,
-                updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+                updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                rider TEXT NOT NULL DEFAULT 'default'
             )",
             [],
         )?;
-
-        // Route discovery attempts table to avoid repeated searches
+        self.add_missing_columns(
+            "rider_stats",
+            &[("rider", "TEXT NOT NULL DEFAULT 'default'")],
+        )?;
+        // Older versions only ever read row 1, so extra rows were never used
         self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS route_discovery_attempts (
-                route_id INTEGER PRIMARY KEY,
-                event_name TEXT NOT NULL,
-                last_attempt TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                found BOOLEAN DEFAULT 0,
-                distance_km REAL,
-                elevation_m INTEGER,
-                world TEXT,
-                surface TEXT,
-                route_name TEXT
-            )",
+            "DELETE FROM rider_stats WHERE id NOT IN (SELECT MIN(id) FROM rider_stats GROUP BY rider)",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_rider_stats_rider ON rider_stats(rider)",
             [],
         )?;
 
@@ -213,6 +680,295 @@ impl Database {
             [],
         )?;
 
+        // Multi-lap events: event name patterns known to race several laps
+        // of a route (previously only created by sql/mappings scripts)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS multi_lap_events (
+                event_name_pattern TEXT PRIMARY KEY,
+                route_id INTEGER NOT NULL,
+                lap_count INTEGER NOT NULL,
+                notes TEXT,
+                FOREIGN KEY (route_id) REFERENCES routes(route_id)
+            )",
+            [],
+        )?;
+
+        self.create_import_review_table()?;
+        self.create_start_lines_table()?;
+        self.create_event_archive_table()?;
+        self.create_discovery_queue_table()?;
+        self.create_mapping_audit_table()?;
+        self.create_reported_events_table()?;
+
+        // Racing score after each successful profile fetch
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS score_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recorded_at TIMESTAMP NOT NULL,
+                zwift_score INTEGER NOT NULL,
+                category TEXT,
+                weight_kg REAL,
+                ftp_w INTEGER,
+                source TEXT NOT NULL,
+                rider TEXT NOT NULL DEFAULT 'default'
+            )",
+            [],
+        )?;
+        self.add_missing_columns(
+            "score_history",
+            &[("rider", "TEXT NOT NULL DEFAULT 'default'")],
+        )?;
+
+        Ok(())
+    }
+
+    /// Climbs and sprints along each route, positioned within a single lap
+    fn create_route_segments_table(&self) -> Result<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS route_segments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                route_id INTEGER NOT NULL,
+                segment_type TEXT NOT NULL,
+                name TEXT NOT NULL,
+                start_km REAL NOT NULL,
+                length_km REAL NOT NULL,
+                avg_gradient REAL,
+                FOREIGN KEY (route_id) REFERENCES routes(route_id)
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Start line positions per route and start location, plus road lengths
+    ///
+    /// Together they give the lead-in from pens other than the route's default start.
+    fn create_start_lines_table(&self) -> Result<()> {
+        self.migrate_start_line_locations()?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS start_lines (
+                route_id INTEGER NOT NULL,
+                start_location INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                road INTEGER NOT NULL,
+                road_time INTEGER NOT NULL,
+                forward INTEGER,
+                PRIMARY KEY (route_id, start_location)
+            )",
+            [],
+        )?;
+        self.add_missing_columns("start_lines", &[("forward", "INTEGER")])?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS road_lengths (
+                world TEXT NOT NULL,
+                road INTEGER NOT NULL,
+                length_km REAL NOT NULL,
+                PRIMARY KEY (world, road)
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Events seen in the events feed, kept so results can be matched to them after they finish
+    ///
+    /// Shared by all riders; `payload` is the event as the API returned it.
+    fn create_event_archive_table(&self) -> Result<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS event_archive (
+                event_id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                event_start TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                archived_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Unknown routes waiting to be looked up on route sites
+    ///
+    /// Failed lookups wait longer each time (`next_attempt_at`, UTC); found
+    /// routes stay with `status = 'found'` so they aren't queued again.
+    fn create_discovery_queue_table(&self) -> Result<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS discovery_queue (
+                route_id INTEGER PRIMARY KEY,
+                event_name TEXT NOT NULL,
+                priority INTEGER NOT NULL DEFAULT 1,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL DEFAULT (datetime('now')),
+                last_attempt_at TEXT,
+                last_error TEXT,
+                status TEXT NOT NULL DEFAULT 'pending'
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Route mappings written by tools rather than by hand, one row per write
+    fn create_mapping_audit_table(&self) -> Result<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS mapping_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                applied_at TEXT NOT NULL DEFAULT (datetime('now')),
+                source TEXT NOT NULL,
+                action TEXT NOT NULL,
+                route_id INTEGER NOT NULL,
+                target_route_id INTEGER,
+                event_name TEXT,
+                details TEXT
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Matching events `--watch` has already announced, per rider
+    fn create_reported_events_table(&self) -> Result<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS reported_events (
+                rider TEXT NOT NULL DEFAULT 'default',
+                event_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                event_start TEXT NOT NULL,
+                reported_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (rider, event_id)
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Imported activities waiting for a route to be chosen
+    ///
+    /// Entries are kept after review (`resolution` set) so a re-import skips them.
+    fn create_import_review_table(&self) -> Result<()> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS import_review_queue (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source TEXT NOT NULL,
+                external_id TEXT NOT NULL,
+                event_name TEXT NOT NULL,
+                race_date TEXT NOT NULL,
+                actual_minutes INTEGER NOT NULL,
+                zwift_score INTEGER NOT NULL,
+                category TEXT,
+                avg_power_w INTEGER,
+                np_power_w INTEGER,
+                distance_km REAL NOT NULL,
+                elevation_m INTEGER,
+                candidates TEXT NOT NULL DEFAULT '[]',
+                reason TEXT NOT NULL,
+                resolution TEXT,
+                queued_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                rider TEXT NOT NULL DEFAULT 'default',
+                UNIQUE (source, external_id)
+            )",
+            [],
+        )?;
+        self.add_missing_columns(
+            "import_review_queue",
+            &[("rider", "TEXT NOT NULL DEFAULT 'default'")],
+        )?;
+        Ok(())
+    }
+
+    /// Add provenance columns to routes tables created before they existed
+    fn migrate_route_provenance(&self) -> Result<()> {
+        self.add_missing_columns(
+            "routes",
+            &[
+                ("source", "TEXT NOT NULL DEFAULT 'unknown'"),
+                ("confidence", "REAL NOT NULL DEFAULT 0.5"),
+                ("verified_at", "TIMESTAMP"),
+            ],
+        )
+    }
+
+    /// Rebuild a route_completion table keyed by route only as one keyed by rider and route
+    ///
+    /// Existing completions belong to the default profile.
+    fn migrate_route_completion_riders(&self) -> Result<()> {
+        let columns = self.table_columns("route_completion")?;
+        if columns.is_empty() || columns.iter().any(|c| c == "rider") {
+            return Ok(());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE route_completion_by_rider (
+                rider TEXT NOT NULL DEFAULT 'default',
+                route_id INTEGER NOT NULL,
+                completed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                actual_time_minutes INTEGER,
+                notes TEXT,
+                PRIMARY KEY (rider, route_id),
+                FOREIGN KEY (route_id) REFERENCES routes(route_id)
+            );
+            INSERT INTO route_completion_by_rider
+                (rider, route_id, completed_at, actual_time_minutes, notes)
+                SELECT 'default', route_id, completed_at, actual_time_minutes, notes
+                FROM route_completion;
+            DROP TABLE route_completion;
+            ALTER TABLE route_completion_by_rider RENAME TO route_completion;",
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Key start lines by start location as well as route
+    ///
+    /// Early versions stored one start line per route; it is the route's
+    /// default start ([`crate::lead_in::DEFAULT_START_LOCATION`]).
+    fn migrate_start_line_locations(&self) -> Result<()> {
+        let columns = self.table_columns("start_lines")?;
+        if columns.is_empty() || columns.iter().any(|c| c == "start_location") {
+            return Ok(());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE start_lines_by_location (
+                route_id INTEGER NOT NULL,
+                start_location INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                road INTEGER NOT NULL,
+                road_time INTEGER NOT NULL,
+                forward INTEGER,
+                PRIMARY KEY (route_id, start_location)
+            );
+            INSERT INTO start_lines_by_location (route_id, start_location, name, road, road_time)
+                SELECT route_id, 1, name, road, road_time FROM start_lines;
+            DROP TABLE start_lines;
+            ALTER TABLE start_lines_by_location RENAME TO start_lines;",
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Column names of a table; empty if the table doesn't exist
+    fn table_columns(&self, table: &str) -> Result<Vec<String>> {
+        let columns = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({table})"))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<_, _>>()?;
+        Ok(columns)
+    }
+
+    /// Add columns to a table created by an older version of this tool
+    fn add_missing_columns(&self, table: &str, columns: &[(&str, &str)]) -> Result<()> {
+        let existing = self.table_columns(table)?;
+
+        for (column, definition) in columns {
+            if !existing.iter().any(|c| c == column) {
+                self.conn.execute(
+                    &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+                    [],
+                )?;
+            }
+        }
         Ok(())
     }
 
@@ -289,18 +1045,39 @@ impl Database {
             (3_395_698_268, 60.0, 543, "R3R 60km", "Various", "road"),
         ];
 
-        for (id, dist, elev, name, world, surface) in routes {
-            self.conn.execute(
-                "INSERT OR IGNORE INTO routes (route_id, distance_km, elevation_m, name, world, surface,
-                                              lead_in_distance_km, lead_in_elevation_m) 
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                params![id, dist, elev, name, world, surface, 0.3, 0],
-            )?;
+        for route in routes {
+            self.insert_seed_route(route)?;
         }
 
         Ok(())
     }
 
+    fn insert_seed_route(
+        &self,
+        (id, dist, elev, name, world, surface): (u32, f64, u32, &str, &str, &str),
+    ) -> Result<()> {
+        let seed = RouteSource::Seed;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO routes (route_id, distance_km, elevation_m, name, world, surface,
+                                          lead_in_distance_km, lead_in_elevation_m,
+                                          source, confidence) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                dist,
+                elev,
+                name,
+                world,
+                surface,
+                0.3,
+                0,
+                seed.as_str(),
+                seed.default_confidence()
+            ],
+        )?;
+        Ok(())
+    }
+
     /// Get route data by ID, falling back to alias lookup
     ///
     /// If the route_id isn't found directly, checks the route_aliases table
@@ -331,7 +1108,7 @@ impl Database {
     }
 
     /// Get route data by direct ID (no alias fallback)
-    fn get_route_direct(&self, route_id: u32) -> Result<Option<RouteData>> {
+    pub(crate) fn get_route_direct(&self, route_id: u32) -> Result<Option<RouteData>> {
         let mut stmt = self.conn.prepare(
             "SELECT route_id, distance_km, elevation_m, name, world, surface,
                     lead_in_distance_km, lead_in_elevation_m,
@@ -363,15 +1140,22 @@ impl Database {
         Ok(route)
     }
 
-    /// Add a new route to the database
-    #[allow(dead_code)]
-    pub fn add_route(&self, route: &RouteData) -> Result<()> {
+    /// Add or replace a route, recording where its data came from
+    ///
+    /// Existing data with a higher confidence than `provenance` is never
+    /// overwritten; returns `false` when the write was skipped for that reason.
+    pub fn add_route(&self, route: &RouteData, provenance: &RouteProvenance) -> Result<bool> {
+        if self.is_higher_confidence(route.route_id, provenance.confidence)? {
+            return Ok(false);
+        }
+
         self.conn.execute(
             "INSERT OR REPLACE INTO routes (route_id, distance_km, elevation_m, name, world, surface,
                                            lead_in_distance_km, lead_in_elevation_m,
                                            lead_in_distance_free_ride_km, lead_in_elevation_free_ride_m,
-                                           lead_in_distance_meetups_km, lead_in_elevation_meetups_m, slug) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                                           lead_in_distance_meetups_km, lead_in_elevation_meetups_m, slug,
+                                           source, confidence, verified_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
             params![
                 route.route_id,
                 route.distance_km,
@@ -385,13 +1169,90 @@ impl Database {
                 route.lead_in_elevation_free_ride_m,
                 route.lead_in_distance_meetups_km,
                 route.lead_in_elevation_meetups_m,
-                route.slug
+                route.slug,
+                provenance.source.as_str(),
+                provenance.confidence,
+                provenance.verified_at
             ],
         )?;
-        Ok(())
+        Ok(true)
+    }
+
+    /// Whether a stored route is backed by data more trusted than `confidence`
+    fn is_higher_confidence(&self, route_id: u32, confidence: f64) -> Result<bool> {
+        let existing: Option<f64> = self
+            .conn
+            .query_row(
+                "SELECT confidence FROM routes WHERE route_id = ?1",
+                [route_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(existing.is_some_and(|existing| existing > confidence))
+    }
+
+    /// Get the provenance of a route, following aliases like `get_route`
+    pub fn get_route_provenance(&self, route_id: u32) -> Result<Option<RouteProvenance>> {
+        let query = |id: u32| {
+            self.conn
+                .query_row(
+                    "SELECT source, confidence, verified_at FROM routes WHERE route_id = ?1",
+                    [id],
+                    |row| {
+                        Ok(RouteProvenance {
+                            source: RouteSource::parse(&row.get::<_, String>(0)?),
+                            confidence: row.get(1)?,
+                            verified_at: row.get(2)?,
+                        })
+                    },
+                )
+                .optional()
+        };
+
+        if let Some(provenance) = query(route_id)? {
+            return Ok(Some(provenance));
+        }
+
+        let canonical_id: Option<u32> = self
+            .conn
+            .query_row(
+                "SELECT canonical_route_id FROM route_aliases WHERE alias_route_id = ?1",
+                [route_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match canonical_id {
+            Some(canon_id) => Ok(query(canon_id)?),
+            None => Ok(None),
+        }
+    }
+
+    /// IDs of routes whose data is low confidence, including aliases of them
+    ///
+    /// Matches [`RouteProvenance::is_low_confidence`] for every ID in the set.
+    pub fn get_low_confidence_route_ids(&self) -> Result<std::collections::HashSet<u32>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT route_id FROM routes WHERE source = ?1 OR confidence < ?2
+             UNION
+             SELECT a.alias_route_id FROM route_aliases a
+             JOIN routes r ON r.route_id = a.canonical_route_id
+             WHERE (r.source = ?1 OR r.confidence < ?2)
+               AND a.alias_route_id NOT IN (SELECT route_id FROM routes)",
+        )?;
+        let ids = stmt
+            .query_map(
+                params![RouteSource::Unknown.as_str(), LOW_CONFIDENCE_THRESHOLD],
+                |row| row.get(0),
+            )?
+            .collect::<rusqlite::Result<std::collections::HashSet<u32>>>()?;
+        Ok(ids)
     }
 
     /// Get route data by name
+    ///
+    /// Falls back to the closest known route name when it is a confident,
+    /// unambiguous match (see [`route_names::best_route_match`]).
     #[allow(dead_code)]
     pub fn get_route_by_name(&self, name: &str) -> Result<Option<RouteData>> {
         let mut stmt = self.conn.prepare(
@@ -424,7 +1285,34 @@ impl Database {
             })
             .optional()?;
 
-        Ok(route)
+        if route.is_some() {
+            return Ok(route);
+        }
+        match route_names::confident_match(self.find_routes_by_name(name)?) {
+            Some(found) => self.get_route(found.route_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Known routes ranked by how closely their names match `name`, best first
+    ///
+    /// Stub routes carry no real name and are left out.
+    pub fn find_routes_by_name(&self, name: &str) -> Result<Vec<NameMatch>> {
+        Ok(route_names::rank_routes(name, &self.get_nameable_routes()?))
+    }
+
+    /// Get the routes name matching can pick from: every route except stubs
+    pub fn get_nameable_routes(&self) -> Result<Vec<RouteData>> {
+        let stubs = self
+            .conn
+            .prepare("SELECT route_id FROM routes WHERE source = 'stub'")?
+            .query_map([], |row| row.get::<_, u32>(0))?
+            .collect::<std::result::Result<std::collections::HashSet<_>, _>>()?;
+        Ok(self
+            .get_all_routes()?
+            .into_iter()
+            .filter(|route| !stubs.contains(&route.route_id))
+            .collect())
     }
 
     /// Record an unknown route for future investigation
@@ -448,52 +1336,57 @@ impl Database {
     /// Add a race result
     pub fn add_race_result(&self, result: &RaceResult) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO race_results (route_id, event_name, actual_minutes, zwift_score, race_date, notes) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO race_results (route_id, event_name, actual_minutes, zwift_score, race_date, notes,
+                                       position, field_size, category, avg_power_w, np_power_w,
+                                       weight_kg, event_id, source, dnf, rider, laps) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
             params![
                 result.route_id,
                 result.event_name,
                 result.actual_minutes,
                 result.zwift_score,
                 result.race_date,
-                result.notes
+                result.notes,
+                result.position,
+                result.field_size,
+                result.category,
+                result.avg_power_w,
+                result.np_power_w,
+                result.weight_kg,
+                result.event_id.and_then(|id| i64::try_from(id).ok()),
+                result.source,
+                result.dnf,
+                result.rider.as_deref().unwrap_or(&self.rider),
+                result.laps
             ],
         )?;
         Ok(())
     }
 
-    /// Get race results for a specific route
-    #[allow(dead_code)]
+    /// Get this rider's race results for a specific route
     pub fn get_race_results_for_route(
         &self,
         route_id: u32,
         zwift_score: u32,
     ) -> Result<Vec<RaceResult>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, route_id, event_name, actual_minutes, zwift_score, race_date, notes 
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RACE_RESULT_COLUMNS} 
              FROM race_results 
-             WHERE route_id = ?1 AND zwift_score BETWEEN ?2 - 10 AND ?2 + 10
-             ORDER BY race_date DESC",
-        )?;
+             WHERE route_id = ?1 AND zwift_score BETWEEN ?2 - 10 AND ?2 + 10 AND rider = ?3
+             ORDER BY race_date DESC"
+        ))?;
 
         let results = stmt
-            .query_map(params![route_id, zwift_score], |row| {
-                Ok(RaceResult {
-                    id: Some(row.get(0)?),
-                    route_id: row.get(1)?,
-                    event_name: row.get(2)?,
-                    actual_minutes: row.get(3)?,
-                    zwift_score: row.get(4)?,
-                    race_date: row.get(5)?,
-                    notes: row.get(6)?,
-                })
-            })?
+            .query_map(
+                params![route_id, zwift_score, self.rider],
+                race_result_from_row,
+            )?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(results)
     }
 
-    /// Get average race time for a route
+    /// Get this rider's average race time for a route
     #[allow(dead_code)]
     pub fn get_average_race_time(&self, route_id: u32, _zwift_score: u32) -> Result<Option<u32>> {
         // Get average from recent results (last 3 months are most reliable)
@@ -503,9 +1396,9 @@ impl Database {
             .query_row(
                 "SELECT AVG(actual_minutes) 
              FROM race_results 
-             WHERE route_id = ?1 
+             WHERE route_id = ?1 AND dnf = 0 AND rider = ?2
                AND race_date >= date('now', '-3 months')",
-                [route_id],
+                params![route_id, self.rider],
                 |row| row.get(0),
             )
             .optional()?;
@@ -518,8 +1411,8 @@ impl Database {
                 .query_row(
                     "SELECT AVG(actual_minutes) 
                  FROM race_results 
-                 WHERE route_id = ?1",
-                    [route_id],
+                 WHERE route_id = ?1 AND dnf = 0 AND rider = ?2",
+                    params![route_id, self.rider],
                     |row| row.get(0),
                 )
                 .optional()?
@@ -562,41 +1455,522 @@ impl Database {
         Ok(routes)
     }
 
-    /// Get all race results
-    #[cfg(test)]
+    /// Get all of this rider's race results
+    pub fn get_rider_race_results(&self) -> Result<Vec<RaceResult>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RACE_RESULT_COLUMNS}
+             FROM race_results
+             WHERE rider = ?1
+             ORDER BY race_date DESC"
+        ))?;
+
+        let results = stmt
+            .query_map(params![self.rider], race_result_from_row)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        Ok(results)
+    }
+
+    /// Get all race results, for every rider
     pub fn get_all_race_results(&self) -> Result<Vec<RaceResult>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, route_id, event_name, actual_minutes, zwift_score, race_date, notes 
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {RACE_RESULT_COLUMNS} 
              FROM race_results 
-             ORDER BY race_date DESC",
-        )?;
+             ORDER BY race_date DESC"
+        ))?;
 
         let results = stmt
+            .query_map([], race_result_from_row)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        Ok(results)
+    }
+
+    /// Check whether an identical race result is already recorded for the same rider
+    pub fn has_race_result(&self, result: &RaceResult) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM race_results
+             WHERE route_id = ?1 AND event_name = ?2 AND actual_minutes = ?3 AND race_date = ?4
+               AND rider = ?5",
+            params![
+                result.route_id,
+                result.event_name,
+                result.actual_minutes,
+                result.race_date,
+                result.rider.as_deref().unwrap_or(&self.rider)
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Get the last-updated timestamp of a route (SQLite `CURRENT_TIMESTAMP` format)
+    pub fn get_route_updated_at(&self, route_id: u32) -> Result<Option<String>> {
+        let updated_at = self
+            .conn
+            .query_row(
+                "SELECT updated_at FROM routes WHERE route_id = ?1",
+                [route_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(updated_at.flatten())
+    }
+
+    /// Overwrite a route's last-updated timestamp
+    ///
+    /// Imports use this to keep the timestamp of the source data rather than
+    /// the time of the import, so `newest` conflict resolution stays meaningful.
+    pub fn set_route_updated_at(&self, route_id: u32, updated_at: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE routes SET updated_at = ?2 WHERE route_id = ?1",
+            params![route_id, updated_at],
+        )?;
+        Ok(())
+    }
+
+    /// Get the segments of a route ordered by position, following aliases
+    pub fn get_route_segments(&self, route_id: u32) -> Result<Vec<RouteSegment>> {
+        // Segments are stored against the canonical route
+        let route_id = match self.get_route_direct(route_id)? {
+            Some(_) => route_id,
+            None => self
+                .conn
+                .query_row(
+                    "SELECT canonical_route_id FROM route_aliases WHERE alias_route_id = ?1",
+                    [route_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .unwrap_or(route_id),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT segment_type, name, start_km, length_km, avg_gradient
+             FROM route_segments
+             WHERE route_id = ?1
+             ORDER BY start_km",
+        )?;
+
+        let segments = stmt
+            .query_map([route_id], |row| {
+                Ok(RouteSegment {
+                    segment_type: SegmentType::parse(&row.get::<_, String>(0)?)
+                        .unwrap_or(SegmentType::Climb),
+                    name: row.get(1)?,
+                    start_km: row.get(2)?,
+                    length_km: row.get(3)?,
+                    avg_gradient: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(segments)
+    }
+
+    /// Replace all stored segments of a route
+    pub fn replace_route_segments(&self, route_id: u32, segments: &[RouteSegment]) -> Result<()> {
+        self.in_transaction(|db| {
+            db.conn
+                .execute("DELETE FROM route_segments WHERE route_id = ?1", [route_id])?;
+            for segment in segments {
+                db.conn.execute(
+                    "INSERT INTO route_segments (route_id, segment_type, name, start_km, length_km, avg_gradient)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        route_id,
+                        segment.segment_type.as_str(),
+                        segment.name,
+                        segment.start_km,
+                        segment.length_km,
+                        segment.avg_gradient
+                    ],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Get all stored start lines ordered by name and start location
+    pub fn get_start_lines(&self) -> Result<Vec<StartLine>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT route_id, start_location, name, road, road_time, forward
+             FROM start_lines
+             ORDER BY name, start_location",
+        )?;
+        let start_lines = stmt
+            .query_map([], start_line_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(start_lines)
+    }
+
+    /// Get the start line of a route for one start location
+    pub fn get_start_line(&self, route_id: u32, start_location: u32) -> Result<Option<StartLine>> {
+        let start_line = self
+            .conn
+            .query_row(
+                "SELECT route_id, start_location, name, road, road_time, forward
+                 FROM start_lines
+                 WHERE route_id = ?1 AND start_location = ?2",
+                params![route_id, start_location],
+                start_line_from_row,
+            )
+            .optional()?;
+        Ok(start_line)
+    }
+
+    /// Add or replace start lines, leaving other routes and start locations untouched
+    pub fn save_start_lines(&self, start_lines: &[StartLine]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for start_line in start_lines {
+            tx.execute(
+                "INSERT OR REPLACE INTO start_lines
+                     (route_id, start_location, name, road, road_time, forward)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    start_line.route_id,
+                    start_line.start_location,
+                    start_line.name,
+                    start_line.road,
+                    start_line.road_time,
+                    start_line.forward
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get the length of a road in a world, if known
+    pub fn get_road_length(&self, world: &str, road: u32) -> Result<Option<f64>> {
+        let length = self
+            .conn
+            .query_row(
+                "SELECT length_km FROM road_lengths WHERE world = ?1 AND road = ?2",
+                params![world, road],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(length)
+    }
+
+    /// Add or replace the length of a road
+    pub fn set_road_length(&self, world: &str, road: u32, length_km: f64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO road_lengths (world, road, length_km) VALUES (?1, ?2, ?3)",
+            params![world, road, length_km],
+        )?;
+        Ok(())
+    }
+
+    /// Add or refresh events in the archive
+    ///
+    /// Events that started more than [`EVENT_ARCHIVE_DAYS`] ago are dropped.
+    pub fn archive_events(&self, events: &[ZwiftEvent]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for event in events {
+            tx.execute(
+                "INSERT OR REPLACE INTO event_archive (event_id, name, event_start, payload)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    i64::try_from(event.id)?,
+                    event.name,
+                    event.event_start.to_rfc3339(),
+                    serde_json::to_string(event)?
+                ],
+            )?;
+        }
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(EVENT_ARCHIVE_DAYS);
+        tx.execute(
+            "DELETE FROM event_archive WHERE event_start < ?1",
+            params![cutoff.to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get an archived event by its ID
+    pub fn get_archived_event(&self, event_id: u64) -> Result<Option<ZwiftEvent>> {
+        let payload: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT payload FROM event_archive WHERE event_id = ?1",
+                params![i64::try_from(event_id)?],
+                |row| row.get(0),
+            )
+            .optional()?;
+        payload
+            .map(|payload| serde_json::from_str(&payload).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Get the most recent archived events that have already started, newest first
+    pub fn get_recent_archived_events(&self, limit: usize) -> Result<Vec<ZwiftEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT payload FROM event_archive
+             WHERE event_start <= ?1
+             ORDER BY event_start DESC
+             LIMIT ?2",
+        )?;
+        let payloads = stmt
+            .query_map(
+                params![chrono::Utc::now().to_rfc3339(), limit as i64],
+                |row| row.get::<_, String>(0),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        payloads
+            .iter()
+            .map(|payload| serde_json::from_str(payload).map_err(Into::into))
+            .collect()
+    }
+
+    /// Keep the events this rider hasn't been told about yet
+    pub fn unreported_events<'a>(&self, events: &'a [ZwiftEvent]) -> Result<Vec<&'a ZwiftEvent>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM reported_events WHERE rider = ?1 AND event_id = ?2")?;
+        let mut unreported = Vec::new();
+        for event in events {
+            if !stmt.exists(params![self.rider, i64::try_from(event.id)?])? {
+                unreported.push(event);
+            }
+        }
+        Ok(unreported)
+    }
+
+    /// Remember that these events were announced to this rider
+    ///
+    /// Events that started more than [`EVENT_ARCHIVE_DAYS`] ago are forgotten.
+    pub fn mark_events_reported(&self, events: &[&ZwiftEvent]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for event in events {
+            tx.execute(
+                "INSERT OR IGNORE INTO reported_events (rider, event_id, name, event_start)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    self.rider,
+                    i64::try_from(event.id)?,
+                    event.name,
+                    event.event_start.to_rfc3339()
+                ],
+            )?;
+        }
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(EVENT_ARCHIVE_DAYS);
+        tx.execute(
+            "DELETE FROM reported_events WHERE event_start < ?1",
+            params![cutoff.to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Append a racing score reading for this rider
+    pub fn record_score(&self, entry: &ScoreHistoryEntry) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO score_history
+                (recorded_at, zwift_score, category, weight_kg, ftp_w, source, rider)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                entry.recorded_at,
+                entry.zwift_score,
+                entry.category,
+                entry.weight_kg,
+                entry.ftp_w,
+                entry.source,
+                self.rider
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get this rider's racing score as last recorded on or before `date` (YYYY-MM-DD)
+    pub fn get_score_on(&self, date: &str) -> Result<Option<u32>> {
+        let score = self
+            .conn
+            .query_row(
+                "SELECT zwift_score FROM score_history
+                 WHERE rider = ?1 AND substr(recorded_at, 1, 10) <= ?2
+                 ORDER BY recorded_at DESC, id DESC LIMIT 1",
+                params![self.rider, date],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(score)
+    }
+
+    /// Get this rider's most recent `limit` score readings, oldest first
+    pub fn get_score_history(&self, limit: usize) -> Result<Vec<ScoreHistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT recorded_at, zwift_score, category, weight_kg, ftp_w, source
+             FROM (SELECT * FROM score_history WHERE rider = ?2
+                   ORDER BY recorded_at DESC, id DESC LIMIT ?1)
+             ORDER BY recorded_at, id",
+        )?;
+        let entries = stmt
+            .query_map(params![limit as i64, self.rider], |row| {
+                Ok(ScoreHistoryEntry {
+                    recorded_at: row.get(0)?,
+                    zwift_score: row.get(1)?,
+                    category: row.get(2)?,
+                    weight_kg: row.get(3)?,
+                    ftp_w: row.get(4)?,
+                    source: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Get all route aliases as (alias_route_id, canonical_route_id, notes)
+    pub fn get_route_aliases(&self) -> Result<Vec<(u32, u32, Option<String>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT alias_route_id, canonical_route_id, notes
+             FROM route_aliases
+             ORDER BY alias_route_id",
+        )?;
+
+        let aliases = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(aliases)
+    }
+
+    /// Add or replace a route alias
+    pub fn add_route_alias(
+        &self,
+        alias_route_id: u32,
+        canonical_route_id: u32,
+        notes: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO route_aliases (alias_route_id, canonical_route_id, notes)
+             VALUES (?1, ?2, ?3)",
+            params![alias_route_id, canonical_route_id, notes],
+        )?;
+        Ok(())
+    }
+
+    /// Get all multi-lap patterns
+    pub fn get_multi_lap_patterns(&self) -> Result<Vec<MultiLapPattern>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT event_name_pattern, route_id, lap_count, notes
+             FROM multi_lap_events
+             ORDER BY event_name_pattern",
+        )?;
+
+        let patterns = stmt
             .query_map([], |row| {
-                // Handle zwift_score as either integer or real
-                let zwift_score_raw: Result<u32, _> = row.get(4);
-                let zwift_score = match zwift_score_raw {
-                    Ok(val) => val,
-                    Err(_) => {
-                        // Try as f64 and convert
-                        let val: f64 = row.get(4)?;
-                        val.round() as u32
-                    }
-                };
-
-                Ok(RaceResult {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(patterns)
+    }
+
+    /// Add or replace a multi-lap event pattern
+    pub fn add_multi_lap_pattern(
+        &self,
+        event_name_pattern: &str,
+        route_id: u32,
+        lap_count: u32,
+        notes: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO multi_lap_events (event_name_pattern, route_id, lap_count, notes)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![event_name_pattern, route_id, lap_count, notes],
+        )?;
+        Ok(())
+    }
+
+    /// Queue an imported activity of this rider for manual route review (replaces an earlier entry)
+    pub fn queue_for_review(&self, item: &ReviewItem) -> Result<()> {
+        let result = &item.result;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO import_review_queue
+                (source, external_id, event_name, race_date, actual_minutes, zwift_score,
+                 category, avg_power_w, np_power_w, distance_km, elevation_m, candidates, reason,
+                 rider)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                item.source,
+                item.external_id,
+                result.event_name,
+                result.race_date,
+                result.actual_minutes,
+                result.zwift_score,
+                result.category,
+                result.avg_power_w,
+                result.np_power_w,
+                item.distance_km,
+                item.elevation_m,
+                item.candidates,
+                item.reason,
+                self.rider
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get this rider's activities still waiting for review, oldest first
+    pub fn get_review_queue(&self) -> Result<Vec<ReviewItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, source, external_id, event_name, race_date, actual_minutes, zwift_score,
+                    category, avg_power_w, np_power_w, distance_km, elevation_m, candidates, reason
+             FROM import_review_queue
+             WHERE resolution IS NULL AND rider = ?1
+             ORDER BY race_date, id",
+        )?;
+
+        let items = stmt
+            .query_map(params![self.rider], |row| {
+                let source: String = row.get(1)?;
+                Ok(ReviewItem {
                     id: row.get(0)?,
-                    route_id: row.get(1)?,
-                    event_name: row.get(2)?,
-                    actual_minutes: row.get(3)?,
-                    zwift_score,
-                    race_date: row.get(5)?,
-                    notes: row.get(6)?,
+                    external_id: row.get(2)?,
+                    result: RaceResult {
+                        event_name: row.get(3)?,
+                        race_date: row.get(4)?,
+                        actual_minutes: row.get(5)?,
+                        zwift_score: row.get(6)?,
+                        category: row.get(7)?,
+                        avg_power_w: row.get(8)?,
+                        np_power_w: row.get(9)?,
+                        source: Some(source.clone()),
+                        ..Default::default()
+                    },
+                    source,
+                    distance_km: row.get(10)?,
+                    elevation_m: row.get(11)?,
+                    candidates: row.get(12)?,
+                    reason: row.get(13)?,
                 })
             })?
-            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(results)
+        Ok(items)
+    }
+
+    /// Check whether an activity has been queued for review before (pending or not)
+    pub fn has_review_entry(&self, source: &str, external_id: &str) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM import_review_queue WHERE source = ?1 AND external_id = ?2",
+            params![source, external_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Close a pending review entry (e.g. "recorded", "dismissed");
+    /// returns false if it was not pending
+    pub fn close_review_item(&self, id: i64, resolution: &str) -> Result<bool> {
+        let closed = self.conn.execute(
+            "UPDATE import_review_queue SET resolution = ?2
+             WHERE id = ?1 AND resolution IS NULL",
+            params![id, resolution],
+        )?;
+        Ok(closed > 0)
     }
 
     /// Get unknown routes that need mapping
@@ -614,14 +1988,14 @@ impl Database {
         Ok(routes)
     }
 
-    /// Get rider stats from the database
+    /// Get this rider's stats from the database
     #[allow(dead_code)]
     pub fn get_rider_stats(&self) -> Result<Option<RiderStats>> {
         let result = self
             .conn
             .query_row(
-                "SELECT height_m, weight_kg, ftp_watts FROM rider_stats WHERE id = 1",
-                [],
+                "SELECT height_m, weight_kg, ftp_watts FROM rider_stats WHERE rider = ?1",
+                params![self.rider],
                 |row| {
                     Ok(RiderStats {
                         height_m: row.get(0)?,
@@ -635,6 +2009,18 @@ impl Database {
         Ok(result)
     }
 
+    /// Add or replace this rider's stats
+    pub fn save_rider_stats(&self, stats: &RiderStats) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO rider_stats (rider, height_m, weight_kg, ftp_watts, updated_at)
+             VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)
+             ON CONFLICT(rider) DO UPDATE SET
+                height_m = ?2, weight_kg = ?3, ftp_watts = ?4, updated_at = CURRENT_TIMESTAMP",
+            params![self.rider, stats.height_m, stats.weight_kg, stats.ftp_watts],
+        )?;
+        Ok(())
+    }
+
     /// Get lap count for multi-lap events
     #[allow(dead_code)]
     pub fn get_multi_lap_info(&self, event_name: &str) -> Result<Option<u32>> {
@@ -667,37 +2053,203 @@ impl Database {
         Ok(result)
     }
 
-    /// Check if we've already tried to discover this route recently
-    pub fn should_attempt_discovery(&self, route_id: u32) -> Result<bool> {
-        let result: Option<i64> = self
+    /// Find the route an event runs on from earlier results or multi-lap patterns
+    ///
+    /// Returns `(route_id, lap_count)`. Results still on the placeholder route
+    /// 9999 are ignored; the most frequently recorded route wins, with the
+    /// laps last recorded for it.
+    pub fn find_route_for_event(&self, event_name: &str) -> Result<Option<(u32, u32)>> {
+        let recorded: Option<(u32, Option<u32>)> = self
             .conn
             .query_row(
-                "SELECT COUNT(*) FROM route_discovery_attempts 
-             WHERE route_id = ?1 
-             AND datetime(last_attempt) > datetime('now', '-10 minutes')",
+                "SELECT route_id,
+                        (SELECT laps FROM race_results r
+                         WHERE r.event_name = ?1 AND r.route_id = race_results.route_id
+                           AND r.laps IS NOT NULL
+                         ORDER BY r.race_date DESC LIMIT 1)
+                 FROM race_results
+                 WHERE event_name = ?1 AND route_id != 9999
+                 GROUP BY route_id
+                 ORDER BY COUNT(*) DESC
+                 LIMIT 1",
+                params![event_name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if let Some((route_id, laps)) = recorded {
+            let laps = match laps {
+                Some(laps) => laps,
+                None => self.get_multi_lap_info(event_name)?.unwrap_or(1),
+            };
+            return Ok(Some((route_id, laps)));
+        }
+
+        // Longest pattern first so "3R Volcano Flat Race - 3 Laps" beats "3R Volcano"
+        let pattern = self
+            .conn
+            .query_row(
+                "SELECT route_id, lap_count FROM multi_lap_events
+                 WHERE ?1 LIKE '%' || event_name_pattern || '%'
+                 ORDER BY LENGTH(event_name_pattern) DESC
+                 LIMIT 1",
+                params![event_name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(pattern)
+    }
+
+    /// Queue unknown routes that aren't queued yet and refresh queued routes' priority
+    ///
+    /// Routes that already have real data (anything but a stub) or are aliases
+    /// of a known route are left out. Returns how many routes were newly queued.
+    pub fn sync_discovery_queue(&self) -> Result<usize> {
+        let before: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM discovery_queue", [], |row| row.get(0))?;
+        self.conn.execute(
+            "INSERT INTO discovery_queue (route_id, event_name, priority)
+             SELECT route_id, event_name, times_seen FROM unknown_routes
+             WHERE route_id NOT IN (SELECT route_id FROM routes WHERE source != 'stub')
+               AND route_id NOT IN (SELECT alias_route_id FROM route_aliases)
+             ON CONFLICT(route_id) DO UPDATE SET
+                event_name = excluded.event_name,
+                priority = excluded.priority",
+            [],
+        )?;
+        let after: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM discovery_queue", [], |row| row.get(0))?;
+        Ok(usize::try_from(after - before).unwrap_or(0))
+    }
+
+    /// Get up to `limit` queued routes due for a lookup, busiest first
+    pub fn get_due_discoveries(&self, limit: usize) -> Result<Vec<DiscoveryQueueEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT route_id, event_name, priority, attempts, next_attempt_at, last_error, status
+             FROM discovery_queue
+             WHERE status = 'pending' AND next_attempt_at <= datetime('now')
+             ORDER BY priority DESC, attempts, route_id
+             LIMIT ?1",
+        )?;
+        let entries = stmt
+            .query_map(
+                params![i64::try_from(limit).unwrap_or(i64::MAX)],
+                discovery_entry_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Get every queued route, busiest first
+    pub fn get_discovery_queue(&self) -> Result<Vec<DiscoveryQueueEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT route_id, event_name, priority, attempts, next_attempt_at, last_error, status
+             FROM discovery_queue
+             ORDER BY priority DESC, route_id",
+        )?;
+        let entries = stmt
+            .query_map([], discovery_entry_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Record a failed lookup and push the next one back
+    ///
+    /// Returns when the route is next due (YYYY-MM-DD HH:MM:SS, UTC). Fails
+    /// if the route isn't in the queue.
+    pub fn record_discovery_failure(&self, route_id: u32, error: &str) -> Result<String> {
+        let attempts: u32 = self
+            .conn
+            .query_row(
+                "SELECT attempts FROM discovery_queue WHERE route_id = ?1",
                 params![route_id],
                 |row| row.get(0),
             )
-            .optional()?;
+            .optional()?
+            .unwrap_or(0)
+            + 1;
+        let now = chrono::Utc::now();
+        let backoff = chrono::Duration::minutes(discovery_backoff_minutes(attempts));
+        let next_attempt_at = (now + backoff).format("%Y-%m-%d %H:%M:%S").to_string();
+        let updated = self.conn.execute(
+            "UPDATE discovery_queue
+             SET attempts = ?2, last_attempt_at = ?3, next_attempt_at = ?4, last_error = ?5
+             WHERE route_id = ?1",
+            params![
+                route_id,
+                attempts,
+                now.format("%Y-%m-%d %H:%M:%S").to_string(),
+                next_attempt_at,
+                error
+            ],
+        )?;
+        if updated == 0 {
+            anyhow::bail!("Route {} is not in the discovery queue", route_id);
+        }
+        Ok(next_attempt_at)
+    }
 
-        // If no recent attempt found, we should try
-        Ok(result.unwrap_or(0) == 0)
+    /// Record a successful lookup; the route leaves the queue
+    pub fn record_discovery_success(&self, route_id: u32) -> Result<()> {
+        self.conn.execute(
+            "UPDATE discovery_queue
+             SET attempts = attempts + 1, last_attempt_at = datetime('now'),
+                 last_error = NULL, status = 'found'
+             WHERE route_id = ?1",
+            params![route_id],
+        )?;
+        Ok(())
     }
 
-    /// Record a discovery attempt
-    pub fn record_discovery_attempt(&self, route_id: u32, event_name: &str) -> Result<()> {
+    /// Record a route mapping written by a tool
+    pub fn record_mapping_audit(
+        &self,
+        source: &str,
+        action: &str,
+        route_id: u32,
+        target_route_id: Option<u32>,
+        event_name: Option<&str>,
+        details: &str,
+    ) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO route_discovery_attempts (route_id, event_name, found) 
-             VALUES (?1, ?2, 0)
-             ON CONFLICT(route_id) DO UPDATE SET 
-                last_attempt = CURRENT_TIMESTAMP,
-                event_name = ?2",
-            params![route_id, event_name],
+            "INSERT INTO mapping_audit (source, action, route_id, target_route_id, event_name, details)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![source, action, route_id, target_route_id, event_name, details],
         )?;
         Ok(())
     }
 
+    /// Get the `limit` most recent audited mappings, newest first
+    pub fn get_mapping_audit(&self, limit: usize) -> Result<Vec<MappingAuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, applied_at, source, action, route_id, target_route_id, event_name, details
+             FROM mapping_audit
+             ORDER BY id DESC
+             LIMIT ?1",
+        )?;
+        let entries = stmt
+            .query_map(params![i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+                Ok(MappingAuditEntry {
+                    id: row.get(0)?,
+                    applied_at: row.get(1)?,
+                    source: row.get(2)?,
+                    action: row.get(3)?,
+                    route_id: row.get(4)?,
+                    target_route_id: row.get(5)?,
+                    event_name: row.get(6)?,
+                    details: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
     /// Save discovered route data
+    ///
+    /// Returns `false` if the route already holds higher-confidence data
+    /// than `source` provides, in which case the routes table is left alone.
+    #[allow(clippy::too_many_arguments)]
     pub fn save_discovered_route(
         &self,
         route_id: u32,
@@ -706,48 +2258,45 @@ impl Database {
         world: &str,
         surface: &str,
         route_name: &str,
-    ) -> Result<()> {
-        // Update discovery attempts table
-        self.conn.execute(
-            "UPDATE route_discovery_attempts 
-             SET found = 1, distance_km = ?2, elevation_m = ?3, 
-                 world = ?4, surface = ?5, route_name = ?6
-             WHERE route_id = ?1",
-            params![
-                route_id,
-                distance_km,
-                elevation_m,
-                world,
-                surface,
-                route_name
-            ],
-        )?;
+        source: RouteSource,
+    ) -> Result<bool> {
+        let provenance = RouteProvenance::verified_now(source);
+        if self.is_higher_confidence(route_id, provenance.confidence)? {
+            return Ok(false);
+        }
 
         // Insert into routes table
         self.conn.execute(
-            "INSERT INTO routes (route_id, distance_km, elevation_m, name, world, surface) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "INSERT INTO routes (route_id, distance_km, elevation_m, name, world, surface,
+                                 source, confidence, verified_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
              ON CONFLICT(route_id) DO UPDATE SET
                 distance_km = ?2,
                 elevation_m = ?3,
                 name = ?4,
                 world = ?5,
-                surface = ?6",
+                surface = ?6,
+                source = ?7,
+                confidence = ?8,
+                verified_at = ?9",
             params![
                 route_id,
                 distance_km,
                 elevation_m,
                 route_name,
                 world,
-                surface
+                surface,
+                provenance.source.as_str(),
+                provenance.confidence,
+                provenance.verified_at
             ],
         )?;
 
-        Ok(())
+        Ok(true)
     }
 
     // Route completion tracking methods
-    /// Mark a route as completed
+    /// Mark a route as completed by this rider
     pub fn mark_route_complete(
         &self,
         route_id: u32,
@@ -755,53 +2304,54 @@ impl Database {
         notes: Option<&str>,
     ) -> Result<()> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO route_completion (route_id, actual_time_minutes, notes, completed_at) 
-             VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
-            params![route_id, time_minutes, notes],
+            "INSERT OR REPLACE INTO route_completion
+                (rider, route_id, actual_time_minutes, notes, completed_at) 
+             VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)",
+            params![self.rider, route_id, time_minutes, notes],
         )?;
         Ok(())
     }
 
-    /// Check if a route has been completed
+    /// Check if this rider has completed a route
     #[allow(dead_code)]
     pub fn is_route_completed(&self, route_id: u32) -> Result<bool> {
         let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM route_completion WHERE route_id = ?1",
-            params![route_id],
+            "SELECT COUNT(*) FROM route_completion WHERE route_id = ?1 AND rider = ?2",
+            params![route_id, self.rider],
             |row| row.get(0),
         )?;
         Ok(count > 0)
     }
 
-    /// Get route completion statistics (completed, total)
+    /// Get this rider's route completion statistics (completed, total)
     pub fn get_completion_stats(&self) -> Result<(u32, u32)> {
         let total: u32 = self
             .conn
             .query_row("SELECT COUNT(*) FROM routes", [], |row| row.get(0))?;
 
-        let completed: u32 =
-            self.conn
-                .query_row("SELECT COUNT(*) FROM route_completion", [], |row| {
-                    row.get(0)
-                })?;
+        let completed: u32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM route_completion WHERE rider = ?1",
+            params![self.rider],
+            |row| row.get(0),
+        )?;
 
         Ok((completed, total))
     }
 
-    /// Get route completion statistics by world
+    /// Get this rider's route completion statistics by world
     pub fn get_world_completion_stats(&self) -> Result<Vec<(String, u32, u32)>> {
         let mut stmt = self.conn.prepare(
             "SELECT r.world, 
                     COUNT(DISTINCT r.route_id) as total,
                     COUNT(DISTINCT rc.route_id) as completed
              FROM routes r
-             LEFT JOIN route_completion rc ON r.route_id = rc.route_id
+             LEFT JOIN route_completion rc ON r.route_id = rc.route_id AND rc.rider = ?1
              GROUP BY r.world
              ORDER BY r.world",
         )?;
 
         let stats = stmt
-            .query_map([], |row| {
+            .query_map(params![self.rider], |row| {
                 Ok((
                     row.get::<_, String>(0)?,
                     row.get::<_, u32>(2)?, // completed
@@ -822,12 +2372,7 @@ impl Database {
 
         let routes = stmt
             .query_map([], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                ))
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -840,17 +2385,12 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT route_id, event_name, actual_minutes, zwift_score 
              FROM race_results 
-             WHERE route_id != 9999"
+             WHERE route_id != 9999 AND dnf = 0",
         )?;
 
         let results = stmt
             .query_map([], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                ))
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -895,6 +2435,7 @@ mod tests {
             zwift_score: 195,
             race_date: Utc::now().format("%Y-%m-%d").to_string(),
             notes: Some("Test result".to_string()),
+            ..Default::default()
         };
 
         db.add_race_result(&result).unwrap();
@@ -938,4 +2479,537 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_lower_confidence_never_overwrites_higher() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        let mut route = db.get_route(1_258_415_487).unwrap().unwrap();
+        let seeded = db.get_route_provenance(1_258_415_487).unwrap().unwrap();
+        assert_eq!(seeded.source, RouteSource::Seed);
+
+        // Game data replaces seed data
+        route.distance_km = 14.2;
+        let offline = RouteProvenance::verified_now(RouteSource::ZwiftOffline);
+        assert!(db.add_route(&route, &offline).unwrap());
+
+        // A stub or a scrape must not clobber it
+        route.distance_km = 0.0;
+        assert!(!db
+            .add_route(&route, &RouteProvenance::unverified(RouteSource::Stub))
+            .unwrap());
+        assert!(!db
+            .save_discovered_route(
+                1_258_415_487,
+                13.0,
+                50,
+                "Crit City",
+                "road",
+                "Bell Lap",
+                RouteSource::WhatsOnZwift,
+            )
+            .unwrap());
+
+        let stored = db.get_route(1_258_415_487).unwrap().unwrap();
+        assert_eq!(stored.distance_km, 14.2);
+        let provenance = db.get_route_provenance(1_258_415_487).unwrap().unwrap();
+        assert_eq!(provenance.source, RouteSource::ZwiftOffline);
+        assert!(provenance.verified_at.is_some());
+        assert!(!provenance.is_low_confidence());
+    }
+
+    #[test]
+    fn test_race_result_details_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        let result = RaceResult {
+            route_id: 1_258_415_487,
+            event_name: "Crit City Race".to_string(),
+            actual_minutes: 27,
+            zwift_score: 230,
+            race_date: "2026-05-04".to_string(),
+            position: Some(12),
+            field_size: Some(41),
+            category: Some("C".to_string()),
+            avg_power_w: Some(212),
+            np_power_w: Some(228),
+            weight_kg: Some(72.5),
+            event_id: Some(4_512_345),
+            source: Some("manual".to_string()),
+            ..Default::default()
+        };
+        db.add_race_result(&result).unwrap();
+
+        let stored = db.get_all_race_results().unwrap().remove(0);
+        assert_eq!(stored.position, Some(12));
+        assert_eq!(stored.field_size, Some(41));
+        assert_eq!(stored.category.as_deref(), Some("C"));
+        assert_eq!(stored.np_power_w, Some(228));
+        assert_eq!(stored.weight_kg, Some(72.5));
+        assert_eq!(stored.event_id, Some(4_512_345));
+        assert!(!stored.dnf);
+        assert!(stored.usable_for_calibration());
+    }
+
+    #[test]
+    fn test_usable_for_calibration() {
+        let result = RaceResult {
+            zwift_score: 195,
+            ..Default::default()
+        };
+        assert!(result.usable_for_calibration());
+
+        let dnf = RaceResult {
+            dnf: true,
+            ..result.clone()
+        };
+        assert!(!dnf.usable_for_calibration());
+
+        let raced_up = RaceResult {
+            category: Some("C".to_string()),
+            ..result.clone()
+        };
+        assert!(!raced_up.usable_for_calibration());
+
+        // E races are open to D riders
+        let open = RaceResult {
+            category: Some("e".to_string()),
+            ..result
+        };
+        assert!(open.usable_for_calibration());
+    }
+
+    #[test]
+    fn test_route_segments_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        db.add_route_alias(111, 1_258_415_487, None).unwrap();
+
+        let sprint = RouteSegment {
+            segment_type: SegmentType::Sprint,
+            name: "Crit City Sprint".to_string(),
+            start_km: 1.5,
+            length_km: 0.2,
+            avg_gradient: Some(0.5),
+        };
+        let kom = RouteSegment {
+            segment_type: SegmentType::Kom,
+            name: "Bell Lap KOM".to_string(),
+            start_km: 0.4,
+            length_km: 0.3,
+            avg_gradient: None,
+        };
+        db.replace_route_segments(1_258_415_487, &[sprint.clone(), kom.clone()])
+            .unwrap();
+
+        // Ordered by position and reachable through the alias
+        let segments = db.get_route_segments(111).unwrap();
+        assert_eq!(segments, vec![kom.clone(), sprint]);
+
+        db.replace_route_segments(1_258_415_487, std::slice::from_ref(&kom))
+            .unwrap();
+        assert_eq!(db.get_route_segments(1_258_415_487).unwrap(), vec![kom]);
+    }
+
+    #[test]
+    fn test_find_route_for_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        db.add_multi_lap_pattern("3R Volcano Flat", 2_143_464_829, 3, None)
+            .unwrap();
+        assert_eq!(
+            db.find_route_for_event("3R Volcano Flat Race - 3 Laps")
+                .unwrap(),
+            Some((2_143_464_829, 3))
+        );
+
+        // An earlier, properly mapped result takes precedence over patterns
+        let result = RaceResult {
+            route_id: 1_258_415_487,
+            event_name: "3R Volcano Flat Race - 3 Laps".to_string(),
+            actual_minutes: 60,
+            zwift_score: 200,
+            race_date: "2025-01-01".to_string(),
+            ..Default::default()
+        };
+        db.add_race_result(&result).unwrap();
+        assert_eq!(
+            db.find_route_for_event("3R Volcano Flat Race - 3 Laps")
+                .unwrap(),
+            Some((1_258_415_487, 3))
+        );
+
+        // Laps recorded with a result beat the pattern
+        db.add_race_result(&RaceResult {
+            race_date: "2025-02-01".to_string(),
+            laps: Some(2),
+            ..result
+        })
+        .unwrap();
+        assert_eq!(
+            db.find_route_for_event("3R Volcano Flat Race - 3 Laps")
+                .unwrap(),
+            Some((1_258_415_487, 2))
+        );
+        assert_eq!(db.find_route_for_event("Unknown Race").unwrap(), None);
+    }
+
+    #[test]
+    fn test_score_history_keeps_latest_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        for (day, score) in [(3, 215), (1, 198), (2, 204)] {
+            db.record_score(&ScoreHistoryEntry {
+                recorded_at: format!("2025-06-0{day} 08:00:00"),
+                zwift_score: score,
+                category: Some("C".to_string()),
+                weight_kg: None,
+                ftp_w: None,
+                source: "zwiftpower".to_string(),
+            })
+            .unwrap();
+        }
+
+        let scores: Vec<u32> = db
+            .get_score_history(10)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.zwift_score)
+            .collect();
+        assert_eq!(scores, [198, 204, 215]);
+        let latest = db.get_score_history(2).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].recorded_at, "2025-06-02 08:00:00");
+
+        assert_eq!(db.get_score_on("2025-06-02").unwrap(), Some(204));
+        assert_eq!(db.get_score_on("2025-07-01").unwrap(), Some(215));
+        assert_eq!(db.get_score_on("2025-05-31").unwrap(), None);
+    }
+
+    #[test]
+    fn test_review_queue_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        let mut item = ReviewItem {
+            source: "strava".to_string(),
+            external_id: "14671146801".to_string(),
+            result: RaceResult {
+                event_name: "Evening Race".to_string(),
+                actual_minutes: 62,
+                zwift_score: 210,
+                race_date: "2025-06-02".to_string(),
+                category: Some("D".to_string()),
+                np_power_w: Some(196),
+                ..Default::default()
+            },
+            distance_km: 37.3,
+            elevation_m: Some(138),
+            candidates: r#"[{"route_id":1,"route_name":"Volcano Flat","laps":3,"score":0.7}]"#
+                .to_string(),
+            reason: "ambiguous".to_string(),
+            ..Default::default()
+        };
+        db.queue_for_review(&item).unwrap();
+        // Re-importing the same activity replaces the entry instead of duplicating it
+        item.reason = "no candidate".to_string();
+        db.queue_for_review(&item).unwrap();
+
+        let queue = db.get_review_queue().unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].reason, "no candidate");
+        assert_eq!(queue[0].result.np_power_w, Some(196));
+        assert_eq!(queue[0].result.source.as_deref(), Some("strava"));
+        assert!(db.has_review_entry("strava", "14671146801").unwrap());
+
+        assert!(db.close_review_item(queue[0].id, "dismissed").unwrap());
+        assert!(!db.close_review_item(queue[0].id, "dismissed").unwrap());
+        assert!(db.get_review_queue().unwrap().is_empty());
+        // Closed entries are remembered so a re-import does not queue them again
+        assert!(db.has_review_entry("strava", "14671146801").unwrap());
+    }
+
+    #[test]
+    fn test_provenance_columns_added_to_old_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("races.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE routes (
+                    route_id INTEGER PRIMARY KEY,
+                    distance_km REAL NOT NULL,
+                    elevation_m INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    world TEXT NOT NULL,
+                    surface TEXT NOT NULL DEFAULT 'road',
+                    lead_in_distance_km REAL DEFAULT 0.0,
+                    lead_in_elevation_m INTEGER DEFAULT 0,
+                    lead_in_distance_free_ride_km REAL,
+                    lead_in_elevation_free_ride_m INTEGER,
+                    lead_in_distance_meetups_km REAL,
+                    lead_in_elevation_meetups_m INTEGER,
+                    slug TEXT,
+                    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                );
+                INSERT INTO routes (route_id, distance_km, elevation_m, name, world)
+                VALUES (42, 10.0, 100, 'Legacy Route', 'Watopia');",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let provenance = db.get_route_provenance(42).unwrap().unwrap();
+        assert_eq!(provenance.source, RouteSource::Unknown);
+        assert_eq!(
+            provenance.confidence,
+            RouteSource::Unknown.default_confidence()
+        );
+        assert!(provenance.verified_at.is_none());
+        assert!(provenance.is_low_confidence());
+        assert!(db.get_low_confidence_route_ids().unwrap().contains(&42));
+    }
+
+    #[test]
+    fn test_start_lines_keyed_by_route_only_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("races.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE start_lines (
+                    route_id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    road INTEGER NOT NULL,
+                    road_time INTEGER NOT NULL
+                );
+                INSERT INTO start_lines VALUES (947394567, 'Crit City - Downtown Dolphin', 0, 628605);",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        assert_eq!(
+            db.get_start_lines().unwrap(),
+            vec![StartLine {
+                route_id: 947_394_567,
+                start_location: crate::lead_in::DEFAULT_START_LOCATION,
+                name: "Crit City - Downtown Dolphin".to_string(),
+                road: 0,
+                road_time: 628_605,
+                forward: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_riders_have_separate_results_and_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("races.db");
+        let jack = Database::open(&path).unwrap();
+        let alice = Database::open(&path).unwrap().with_rider("alice");
+
+        jack.mark_route_complete(2_698_009_951, Some(35), None)
+            .unwrap();
+        jack.add_race_result(&RaceResult {
+            route_id: 2_698_009_951,
+            event_name: "Downtown Dolphin Race".to_string(),
+            actual_minutes: 35,
+            zwift_score: 195,
+            race_date: "2025-06-01".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        alice
+            .save_rider_stats(&RiderStats {
+                height_m: 1.65,
+                weight_kg: 58.0,
+                ftp_watts: Some(220),
+            })
+            .unwrap();
+
+        assert!(jack.is_route_completed(2_698_009_951).unwrap());
+        assert!(!alice.is_route_completed(2_698_009_951).unwrap());
+        assert_eq!(alice.get_completion_stats().unwrap().0, 0);
+        assert!(alice
+            .get_race_results_for_route(2_698_009_951, 195)
+            .unwrap()
+            .is_empty());
+        assert!(alice.get_rider_race_results().unwrap().is_empty());
+        assert_eq!(jack.get_rider_race_results().unwrap().len(), 1);
+        assert_eq!(jack.get_rider_stats().unwrap(), None);
+        assert_eq!(alice.get_rider_stats().unwrap().unwrap().weight_kg, 58.0);
+
+        // Shared listings still see every rider
+        let all = alice.get_all_race_results().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].rider.as_deref(), Some("default"));
+    }
+
+    #[test]
+    fn test_route_completion_migrated_to_default_rider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("races.db");
+        drop(Database::open(&path).unwrap());
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "DROP TABLE route_completion;
+                CREATE TABLE route_completion (
+                    route_id INTEGER PRIMARY KEY,
+                    completed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                    actual_time_minutes INTEGER,
+                    notes TEXT
+                );
+                INSERT INTO route_completion (route_id, actual_time_minutes)
+                VALUES (2698009951, 35);",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        assert!(db.is_route_completed(2_698_009_951).unwrap());
+        let alice = db.with_rider("alice");
+        assert!(!alice.is_route_completed(2_698_009_951).unwrap());
+        alice
+            .mark_route_complete(2_698_009_951, Some(40), None)
+            .unwrap();
+        assert!(alice.is_route_completed(2_698_009_951).unwrap());
+    }
+
+    #[test]
+    fn test_event_archive_round_trip_and_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        let event = |id: u64, days_ago: i64| -> ZwiftEvent {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": format!("Race {id}"),
+                "eventStart": (Utc::now() - chrono::Duration::days(days_ago)).to_rfc3339(),
+                "eventType": "RACE",
+                "routeId": 2_698_009_951_u32,
+                "eventSubgroups": [{"id": 7, "name": "D", "laps": 3}]
+            }))
+            .unwrap()
+        };
+
+        db.archive_events(&[event(1, 1), event(2, EVENT_ARCHIVE_DAYS + 1)])
+            .unwrap();
+        let archived = db.get_archived_event(1).unwrap().unwrap();
+        assert_eq!(archived.name, "Race 1");
+        assert_eq!(archived.event_sub_groups[0].laps, Some(3));
+        assert!(db.get_archived_event(2).unwrap().is_none());
+        assert!(db.get_archived_event(3).unwrap().is_none());
+
+        // Only events that have started are offered for recording
+        db.archive_events(&[event(4, 3), event(5, -1)]).unwrap();
+        let recent: Vec<u64> = db
+            .get_recent_archived_events(10)
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(recent, vec![1, 4]);
+    }
+
+    #[test]
+    fn test_reported_events_per_rider() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("races.db");
+        let db = Database::open(&path).unwrap();
+        let event = |id: u64, days_ago: i64| -> ZwiftEvent {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": format!("Race {id}"),
+                "eventStart": (Utc::now() - chrono::Duration::days(days_ago)).to_rfc3339(),
+                "eventType": "RACE"
+            }))
+            .unwrap()
+        };
+        let events = vec![event(1, -1), event(2, -1), event(3, EVENT_ARCHIVE_DAYS + 1)];
+
+        let ids = |events: Vec<&ZwiftEvent>| events.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(db.unreported_events(&events).unwrap()), vec![1, 2, 3]);
+        db.mark_events_reported(&[&events[0], &events[2]]).unwrap();
+        // Event 3 started long ago, so it's forgotten straight away
+        assert_eq!(ids(db.unreported_events(&events).unwrap()), vec![2, 3]);
+
+        // Another rider hasn't been told about anything
+        let other = Database::open(&path).unwrap().with_rider("alice");
+        assert_eq!(other.unreported_events(&events).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_discovery_queue_backoff_and_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        db.record_unknown_route(101, "Quiet Race", "RACE").unwrap();
+        for _ in 0..3 {
+            db.record_unknown_route(202, "Busy Race", "RACE").unwrap();
+        }
+        // Routes with real data aren't queued
+        db.record_unknown_route(1_258_415_487, "Known Race", "RACE")
+            .unwrap();
+        assert!(db.get_route(1_258_415_487).unwrap().is_some());
+
+        assert_eq!(db.sync_discovery_queue().unwrap(), 2);
+        assert_eq!(db.sync_discovery_queue().unwrap(), 0);
+        let due: Vec<u32> = db
+            .get_due_discoveries(10)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.route_id)
+            .collect();
+        assert_eq!(due, [202, 101]);
+
+        // A failure waits out the backoff; a success leaves the queue
+        db.record_discovery_failure(202, "Could not find route information")
+            .unwrap();
+        db.record_discovery_success(101).unwrap();
+        assert!(db.get_due_discoveries(10).unwrap().is_empty());
+
+        let queue = db.get_discovery_queue().unwrap();
+        assert_eq!(queue[0].route_id, 202);
+        assert_eq!(queue[0].priority, 3);
+        assert_eq!(queue[0].attempts, 1);
+        assert_eq!(
+            queue[0].last_error.as_deref(),
+            Some("Could not find route information")
+        );
+        assert!(!queue[0].found);
+        assert!(queue[1].found);
+
+        // Seeing the route again raises its priority but keeps its backoff
+        db.record_unknown_route(202, "Busy Race", "RACE").unwrap();
+        db.sync_discovery_queue().unwrap();
+        let queue = db.get_discovery_queue().unwrap();
+        assert_eq!((queue[0].priority, queue[0].attempts), (4, 1));
+    }
+
+    #[test]
+    fn test_discovery_failure_needs_a_queued_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+
+        assert!(db.record_discovery_failure(303, "Not found").is_err());
+        assert!(db.get_discovery_queue().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_discovery_backoff_doubles_up_to_a_week() {
+        assert_eq!(discovery_backoff_minutes(1), 10);
+        assert_eq!(discovery_backoff_minutes(2), 20);
+        assert_eq!(discovery_backoff_minutes(4), 80);
+        assert_eq!(discovery_backoff_minutes(12), DISCOVERY_BACKOFF_MAX_MINUTES);
+        assert_eq!(
+            discovery_backoff_minutes(500),
+            DISCOVERY_BACKOFF_MAX_MINUTES
+        );
+    }
 }
@@ -0,0 +1,387 @@
+//! Route mappings inferred from event descriptions
+//!
+//! Events on unknown routes often say what they ride: "3 laps of Castle to
+//! Castle". The route named there is matched against known routes by name
+//! (see [`crate::route_names`]); a confident match makes the event's route ID
+//! an alias of the known route, and a lap count above one becomes a multi-lap
+//! pattern for the event name. Routes that match nothing but whose description
+//! gives a distance and elevation become new `routes` rows. Every write is
+//! recorded in the `mapping_audit` table.
+
+use crate::database::{Database, RouteSource};
+use crate::models::ZwiftEvent;
+use crate::parsing::parse_description_data;
+use crate::route_discovery::parse_route_from_description;
+use crate::route_names::confident_match;
+use crate::route_providers::{detect_world, format_world_name};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Name recorded as the source of mappings in the audit table
+pub const AUDIT_SOURCE: &str = "analyze-descriptions";
+
+/// Audit source of aliases proposed by route discovery from event names
+pub const DISCOVERY_AUDIT_SOURCE: &str = "discover";
+
+/// A write to the route tables suggested by an event description
+#[derive(Debug, Clone, PartialEq)]
+pub enum MappingChange {
+    /// The event's route ID is a known route under another ID
+    Alias {
+        alias_route_id: u32,
+        canonical_route_id: u32,
+        route_name: String,
+        /// Name match score (0.0 - 1.0)
+        score: f64,
+    },
+    /// Events with this name ride several laps of a known route
+    MultiLap {
+        event_name_pattern: String,
+        route_id: u32,
+        route_name: String,
+        laps: u32,
+    },
+    /// A route not known under any name, sized from the description (per lap)
+    NewRoute {
+        route_id: u32,
+        name: String,
+        world: String,
+        distance_km: f64,
+        elevation_m: u32,
+    },
+}
+
+impl MappingChange {
+    /// Action name stored in the audit table
+    #[must_use]
+    pub fn action(&self) -> &'static str {
+        match self {
+            MappingChange::Alias { .. } => "alias",
+            MappingChange::MultiLap { .. } => "multi-lap",
+            MappingChange::NewRoute { .. } => "route",
+        }
+    }
+
+    /// Route ID the change is written for
+    #[must_use]
+    pub fn route_id(&self) -> u32 {
+        match self {
+            MappingChange::Alias { alias_route_id, .. } => *alias_route_id,
+            MappingChange::MultiLap { route_id, .. } | MappingChange::NewRoute { route_id, .. } => {
+                *route_id
+            }
+        }
+    }
+}
+
+impl fmt::Display for MappingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MappingChange::Alias {
+                alias_route_id,
+                canonical_route_id,
+                route_name,
+                score,
+            } => write!(
+                f,
+                "alias {alias_route_id} -> {canonical_route_id} ({route_name}, {:.0}% name match)",
+                score * 100.0
+            ),
+            MappingChange::MultiLap {
+                event_name_pattern,
+                route_id,
+                route_name,
+                laps,
+            } => write!(
+                f,
+                "multi-lap '{event_name_pattern}' -> {laps} laps of {route_name} ({route_id})"
+            ),
+            MappingChange::NewRoute {
+                route_id,
+                name,
+                world,
+                distance_km,
+                elevation_m,
+            } => write!(
+                f,
+                "new route {route_id} '{name}' ({world}, {distance_km:.1} km, {elevation_m} m)"
+            ),
+        }
+    }
+}
+
+/// A change together with the event it was inferred from
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedMapping {
+    pub event_name: String,
+    pub change: MappingChange,
+}
+
+/// Work out the mappings the descriptions of `events` support
+///
+/// Only events on unknown routes (or without a route ID) are looked at, and
+/// mappings that already exist or were planned for an earlier event are left
+/// out, so the result can be applied as is.
+///
+/// # Errors
+///
+/// Returns an error if the database can't be read.
+pub fn plan_mappings(db: &Database, events: &[ZwiftEvent]) -> Result<Vec<PlannedMapping>> {
+    let mut aliased: HashSet<u32> = db
+        .get_route_aliases()?
+        .into_iter()
+        .map(|(alias_route_id, _, _)| alias_route_id)
+        .collect();
+    let mut patterns: HashSet<String> = db
+        .get_multi_lap_patterns()?
+        .into_iter()
+        .map(|(pattern, _, _, _)| pattern)
+        .collect();
+    let mut new_routes: HashSet<u32> = HashSet::new();
+    let mut planned = Vec::new();
+
+    for event in events {
+        let unknown_route_id = match event.route_id {
+            Some(route_id) if db.get_route(route_id)?.is_some() => continue,
+            route_id => route_id,
+        };
+        let Some(parsed) = event
+            .description
+            .as_deref()
+            .and_then(parse_route_from_description)
+        else {
+            continue;
+        };
+        let mut plan = |change: MappingChange| {
+            planned.push(PlannedMapping {
+                event_name: event.name.clone(),
+                change,
+            });
+        };
+
+        if let Some(known) = confident_match(db.find_routes_by_name(&parsed.route_name)?) {
+            if let Some(route_id) = unknown_route_id.filter(|id| aliased.insert(*id)) {
+                plan(MappingChange::Alias {
+                    alias_route_id: route_id,
+                    canonical_route_id: known.route_id,
+                    route_name: known.route_name.clone(),
+                    score: known.score,
+                });
+            }
+            let pattern = event.name.trim().to_string();
+            if parsed.laps > 1 && patterns.insert(pattern.clone()) {
+                plan(MappingChange::MultiLap {
+                    event_name_pattern: pattern,
+                    route_id: known.route_id,
+                    route_name: known.route_name,
+                    laps: parsed.laps,
+                });
+            }
+            continue;
+        }
+
+        // Nothing by that name: a new route, if the description sizes it
+        let Some(route_id) = unknown_route_id.filter(|id| !aliased.contains(id)) else {
+            continue;
+        };
+        let data = parse_description_data(&event.description);
+        if let (Some(distance_km), Some(elevation_m)) = (data.distance_km, data.elevation_m) {
+            if new_routes.insert(route_id) {
+                let laps = parsed.laps.max(1);
+                let world = detect_world(&format!("{} {}", event.name, parsed.route_name))
+                    .map_or_else(|| "Unknown".to_string(), format_world_name);
+                plan(MappingChange::NewRoute {
+                    route_id,
+                    name: parsed.route_name,
+                    world,
+                    distance_km: distance_km / f64::from(laps),
+                    elevation_m: elevation_m / laps,
+                });
+            }
+        }
+    }
+    Ok(planned)
+}
+
+/// Write planned mappings and record each one in the audit table under `source`
+///
+/// New routes never overwrite data from a more trusted source. An alias also
+/// resolves the route's `discovery_queue` entry. Each change is written with
+/// its audit record in one transaction. Returns how many changes were written.
+///
+/// # Errors
+///
+/// Returns an error if a write fails; earlier changes stay written.
+pub fn apply_mappings(db: &Database, source: &str, planned: &[PlannedMapping]) -> Result<usize> {
+    let mut applied = 0;
+    for PlannedMapping { event_name, change } in planned {
+        let written = db.in_transaction(|db| {
+            let notes = format!("Inferred by {source} from '{event_name}'");
+            let (route_id, target_route_id) = match change {
+                MappingChange::Alias {
+                    alias_route_id,
+                    canonical_route_id,
+                    ..
+                } => {
+                    db.add_route_alias(*alias_route_id, *canonical_route_id, Some(&notes))?;
+                    db.record_discovery_success(*alias_route_id)?;
+                    (*alias_route_id, Some(*canonical_route_id))
+                }
+                MappingChange::MultiLap {
+                    event_name_pattern,
+                    route_id,
+                    laps,
+                    ..
+                } => {
+                    db.add_multi_lap_pattern(event_name_pattern, *route_id, *laps, Some(&notes))?;
+                    (*route_id, Some(*route_id))
+                }
+                MappingChange::NewRoute {
+                    route_id,
+                    name,
+                    world,
+                    distance_km,
+                    elevation_m,
+                } => {
+                    if !db.save_discovered_route(
+                        *route_id,
+                        *distance_km,
+                        *elevation_m,
+                        world,
+                        "road",
+                        name,
+                        RouteSource::Unknown,
+                    )? {
+                        return Ok(false);
+                    }
+                    (*route_id, None)
+                }
+            };
+            db.record_mapping_audit(
+                source,
+                change.action(),
+                route_id,
+                target_route_id,
+                Some(event_name),
+                &change.to_string(),
+            )?;
+            Ok(true)
+        })?;
+        if written {
+            applied += 1;
+        }
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn event(name: &str, route_id: Option<u32>, description: &str) -> ZwiftEvent {
+        ZwiftEvent {
+            id: 1,
+            name: name.to_string(),
+            event_start: Utc::now(),
+            event_type: "RACE".to_string(),
+            distance_in_meters: Some(0.0),
+            duration_in_minutes: None,
+            duration_in_seconds: None,
+            route_id,
+            route: None,
+            description: Some(description.to_string()),
+            category_enforcement: false,
+            event_sub_groups: vec![],
+            sport: "CYCLING".to_string(),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_plan_and_apply_mappings() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        let events = vec![
+            event(
+                "Makuri Monday Race",
+                Some(111),
+                "3 laps of Castle to Castle, sprint at the end",
+            ),
+            // Same route ID seen again: planned once
+            event(
+                "Makuri Monday Race",
+                Some(111),
+                "3 laps of Castle to Castle",
+            ),
+            event(
+                "Harbour Sprint",
+                Some(222),
+                "2 laps of Harbour Loop, Distance: 20 km, Elevation: 60 m",
+            ),
+            // Known route: nothing to do
+            event("Bell Lap Crit", Some(1_258_415_487), "10 laps of Bell Lap"),
+            event("Mystery Race", Some(333), "2 laps of Mystery Loop"),
+        ];
+
+        db.record_unknown_route(111, "Makuri Monday Race", "RACE")
+            .unwrap();
+        db.sync_discovery_queue().unwrap();
+
+        let planned = plan_mappings(&db, &events).unwrap();
+        let changes: Vec<_> = planned.iter().map(|p| &p.change).collect();
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(
+            changes[0],
+            MappingChange::Alias {
+                alias_route_id: 111,
+                canonical_route_id: 3_742_187_716,
+                ..
+            }
+        ));
+        assert!(matches!(
+            changes[1],
+            MappingChange::MultiLap {
+                route_id: 3_742_187_716,
+                laps: 3,
+                ..
+            }
+        ));
+        match changes[2] {
+            MappingChange::NewRoute {
+                route_id,
+                distance_km,
+                elevation_m,
+                ..
+            } => {
+                assert_eq!(*route_id, 222);
+                assert!((distance_km - 10.0).abs() < 1e-9);
+                assert_eq!(*elevation_m, 30);
+            }
+            other => panic!("expected a new route, got {other:?}"),
+        }
+
+        assert_eq!(apply_mappings(&db, AUDIT_SOURCE, &planned).unwrap(), 3);
+        assert_eq!(db.get_route(111).unwrap().unwrap().route_id, 3_742_187_716);
+        // The alias resolves the queued unknown route
+        assert!(db.get_due_discoveries(10).unwrap().is_empty());
+        assert!(db.get_discovery_queue().unwrap()[0].found);
+        assert_eq!(
+            db.get_multi_lap_info("Makuri Monday Race").unwrap(),
+            Some(3)
+        );
+        assert_eq!(db.get_route(222).unwrap().unwrap().name, "Harbour Loop");
+
+        let audit = db.get_mapping_audit(10).unwrap();
+        assert_eq!(audit.len(), 3);
+        assert_eq!(audit[0].action, "route");
+        assert_eq!(audit[2].action, "alias");
+        assert_eq!(audit[2].source, AUDIT_SOURCE);
+        assert_eq!(audit[2].target_route_id, Some(3_742_187_716));
+        assert_eq!(audit[2].event_name.as_deref(), Some("Makuri Monday Race"));
+
+        // Applied mappings aren't planned again
+        assert!(plan_mappings(&db, &events).unwrap().is_empty());
+    }
+}
@@ -10,6 +10,15 @@ use crate::duration_estimation::{
     get_route_difficulty_multiplier_from_elevation_and_category,
 };
 use crate::models::RouteData;
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+
+lazy_static! {
+    /// Low-confidence route IDs, loaded once per run
+    static ref LOW_CONFIDENCE_ROUTES: HashSet<u32> = Database::new()
+        .and_then(|db| db.get_low_confidence_route_ids())
+        .unwrap_or_default();
+}
 
 /// Get route data from the database
 pub fn get_route_data_from_db(route_id: u32) -> Option<DbRouteData> {
@@ -19,19 +28,32 @@ pub fn get_route_data_from_db(route_id: u32) -> Option<DbRouteData> {
     }
 }
 
+/// Whether a route's stored data comes from a low-confidence source
+///
+/// Routes missing from the database are not flagged; they are reported as
+/// unknown routes instead. Provenance is read once and cached for the run.
+#[must_use]
+pub fn is_low_confidence_route(route_id: u32) -> bool {
+    LOW_CONFIDENCE_ROUTES.contains(&route_id)
+}
+
+/// Convert a database route to models::RouteData
+fn to_route_data(db_route: DbRouteData) -> RouteData {
+    RouteData {
+        distance_km: db_route.distance_km,
+        elevation_m: db_route.elevation_m,
+        name: Box::leak(db_route.name.into_boxed_str()),
+        world: Box::leak(db_route.world.into_boxed_str()),
+        surface: Box::leak(db_route.surface.into_boxed_str()),
+        lead_in_distance_km: db_route.lead_in_distance_km,
+    }
+}
+
 /// Get route data and convert to models::RouteData
 pub fn get_route_data(route_id: u32) -> Option<RouteData> {
     // First try database
     if let Some(db_route) = get_route_data_from_db(route_id) {
-        // Map fields from database RouteData to models RouteData
-        return Some(RouteData {
-            distance_km: db_route.distance_km,
-            elevation_m: db_route.elevation_m,
-            name: Box::leak(db_route.name.into_boxed_str()),
-            world: Box::leak(db_route.world.into_boxed_str()),
-            surface: Box::leak(db_route.surface.into_boxed_str()),
-            lead_in_distance_km: db_route.lead_in_distance_km,
-        });
+        return Some(to_route_data(db_route));
     }
 
     // Fallback to hardcoded data for common routes
@@ -49,6 +71,30 @@ pub fn get_route_data(route_id: u32) -> Option<RouteData> {
     }
 }
 
+/// Get route data with the lead-in from an event start location (pen)
+///
+/// See [`crate::lead_in`]; the default start keeps the stored lead-in.
+pub fn get_route_data_for_start(route_id: u32, start_location: Option<u32>) -> Option<RouteData> {
+    let default_start = crate::lead_in::DEFAULT_START_LOCATION;
+    if start_location.unwrap_or(default_start) == default_start {
+        return get_route_data(route_id);
+    }
+    // One connection for both the route and its start lines
+    let db = Database::new().ok();
+    let Some((db, db_route)) = db.and_then(|db| {
+        let db_route = db.get_route(route_id).ok().flatten()?;
+        Some((db, db_route))
+    }) else {
+        return get_route_data(route_id);
+    };
+    let lead_in = crate::lead_in::lead_in_for_start_location(&db, &db_route, start_location).ok();
+    let mut route_data = to_route_data(db_route);
+    if let Some(lead_in) = lead_in {
+        route_data.lead_in_distance_km = lead_in;
+    }
+    Some(route_data)
+}
+
 /// Estimate duration based on route_id only
 pub fn estimate_duration_from_route_id(route_id: u32, zwift_score: u32) -> Option<u32> {
     let route_data = get_route_data(route_id)?;
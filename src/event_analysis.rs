@@ -3,7 +3,9 @@
 //! This module contains functions for analyzing Zwift events.
 
 use crate::category::{category_matches_subgroup, get_category_from_score};
+use crate::constants::METERS_PER_KILOMETER;
 use crate::models::{EventSubGroup, ZwiftEvent};
+use crate::parsing::parse_category_courses;
 use std::collections::HashMap;
 
 /// Find the subgroup that matches the user's category
@@ -24,6 +26,48 @@ pub fn find_user_subgroup<'a>(
         .find(|sg| category_matches_subgroup(user_category, &sg.name))
 }
 
+/// Fill in subgroups' laps and distance from a per-category list in the description
+///
+/// Racing Score events often leave `distance_in_meters` at 0 and say what each
+/// category rides in the description instead ("A: 4 laps / B: 3 laps / C,D: 2
+/// laps"). Only values the API left empty are filled in.
+pub fn apply_description_courses(event: &mut ZwiftEvent) {
+    let Some(description) = &event.description else {
+        return;
+    };
+    let courses = parse_category_courses(description);
+    if courses.is_empty() {
+        return;
+    }
+
+    for subgroup in &mut event.event_sub_groups {
+        let Some(category) = subgroup_category(&subgroup.name) else {
+            continue;
+        };
+        let Some(course) = courses
+            .iter()
+            .find(|course| course.categories.contains(&category))
+        else {
+            continue;
+        };
+        if subgroup.laps.is_none_or(|laps| laps == 0) {
+            subgroup.laps = course.laps.or(subgroup.laps);
+        }
+        if subgroup.distance_in_meters.is_none_or(|d| d <= 0.0) {
+            if let Some(distance_km) = course.distance_km {
+                subgroup.distance_in_meters = Some(distance_km * METERS_PER_KILOMETER);
+            }
+        }
+    }
+}
+
+/// Category letter of a subgroup name, e.g. "Cat C Women" -> 'C'
+fn subgroup_category(name: &str) -> Option<char> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .find(|word| matches!(*word, "A" | "B" | "C" | "D" | "E"))
+        .and_then(|word| word.chars().next())
+}
+
 /// Count events by type for display
 pub fn count_events_by_type(events: &[ZwiftEvent]) -> Vec<(String, usize)> {
     let mut event_counts = HashMap::new();
@@ -147,6 +191,8 @@ mod tests {
                     category_enforcement: None,
                     range_access_label: None,
                     laps: None,
+                    start_location: None,
+                    event_subgroup_start: None,
                 },
                 EventSubGroup {
                     id: 2,
@@ -157,6 +203,8 @@ mod tests {
                     category_enforcement: None,
                     range_access_label: None,
                     laps: None,
+                    start_location: None,
+                    event_subgroup_start: None,
                 },
                 EventSubGroup {
                     id: 3,
@@ -167,6 +215,8 @@ mod tests {
                     category_enforcement: None,
                     range_access_label: None,
                     laps: None,
+                    start_location: None,
+                    event_subgroup_start: None,
                 },
                 EventSubGroup {
                     id: 4,
@@ -177,6 +227,8 @@ mod tests {
                     category_enforcement: None,
                     range_access_label: None,
                     laps: None,
+                    start_location: None,
+                    event_subgroup_start: None,
                 },
                 EventSubGroup {
                     id: 5,
@@ -187,6 +239,8 @@ mod tests {
                     category_enforcement: None,
                     range_access_label: None,
                     laps: None,
+                    start_location: None,
+                    event_subgroup_start: None,
                 },
             ],
         };
@@ -231,6 +285,8 @@ mod tests {
                 category_enforcement: None,
                 range_access_label: None,
                 laps: None,
+                start_location: None,
+                event_subgroup_start: None,
             },
             EventSubGroup {
                 id: 2,
@@ -241,6 +297,8 @@ mod tests {
                 category_enforcement: None,
                 range_access_label: None,
                 laps: None,
+                start_location: None,
+                event_subgroup_start: None,
             },
         ];
 
@@ -252,4 +310,61 @@ mod tests {
         let subgroup = find_user_subgroup(&event, 200).unwrap();
         assert_eq!(subgroup.name, "C");
     }
+
+    #[test]
+    fn test_apply_description_courses() {
+        let subgroup = |id: u32, name: &str, distance_in_meters: Option<f64>| EventSubGroup {
+            id,
+            name: name.to_string(),
+            route_id: None,
+            distance_in_meters,
+            duration_in_minutes: None,
+            category_enforcement: None,
+            range_access_label: None,
+            laps: None,
+            start_location: None,
+            event_subgroup_start: None,
+        };
+        let mut event = ZwiftEvent {
+            id: 1,
+            name: "Racing Score Race".to_string(),
+            description: Some(
+                "A: 4 laps / B: 3 laps / C,D: 2 laps (24.6 km)\nE: 12 km".to_string(),
+            ),
+            event_start: Utc::now(),
+            event_type: "RACE".to_string(),
+            distance_in_meters: Some(0.0),
+            duration_in_minutes: None,
+            duration_in_seconds: None,
+            route_id: Some(1),
+            route: None,
+            category_enforcement: false,
+            sport: "CYCLING".to_string(),
+            tags: vec![],
+            event_sub_groups: vec![
+                subgroup(1, "A", Some(0.0)),
+                subgroup(2, "B", None),
+                subgroup(3, "Cat C", None),
+                subgroup(4, "D", Some(20000.0)),
+                subgroup(5, "E", None),
+            ],
+        };
+
+        apply_description_courses(&mut event);
+
+        let laps: Vec<_> = event.event_sub_groups.iter().map(|sg| sg.laps).collect();
+        assert_eq!(laps, vec![Some(4), Some(3), Some(2), Some(2), None]);
+        let distances: Vec<_> = event
+            .event_sub_groups
+            .iter()
+            .map(|sg| sg.distance_in_meters)
+            .collect();
+        // Distances from the API are kept
+        assert_eq!(
+            distances,
+            vec![Some(0.0), None, Some(24600.0), Some(20000.0), Some(12000.0)]
+        );
+        // Each category gets its own subgroup estimate input
+        assert_eq!(find_user_subgroup(&event, 195).unwrap().laps, Some(2));
+    }
 }
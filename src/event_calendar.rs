@@ -0,0 +1,252 @@
+//! iCalendar export of matching events
+//!
+//! `--ics <file>` writes the search results as an RFC 5545 calendar so a
+//! calendar app subscribed to the file picks up suitable races. Each event
+//! runs from its start to the estimated finish; the description carries the
+//! route, distance, climbing and a link to the event page.
+
+use crate::event_export::EventRecord;
+use crate::formatting::format_duration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Longest content line, in octets, before it is folded
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Build a calendar of `events`
+///
+/// `stamp` is when the calendar was generated (`DTSTAMP`); `reminder_minutes`
+/// adds an alarm that many minutes before each event.
+#[must_use]
+pub fn calendar(
+    events: &[EventRecord],
+    stamp: DateTime<Utc>,
+    reminder_minutes: Option<u32>,
+) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//zwift-race-finder//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, "X-WR-CALNAME:Zwift races");
+    for event in events {
+        push_event(&mut ics, event, stamp, reminder_minutes);
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Write a calendar of `events` to `path`, replacing the file
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written.
+pub fn write_calendar(
+    path: &Path,
+    events: &[EventRecord],
+    stamp: DateTime<Utc>,
+    reminder_minutes: Option<u32>,
+) -> Result<()> {
+    std::fs::write(path, calendar(events, stamp, reminder_minutes))
+        .with_context(|| format!("Failed to write calendar {}", path.display()))
+}
+
+fn push_event(
+    ics: &mut String,
+    event: &EventRecord,
+    stamp: DateTime<Utc>,
+    reminder_minutes: Option<u32>,
+) {
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:{}@zwift-race-finder", event.id));
+    push_line(ics, &format!("DTSTAMP:{}", ics_time(stamp)));
+    push_line(ics, &format!("DTSTART:{}", ics_time(event.start)));
+    if let Some(end) = event.estimated_end {
+        push_line(ics, &format!("DTEND:{}", ics_time(end)));
+    }
+    push_line(ics, &format!("SUMMARY:{}", escape_text(&event.name)));
+    push_line(
+        ics,
+        &format!("DESCRIPTION:{}", escape_text(&description(event))),
+    );
+    let world = event
+        .route
+        .as_ref()
+        .and_then(|route| route.world.as_deref());
+    let location = world.map_or_else(|| "Zwift".to_string(), |world| format!("Zwift - {world}"));
+    push_line(ics, &format!("LOCATION:{}", escape_text(&location)));
+    push_line(ics, &format!("URL:{}", event.url));
+    push_line(
+        ics,
+        &format!("CATEGORIES:{}", escape_text(&event.event_type)),
+    );
+    if let Some(minutes) = reminder_minutes {
+        push_line(ics, "BEGIN:VALARM");
+        push_line(ics, "ACTION:DISPLAY");
+        push_line(ics, &format!("TRIGGER:-PT{minutes}M"));
+        push_line(ics, &format!("DESCRIPTION:{}", escape_text(&event.name)));
+        push_line(ics, "END:VALARM");
+    }
+    push_line(ics, "END:VEVENT");
+}
+
+/// Route, distance, climbing, estimate and link, one per line
+fn description(event: &EventRecord) -> String {
+    let mut text = String::new();
+    if let Some(route) = &event.route {
+        let name = route.name.as_deref().unwrap_or("Unknown route");
+        let _ = write!(text, "Route: {name}");
+        if let Some(world) = &route.world {
+            let _ = write!(text, " ({world})");
+        }
+        if route.laps > 1 {
+            let _ = write!(text, ", {} laps", route.laps);
+        }
+        text.push('\n');
+    }
+    match (event.distance_km, event.elevation_m) {
+        (Some(km), Some(m)) => {
+            let _ = writeln!(text, "Distance: {km:.1} km, elevation: {m} m");
+        }
+        (Some(km), None) => {
+            let _ = writeln!(text, "Distance: {km:.1} km");
+        }
+        _ => {}
+    }
+    if let Some(minutes) = event.estimated_minutes {
+        let _ = writeln!(text, "Estimated time: {}", format_duration(minutes));
+    }
+    if let Some(subgroup) = &event.subgroup {
+        let _ = writeln!(text, "Pen: {}", subgroup.name);
+    }
+    text.push_str(&event.url);
+    text
+}
+
+/// UTC date-time in iCalendar's basic format
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folded at 75 octets without splitting a character
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_export::{event_url, RouteRecord, SubgroupRecord};
+
+    fn record() -> EventRecord {
+        let start: DateTime<Utc> = "2026-10-18T09:00:00Z".parse().unwrap();
+        EventRecord {
+            id: 4_567_890,
+            name: "Makuri Monday Race; Castle, Sprint".to_string(),
+            event_type: "RACE".to_string(),
+            start,
+            url: event_url(4_567_890),
+            estimated_minutes: Some(65),
+            estimated_end: Some(start + chrono::Duration::minutes(65)),
+            distance_km: Some(24.64),
+            elevation_m: Some(180),
+            route: Some(RouteRecord {
+                route_id: 3_742_187_716,
+                known: true,
+                name: Some("Castle to Castle".to_string()),
+                world: Some("Makuri Islands".to_string()),
+                surface: Some("road".to_string()),
+                lap_distance_km: Some(12.0),
+                lap_elevation_m: Some(90),
+                lead_in_km: Some(0.64),
+                laps: 2,
+                low_confidence: false,
+            }),
+            subgroup: Some(SubgroupRecord {
+                id: 7,
+                name: "C".to_string(),
+                laps: Some(2),
+                distance_km: None,
+                start_location: None,
+                start: None,
+            }),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_calendar_event() {
+        let stamp: DateTime<Utc> = "2026-10-17T20:00:00Z".parse().unwrap();
+        let ics = calendar(&[record()], stamp, Some(15));
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_OCTETS + 1));
+
+        // Unfold before looking at values
+        let unfolded = ics.replace("\r\n ", "");
+        let lines: Vec<&str> = unfolded.split("\r\n").collect();
+        assert!(lines.contains(&"UID:4567890@zwift-race-finder"));
+        assert!(lines.contains(&"DTSTAMP:20261017T200000Z"));
+        assert!(lines.contains(&"DTSTART:20261018T090000Z"));
+        assert!(lines.contains(&"DTEND:20261018T100500Z"));
+        assert!(lines.contains(&"SUMMARY:Makuri Monday Race\\; Castle\\, Sprint"));
+        assert!(lines.contains(&"LOCATION:Zwift - Makuri Islands"));
+        assert!(lines.contains(&"URL:https://www.zwift.com/events/view/4567890"));
+        assert!(lines.contains(&"TRIGGER:-PT15M"));
+        assert!(lines.contains(
+            &"DESCRIPTION:Route: Castle to Castle (Makuri Islands)\\, 2 laps\\nDistance: 24.6 km\\, \
+              elevation: 180 m\\nEstimated time: 01:05\\nPen: C\\nhttps://www.zwift.com/events/view/4567890"
+        ));
+    }
+
+    #[test]
+    fn test_calendar_without_reminder_or_events() {
+        let stamp = Utc::now();
+        assert!(!calendar(&[record()], stamp, None).contains("VALARM"));
+        assert!(!calendar(&[], stamp, Some(10)).contains("VEVENT"));
+    }
+
+    #[test]
+    fn test_push_line_folds_multibyte_text() {
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("SUMMARY:{}", "é".repeat(60)));
+        let lines: Vec<&str> = ics.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            ics.replace("\r\n ", "").trim_end(),
+            format!("SUMMARY:{}", "é".repeat(60))
+        );
+    }
+}
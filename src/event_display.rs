@@ -6,18 +6,29 @@ use crate::category::{
     get_category_from_score, get_category_speed, get_detailed_category_from_score,
 };
 use crate::constants::METERS_PER_KILOMETER;
-use crate::database::Database;
+use crate::database::{Database, RouteData, RouteSegment, SegmentType};
 use crate::duration_estimation::estimate_duration_for_category;
 use crate::duration_estimation::get_route_difficulty_multiplier_from_elevation_and_category;
-use crate::estimation::{get_route_data, get_route_data_from_db};
+use crate::estimation::{
+    get_route_data, get_route_data_for_start, get_route_data_from_db, is_low_confidence_route,
+};
 use crate::event_analysis::find_user_subgroup;
 use crate::event_filtering::FilterStats;
 use crate::formatting::format_duration;
 use crate::models::{EventSubGroup, ZwiftEvent};
 use crate::parsing::{estimate_distance_from_name, parse_description_data};
 use crate::route_discovery;
+use crate::route_names::{self, NameMatch};
 use chrono::{DateTime, Local};
 use colored::Colorize;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// Routes unknown route IDs are name matched against, loaded once per run
+    static ref NAMEABLE_ROUTES: Vec<RouteData> = Database::new()
+        .and_then(|db| db.get_nameable_routes())
+        .unwrap_or_default();
+}
 
 /// Display the basic event header information
 pub fn display_event_header(event: &ZwiftEvent) {
@@ -63,17 +74,47 @@ pub fn display_route_info(event: &ZwiftEvent) {
                 route_data.elevation_m,
                 completion_marker
             );
+            if is_low_confidence_route(route_id) {
+                println!(
+                    "  {} route data is low confidence - estimates may be off",
+                    "⚠️".yellow()
+                );
+            }
         } else {
             println!(
                 "{}: {} (unknown route - automatically logged)",
                 "Route ID".bright_blue().dimmed(),
                 route_id.to_string().yellow()
             );
+            if let Some(likely) = likely_known_route(event, &NAMEABLE_ROUTES) {
+                println!(
+                    "  {} likely {} ({}, {:.0}% name match)",
+                    "↳".dimmed(),
+                    likely.route_name,
+                    likely.world,
+                    likely.score * 100.0
+                );
+            }
             log_unknown_route(event);
         }
     }
 }
 
+/// Known route an event with an unknown route ID most likely runs on
+///
+/// Matches the route named in the description, falling back to the event's
+/// route or name. Only confident, unambiguous name matches count.
+fn likely_known_route(event: &ZwiftEvent, routes: &[RouteData]) -> Option<NameMatch> {
+    let name = event
+        .description
+        .as_deref()
+        .and_then(route_discovery::parse_route_from_description)
+        .map(|parsed| parsed.route_name)
+        .or_else(|| event.route.clone())
+        .unwrap_or_else(|| event.name.clone());
+    route_names::best_route_match(&name, routes)
+}
+
 /// Log unknown route for future mapping
 pub fn log_unknown_route(event: &ZwiftEvent) {
     if let Some(route_id) = event.route_id {
@@ -142,7 +183,8 @@ fn display_route_based_duration(
     distance_meters: Option<f64>,
     zwift_score: u32,
 ) {
-    if let Some(route_data) = get_route_data(route_id) {
+    let start_location = user_subgroup.and_then(|sg| sg.start_location);
+    if let Some(route_data) = get_route_data_for_start(route_id, start_location) {
         let (actual_distance_km, lap_count) =
             calculate_actual_distance(&route_data, user_subgroup, distance_meters, event);
 
@@ -161,10 +203,15 @@ fn display_route_based_duration(
 
         // Show lead-in distance if significant
         if route_data.lead_in_distance_km > 0.1 {
+            let pen = start_location
+                .filter(|loc| *loc != crate::lead_in::DEFAULT_START_LOCATION)
+                .map(|loc| format!(" (from start location {loc})"))
+                .unwrap_or_default();
             println!(
-                "{}: {:.1} km",
+                "{}: {:.1} km{}",
                 "Lead-in".bright_blue(),
-                route_data.lead_in_distance_km
+                route_data.lead_in_distance_km,
+                pen
             );
         }
 
@@ -225,6 +272,72 @@ fn calculate_actual_distance(
     (actual_distance_km, lap_count)
 }
 
+/// Race distances (km from the start, including lead-in) at which a segment
+/// begins on each lap
+fn segment_positions(
+    segment: &RouteSegment,
+    route_distance_km: f64,
+    lead_in_distance_km: f64,
+    lap_count: u32,
+) -> Vec<f64> {
+    (0..lap_count.max(1))
+        .map(|lap| lead_in_distance_km + f64::from(lap) * route_distance_km + segment.start_km)
+        .collect()
+}
+
+/// Display the route's climbs and sprints, repeated for each lap
+pub fn display_route_segments(event: &ZwiftEvent, zwift_score: u32) {
+    let Some(route_id) = event.route_id else {
+        return;
+    };
+    let user_subgroup = find_user_subgroup(event, zwift_score);
+    let start_location = user_subgroup.and_then(|sg| sg.start_location);
+    let Some(route_data) = get_route_data_for_start(route_id, start_location) else {
+        return;
+    };
+    let segments = match Database::new().and_then(|db| db.get_route_segments(route_id)) {
+        Ok(segments) if !segments.is_empty() => segments,
+        _ => return,
+    };
+
+    let distance_meters = user_subgroup
+        .and_then(|sg| sg.distance_in_meters)
+        .or(event.distance_in_meters);
+    let (_, lap_count) =
+        calculate_actual_distance(&route_data, user_subgroup, distance_meters, event);
+
+    println!("{}:", "Segments".bright_blue());
+    for segment in &segments {
+        let icon = match segment.segment_type {
+            SegmentType::Kom => "⛰️ ",
+            SegmentType::Sprint => "⚡",
+            SegmentType::Climb => "📈",
+        };
+        let gradient = segment
+            .avg_gradient
+            .map(|g| format!(" @ {g:.1}%"))
+            .unwrap_or_default();
+        let positions = segment_positions(
+            segment,
+            route_data.distance_km,
+            route_data.lead_in_distance_km,
+            lap_count,
+        )
+        .iter()
+        .map(|km| format!("{km:.1}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+        println!(
+            "  {} {} ({:.1} km{}) at km {}",
+            icon,
+            segment.name,
+            segment.length_km,
+            gradient,
+            positions.dimmed()
+        );
+    }
+}
+
 /// Display calculated duration based on route data
 fn display_calculated_duration(
     route_data: &crate::models::RouteData,
@@ -477,12 +590,16 @@ pub fn print_event(event: &ZwiftEvent, zwift_score: u32) {
     display_event_header(event);
     display_route_info(event);
     display_duration_info(event, zwift_score);
+    display_route_segments(event, zwift_score);
     display_category_enforcement(event);
     display_subgroups(event, zwift_score);
     display_description_info(event);
     display_external_url(event);
 }
 
+/// Suffix on the distance column for routes with low-confidence data
+const LOW_CONFIDENCE_MARKER: &str = "*";
+
 /// Structure to hold table row data for compact display
 pub struct EventTableRow {
     pub name: String,
@@ -490,6 +607,8 @@ pub struct EventTableRow {
     pub distance: String,
     pub elevation: String,
     pub duration: String,
+    /// Route data comes from a low-confidence source (e.g. a stub)
+    pub low_confidence: bool,
 }
 
 /// Prepare event data for table display
@@ -499,9 +618,10 @@ pub fn prepare_event_row(event: &ZwiftEvent, zwift_score: u32) -> EventTableRow
 
     // Get route data and calculate total distance and elevation
     let (distance_str, elevation_str, duration_str) = if let Some(route_id) = event.route_id {
-        if let Some(route_data) = get_route_data(route_id) {
+        let user_subgroup = find_user_subgroup(event, zwift_score);
+        let start_location = user_subgroup.and_then(|sg| sg.start_location);
+        if let Some(route_data) = get_route_data_for_start(route_id, start_location) {
             // Calculate total distance including lead-in
-            let user_subgroup = find_user_subgroup(event, zwift_score);
             let distance_meters = user_subgroup
                 .and_then(|sg| sg.distance_in_meters)
                 .or(event.distance_in_meters);
@@ -589,12 +709,20 @@ pub fn prepare_event_row(event: &ZwiftEvent, zwift_score: u32) -> EventTableRow
         }
     };
 
+    let low_confidence = event.route_id.is_some_and(is_low_confidence_route);
+    let distance_str = if low_confidence {
+        format!("{distance_str}{LOW_CONFIDENCE_MARKER}")
+    } else {
+        distance_str
+    };
+
     EventTableRow {
         name: event.name.clone(),
         time: time_str,
         distance: distance_str,
         elevation: elevation_str,
         duration: duration_str,
+        low_confidence,
     }
 }
 
@@ -611,9 +739,11 @@ pub fn print_events_table(events: &[ZwiftEvent], zwift_score: u32) {
 
     // Collect data for all events
     let mut rows: Vec<(EventTableRow, DateTime<Local>)> = Vec::new();
+    let mut any_low_confidence = false;
 
     for event in events {
         let row = prepare_event_row(event, zwift_score);
+        any_low_confidence |= row.low_confidence;
         let local_time: DateTime<Local> = event.event_start.into();
         rows.push((row, local_time));
     }
@@ -705,6 +835,16 @@ pub fn print_events_table(events: &[ZwiftEvent], zwift_score: u32) {
     }
 
     println!("{}", "─".repeat(total_width).dimmed());
+
+    if any_low_confidence {
+        println!(
+            "{}",
+            format!(
+                "{LOW_CONFIDENCE_MARKER} Route data from a low-confidence source (e.g. a placeholder); estimate may be off"
+            )
+            .dimmed()
+        );
+    }
 }
 
 /// Display filter statistics and actionable fixes
@@ -1042,6 +1182,26 @@ mod tests {
         log_unknown_route(&event);
     }
 
+    #[test]
+    fn test_segment_positions_repeat_per_lap() {
+        let segment = RouteSegment {
+            segment_type: SegmentType::Sprint,
+            name: "Watopia Sprint".to_string(),
+            start_km: 2.0,
+            length_km: 0.4,
+            avg_gradient: None,
+        };
+
+        let positions = segment_positions(&segment, 10.0, 0.5, 3);
+        assert_eq!(positions.len(), 3);
+        assert!((positions[0] - 2.5).abs() < 1e-9);
+        assert!((positions[1] - 12.5).abs() < 1e-9);
+        assert!((positions[2] - 22.5).abs() < 1e-9);
+
+        // Unknown lap count still shows the first lap
+        assert_eq!(segment_positions(&segment, 10.0, 0.5, 0).len(), 1);
+    }
+
     #[test]
     fn test_calculate_actual_distance_arithmetic() {
         // Test division operations in calculate_actual_distance
@@ -1081,6 +1241,8 @@ mod tests {
             category_enforcement: None,
             range_access_label: None,
             laps: None,
+            start_location: None,
+            event_subgroup_start: None,
         };
 
         let (distance2, laps2) =
@@ -1194,6 +1356,8 @@ mod tests {
             category_enforcement: None,
             range_access_label: None,
             laps: Some(3),
+            start_location: None,
+            event_subgroup_start: None,
         }];
 
         // Also need to find the user's subgroup - add the score range
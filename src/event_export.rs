@@ -0,0 +1,512 @@
+//! Machine-readable output of matching events
+//!
+//! `--format json|csv|ndjson` writes the search results for scripts,
+//! spreadsheets and bots instead of the coloured table. The schema is
+//! documented in `docs/reference/OUTPUT_FORMATS.md`; fields are only ever
+//! added; renaming or removing one bumps [`SCHEMA_VERSION`].
+
+use crate::category::get_category_from_score;
+use crate::constants::METERS_PER_KILOMETER;
+use crate::estimation::{get_route_data_for_start, is_low_confidence_route};
+use crate::event_analysis::find_user_subgroup;
+use crate::event_filtering::{estimate_event_duration, FilterStats};
+use crate::models::ZwiftEvent;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Version of the output schema
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Columns of the CSV output, in order
+pub const CSV_COLUMNS: [&str; 16] = [
+    "id",
+    "name",
+    "event_type",
+    "start",
+    "url",
+    "estimated_minutes",
+    "estimated_end",
+    "distance_km",
+    "elevation_m",
+    "route_id",
+    "route_name",
+    "world",
+    "laps",
+    "subgroup",
+    "low_confidence",
+    "tags",
+];
+
+/// How search results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Coloured table (or verbose text with `-v`)
+    #[default]
+    Table,
+    /// One JSON document with the events and filter stats
+    Json,
+    /// One row per event, with a header row
+    Csv,
+    /// One JSON object per line: the events, then a summary
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// `true` for formats meant for programs rather than people
+    #[must_use]
+    pub fn is_machine_readable(self) -> bool {
+        self != OutputFormat::Table
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            other => Err(format!(
+                "unknown output format '{other}' (expected table, json, csv or ndjson)"
+            )),
+        }
+    }
+}
+
+/// A matching event with its estimate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventRecord {
+    pub id: u64,
+    pub name: String,
+    pub event_type: String,
+    pub start: DateTime<Utc>,
+    /// Event page on zwift.com
+    pub url: String,
+    /// Estimated riding time for the rider's category
+    pub estimated_minutes: Option<u32>,
+    /// `start` plus the estimate
+    pub estimated_end: Option<DateTime<Utc>>,
+    /// Total distance including the lead-in
+    pub distance_km: Option<f64>,
+    /// Total climbing over all laps
+    pub elevation_m: Option<u32>,
+    pub route: Option<RouteRecord>,
+    /// The subgroup (pen) the rider's category races in
+    pub subgroup: Option<SubgroupRecord>,
+    pub tags: Vec<String>,
+}
+
+/// The route an event runs on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteRecord {
+    pub route_id: u32,
+    /// `false` when the route ID isn't in the database; the fields below are then empty
+    pub known: bool,
+    pub name: Option<String>,
+    pub world: Option<String>,
+    pub surface: Option<String>,
+    /// One lap, without the lead-in
+    pub lap_distance_km: Option<f64>,
+    pub lap_elevation_m: Option<u32>,
+    pub lead_in_km: Option<f64>,
+    pub laps: u32,
+    /// Route data comes from a low-confidence source
+    pub low_confidence: bool,
+}
+
+/// An event subgroup (pen)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubgroupRecord {
+    pub id: u32,
+    pub name: String,
+    pub laps: Option<u32>,
+    pub distance_km: Option<f64>,
+    pub start_location: Option<u32>,
+    pub start: Option<DateTime<Utc>>,
+}
+
+/// Why fetched events didn't make it into the results
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterSummary {
+    pub fetched: usize,
+    pub matched: usize,
+    pub sport_filtered: u32,
+    pub time_filtered: u32,
+    pub type_filtered: u32,
+    pub tag_filtered: u32,
+    pub completed_routes_filtered: u32,
+    pub duration_filtered: u32,
+    pub unknown_routes: u32,
+    pub missing_distance: u32,
+}
+
+impl FilterSummary {
+    #[must_use]
+    pub fn new(stats: &FilterStats, fetched: usize, matched: usize) -> Self {
+        FilterSummary {
+            fetched,
+            matched,
+            sport_filtered: stats.sport_filtered,
+            time_filtered: stats.time_filtered,
+            type_filtered: stats.type_filtered,
+            tag_filtered: stats.tag_filtered,
+            completed_routes_filtered: stats.completed_routes_filtered,
+            duration_filtered: stats.duration_filtered,
+            unknown_routes: stats.unknown_routes,
+            missing_distance: stats.missing_distance,
+        }
+    }
+}
+
+/// Everything about a search except its events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchSummary {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub zwift_score: u32,
+    pub category: String,
+    pub filter_stats: FilterSummary,
+}
+
+/// A whole search, as written by `--format json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchOutput {
+    #[serde(flatten)]
+    pub summary: SearchSummary,
+    pub events: Vec<EventRecord>,
+}
+
+impl SearchOutput {
+    /// Build the output for `events` that matched out of `fetched`
+    #[must_use]
+    pub fn new(
+        events: &[ZwiftEvent],
+        zwift_score: u32,
+        stats: &FilterStats,
+        fetched: usize,
+    ) -> Self {
+        SearchOutput {
+            summary: SearchSummary {
+                schema_version: SCHEMA_VERSION,
+                generated_at: Utc::now(),
+                zwift_score,
+                category: get_category_from_score(zwift_score).to_string(),
+                filter_stats: FilterSummary::new(stats, fetched, events.len()),
+            },
+            events: events
+                .iter()
+                .map(|event| event_record(event, zwift_score))
+                .collect(),
+        }
+    }
+}
+
+/// One NDJSON line
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum NdjsonLine<'a> {
+    Event(&'a EventRecord),
+    Summary(&'a SearchSummary),
+}
+
+/// Event page on zwift.com
+#[must_use]
+pub fn event_url(event_id: u64) -> String {
+    format!("https://www.zwift.com/events/view/{event_id}")
+}
+
+/// Describe `event` for a rider with `zwift_score`
+///
+/// Distance and climbing follow the table: the rider's subgroup laps (or
+/// distance) times the route, plus the lead-in from the subgroup's start.
+#[must_use]
+pub fn event_record(event: &ZwiftEvent, zwift_score: u32) -> EventRecord {
+    let subgroup = find_user_subgroup(event, zwift_score);
+    let stated_distance_km = subgroup
+        .and_then(|sg| sg.distance_in_meters)
+        .or(event.distance_in_meters)
+        .filter(|&d| d > 0.0)
+        .map(|d| d / METERS_PER_KILOMETER);
+
+    let subgroup_laps = subgroup.and_then(|sg| sg.laps).filter(|&laps| laps > 0);
+
+    let route = event.route_id.map(|route_id| {
+        let start_location = subgroup.and_then(|sg| sg.start_location);
+        match get_route_data_for_start(route_id, start_location) {
+            Some(data) => {
+                let laps = subgroup_laps.unwrap_or_else(|| {
+                    stated_distance_km.map_or(1, |km| laps_for_distance(km, data.distance_km))
+                });
+                RouteRecord {
+                    route_id,
+                    known: true,
+                    name: Some(data.name.to_string()),
+                    world: Some(data.world.to_string()),
+                    surface: Some(data.surface.to_string()),
+                    lap_distance_km: Some(data.distance_km),
+                    lap_elevation_m: Some(data.elevation_m),
+                    lead_in_km: Some(data.lead_in_distance_km),
+                    laps,
+                    low_confidence: is_low_confidence_route(route_id),
+                }
+            }
+            None => RouteRecord {
+                route_id,
+                known: false,
+                name: event.route.clone(),
+                world: None,
+                surface: None,
+                lap_distance_km: None,
+                lap_elevation_m: None,
+                lead_in_km: None,
+                laps: subgroup_laps.unwrap_or(1),
+                low_confidence: false,
+            },
+        }
+    });
+
+    let (distance_km, elevation_m) = match &route {
+        Some(RouteRecord {
+            lap_distance_km: Some(lap_km),
+            lap_elevation_m: Some(lap_elevation_m),
+            lead_in_km,
+            laps,
+            ..
+        }) => {
+            let ridden_km = if subgroup_laps.is_some() {
+                lap_km * f64::from(*laps)
+            } else {
+                stated_distance_km.unwrap_or(lap_km * f64::from(*laps))
+            };
+            (
+                Some(ridden_km + lead_in_km.unwrap_or(0.0)),
+                Some(lap_elevation_m * laps),
+            )
+        }
+        _ => (stated_distance_km, None),
+    };
+
+    let estimated_minutes = estimate_event_duration(event, zwift_score);
+    EventRecord {
+        id: event.id,
+        name: event.name.clone(),
+        event_type: event.event_type.clone(),
+        start: event.event_start,
+        url: event_url(event.id),
+        estimated_minutes,
+        estimated_end: estimated_minutes
+            .map(|minutes| event.event_start + chrono::Duration::minutes(i64::from(minutes))),
+        distance_km,
+        elevation_m,
+        route,
+        subgroup: subgroup.map(|sg| SubgroupRecord {
+            id: sg.id,
+            name: sg.name.clone(),
+            laps: sg.laps,
+            distance_km: sg
+                .distance_in_meters
+                .filter(|&d| d > 0.0)
+                .map(|d| d / METERS_PER_KILOMETER),
+            start_location: sg.start_location,
+            start: sg.event_subgroup_start,
+        }),
+        tags: event.tags.clone(),
+    }
+}
+
+/// Whole laps of a `lap_km` route closest to `distance_km` (at least one)
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn laps_for_distance(distance_km: f64, lap_km: f64) -> u32 {
+    if lap_km <= 0.0 {
+        return 1;
+    }
+    ((distance_km / lap_km).round() as u32).max(1)
+}
+
+/// Write a search in a machine-readable `format`
+///
+/// # Errors
+///
+/// Returns an error for [`OutputFormat::Table`], which isn't written here,
+/// or if writing fails.
+pub fn write_output(
+    out: &mut impl Write,
+    format: OutputFormat,
+    output: &SearchOutput,
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => anyhow::bail!("the table format is printed by event_display"),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, output)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for event in &output.events {
+                serde_json::to_writer(&mut *out, &NdjsonLine::Event(event))?;
+                writeln!(out)?;
+            }
+            serde_json::to_writer(&mut *out, &NdjsonLine::Summary(&output.summary))?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_csv(out, &output.events)?,
+    }
+    Ok(())
+}
+
+fn write_csv(out: &mut impl Write, events: &[EventRecord]) -> Result<()> {
+    writeln!(out, "{}", CSV_COLUMNS.join(","))?;
+    for event in events {
+        let route = event.route.as_ref();
+        let fields = [
+            event.id.to_string(),
+            event.name.clone(),
+            event.event_type.clone(),
+            event.start.to_rfc3339(),
+            event.url.clone(),
+            optional(event.estimated_minutes),
+            optional(event.estimated_end.map(|end| end.to_rfc3339())),
+            optional(event.distance_km.map(|km| format!("{km:.2}"))),
+            optional(event.elevation_m),
+            optional(route.map(|r| r.route_id)),
+            optional(route.and_then(|r| r.name.clone())),
+            optional(route.and_then(|r| r.world.clone())),
+            optional(route.map(|r| r.laps)),
+            optional(event.subgroup.as_ref().map(|sg| sg.name.clone())),
+            route.is_some_and(|r| r.low_confidence).to_string(),
+            event.tags.join(";"),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quote a CSV field when it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EventSubGroup;
+
+    fn sample_output() -> SearchOutput {
+        let event = ZwiftEvent {
+            id: 4_567_890,
+            name: "Sunday Race, \"Fast\"".to_string(),
+            event_start: "2026-10-18T09:00:00Z".parse().unwrap(),
+            event_type: "RACE".to_string(),
+            distance_in_meters: Some(0.0),
+            duration_in_minutes: None,
+            duration_in_seconds: None,
+            route_id: Some(999_999_001),
+            route: Some("Mystery Loop".to_string()),
+            description: None,
+            category_enforcement: false,
+            event_sub_groups: vec![EventSubGroup {
+                id: 7,
+                name: "D".to_string(),
+                route_id: None,
+                distance_in_meters: Some(24_000.0),
+                duration_in_minutes: None,
+                category_enforcement: None,
+                range_access_label: None,
+                laps: None,
+                start_location: None,
+                event_subgroup_start: None,
+            }],
+            sport: "CYCLING".to_string(),
+            tags: vec!["ranked".to_string(), "zracing".to_string()],
+        };
+        let stats = FilterStats {
+            time_filtered: 3,
+            duration_filtered: 5,
+            ..FilterStats::default()
+        };
+        SearchOutput::new(&[event], 195, &stats, 9)
+    }
+
+    #[test]
+    fn test_event_record_for_unknown_route() {
+        let output = sample_output();
+        let record = &output.events[0];
+        assert_eq!(record.url, "https://www.zwift.com/events/view/4567890");
+        assert_eq!(record.distance_km, Some(24.0));
+        assert_eq!(record.elevation_m, None);
+        let route = record.route.as_ref().unwrap();
+        assert!(!route.known);
+        assert_eq!(route.name.as_deref(), Some("Mystery Loop"));
+        assert_eq!(record.subgroup.as_ref().unwrap().name, "D");
+        let minutes = record.estimated_minutes.unwrap();
+        assert_eq!(
+            record.estimated_end.unwrap() - record.start,
+            chrono::Duration::minutes(i64::from(minutes))
+        );
+        assert_eq!(output.summary.filter_stats.fetched, 9);
+        assert_eq!(output.summary.filter_stats.matched, 1);
+        assert_eq!(output.summary.category, "D");
+    }
+
+    #[test]
+    fn test_json_and_ndjson_output() {
+        let output = sample_output();
+
+        let mut json = Vec::new();
+        write_output(&mut json, OutputFormat::Json, &output).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["events"][0]["id"], 4_567_890);
+        assert_eq!(value["events"][0]["route"]["known"], false);
+        assert_eq!(value["filter_stats"]["duration_filtered"], 5);
+
+        let mut ndjson = Vec::new();
+        write_output(&mut ndjson, OutputFormat::Ndjson, &output).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["record"], "event");
+        assert_eq!(lines[0]["name"], "Sunday Race, \"Fast\"");
+        assert_eq!(lines[1]["record"], "summary");
+        assert_eq!(lines[1]["filter_stats"]["time_filtered"], 3);
+    }
+
+    #[test]
+    fn test_csv_output() {
+        let mut csv = Vec::new();
+        write_output(&mut csv, OutputFormat::Csv, &sample_output()).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), CSV_COLUMNS.join(","));
+        let row = lines.next().unwrap();
+        assert!(row
+            .starts_with("4567890,\"Sunday Race, \"\"Fast\"\"\",RACE,2026-10-18T09:00:00+00:00,"));
+        assert!(row.ends_with(",999999001,Mystery Loop,,1,D,false,ranked;zracing"));
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("JSON".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert_eq!("jsonl".parse::<OutputFormat>(), Ok(OutputFormat::Ndjson));
+        assert!("xml".parse::<OutputFormat>().is_err());
+        assert!(!OutputFormat::Table.is_machine_readable());
+        assert!(OutputFormat::Csv.is_machine_readable());
+    }
+}
@@ -3,7 +3,7 @@
 //! This module contains predicates and filtering functions for Zwift events
 
 use crate::database::Database;
-use crate::models::ZwiftEvent;
+use crate::models::{EventSubGroup, ZwiftEvent};
 use chrono::{DateTime, Utc};
 
 /// Statistics tracking what was filtered out
@@ -109,6 +109,37 @@ pub fn filter_by_excluded_tags(events: &mut Vec<ZwiftEvent>, exclude_tags: &[Str
     (pre_count - events.len()) as u32
 }
 
+/// Count events on routes that aren't in the database
+pub fn count_unknown_routes(events: &[ZwiftEvent]) -> u32 {
+    events
+        .iter()
+        .filter(|event| {
+            event
+                .route_id
+                .is_some_and(|route_id| crate::estimation::get_route_data(route_id).is_none())
+        })
+        .count() as u32
+}
+
+/// Count events with no distance from the event, its subgroups or a known route
+pub fn count_missing_distance(events: &[ZwiftEvent]) -> u32 {
+    events
+        .iter()
+        .filter(|event| {
+            let has_distance = |distance: Option<f64>| distance.is_some_and(|d| d > 0.0);
+            !has_distance(event.distance_in_meters)
+                && !event
+                    .event_sub_groups
+                    .iter()
+                    .any(|subgroup| has_distance(subgroup.distance_in_meters))
+                && event
+                    .route_id
+                    .and_then(crate::estimation::get_route_data)
+                    .is_none()
+        })
+        .count() as u32
+}
+
 /// Filter to show only new/uncompleted routes
 pub fn filter_new_routes_only(events: &mut Vec<ZwiftEvent>) -> u32 {
     let db = Database::new().ok();
@@ -137,10 +168,48 @@ pub fn event_matches_duration(
     zwift_score: u32,
 ) -> bool {
     use crate::category::{category_matches_subgroup, get_category_from_score};
+
+    let within_tolerance =
+        |duration: u32| (duration as i32 - target_duration as i32).abs() <= tolerance as i32;
+
+    if let Some(duration) = estimate_from_event_data(event, zwift_score) {
+        return within_tolerance(duration);
+    }
+
+    // Check subgroups if main event has no distance/duration
+    let user_category = get_category_from_score(zwift_score);
+    event.event_sub_groups.iter().any(|subgroup| {
+        // Only the user's category counts
+        category_matches_subgroup(user_category, &subgroup.name)
+            && estimate_subgroup_duration(event, subgroup, zwift_score)
+                .is_some_and(within_tolerance)
+    })
+}
+
+/// Estimate how long `event` takes a rider with `zwift_score`, in minutes
+///
+/// Uses the same sources as [`event_matches_duration`]: a fixed duration,
+/// the route and the rider's subgroup, the event distance, the description
+/// and finally the event name.
+pub fn estimate_event_duration(event: &ZwiftEvent, zwift_score: u32) -> Option<u32> {
+    use crate::category::{category_matches_subgroup, get_category_from_score};
+
+    estimate_from_event_data(event, zwift_score).or_else(|| {
+        let user_category = get_category_from_score(zwift_score);
+        event
+            .event_sub_groups
+            .iter()
+            .filter(|subgroup| category_matches_subgroup(user_category, &subgroup.name))
+            .find_map(|subgroup| estimate_subgroup_duration(event, subgroup, zwift_score))
+    })
+}
+
+/// Estimate from the event's duration, route, distance, description or name
+fn estimate_from_event_data(event: &ZwiftEvent, zwift_score: u32) -> Option<u32> {
     use crate::constants::METERS_PER_KILOMETER;
     use crate::duration_estimation::estimate_duration_for_category;
     use crate::estimation::{
-        estimate_duration_from_route_id, estimate_duration_with_distance, get_route_data,
+        estimate_duration_from_route_id, estimate_duration_with_distance, get_route_data_for_start,
     };
     use crate::event_analysis::find_user_subgroup;
     use crate::models::is_racing_score_event;
@@ -152,9 +221,8 @@ pub fn event_matches_duration(
         .filter(|&d| d > 0)
         .or_else(|| event.duration_in_seconds.map(|s| s / 60).filter(|&d| d > 0));
 
-    if let Some(duration) = duration_minutes {
-        let diff = (duration as i32 - target_duration as i32).abs();
-        return diff <= tolerance as i32;
+    if duration_minutes.is_some() {
+        return duration_minutes;
     }
 
     // PRIMARY METHOD: Use route_id with actual distance for most accurate estimation
@@ -166,14 +234,13 @@ pub fn event_matches_duration(
         if let Some(sg) = user_subgroup {
             if let Some(laps) = sg.laps {
                 // We have lap count - calculate based on route distance * laps + lead-in
-                if let Some(route_data) = get_route_data(route_id) {
+                if let Some(route_data) = get_route_data_for_start(route_id, sg.start_location) {
                     let total_distance_km =
                         route_data.lead_in_distance_km + (route_data.distance_km * laps as f64);
                     if let Some(estimated_duration) =
                         estimate_duration_with_distance(route_id, total_distance_km, zwift_score)
                     {
-                        let diff = (estimated_duration as i32 - target_duration as i32).abs();
-                        return diff <= tolerance as i32;
+                        return Some(estimated_duration);
                     }
                 }
             }
@@ -186,19 +253,17 @@ pub fn event_matches_duration(
 
         if let Some(dist_m) = distance_meters.filter(|&d| d > 0.0) {
             let distance_km = dist_m / METERS_PER_KILOMETER;
-            if let Some(estimated_duration) =
-                estimate_duration_with_distance(route_id, distance_km, zwift_score)
-            {
-                let diff = (estimated_duration as i32 - target_duration as i32).abs();
-                return diff <= tolerance as i32;
-            } else {
-                // Unknown route but we have distance - use fallback estimation
-                let route_name = event.route.as_deref().unwrap_or(&event.name);
-                let estimated_duration =
-                    estimate_duration_for_category(distance_km, route_name, zwift_score);
-                let diff = (estimated_duration as i32 - target_duration as i32).abs();
-                return diff <= tolerance as i32;
-            }
+            // Unknown route but we have distance - use fallback estimation
+            return estimate_duration_with_distance(route_id, distance_km, zwift_score).or_else(
+                || {
+                    let route_name = event.route.as_deref().unwrap_or(&event.name);
+                    Some(estimate_duration_for_category(
+                        distance_km,
+                        route_name,
+                        zwift_score,
+                    ))
+                },
+            );
         } else if let Some(estimated_duration) =
             estimate_duration_from_route_id(route_id, zwift_score)
         {
@@ -212,81 +277,75 @@ pub fn event_matches_duration(
                 }
             }
 
-            let diff = (actual_duration as i32 - target_duration as i32).abs();
-            return diff <= tolerance as i32;
+            return Some(actual_duration);
         } else if is_racing_score_event(event) {
             // Racing Score event with route_id but no distance - try parsing description
             if let Some(distance_km) = parse_distance_from_description(&event.description) {
                 if let Some(estimated_duration) =
                     estimate_duration_with_distance(route_id, distance_km, zwift_score)
                 {
-                    let diff = (estimated_duration as i32 - target_duration as i32).abs();
-                    return diff <= tolerance as i32;
+                    return Some(estimated_duration);
                 }
             }
         }
         // If we have a route_id but can't estimate (unknown route with no distance), continue to fallbacks
     }
 
+    let route_name = event.route.as_deref().unwrap_or(&event.name);
+
     // FALLBACK 1: Use provided distance (but not if it's 0.0)
     if let Some(distance) = event.distance_in_meters.filter(|&d| d > 0.0) {
         let distance_km = distance / METERS_PER_KILOMETER;
-        let route_name = event.route.as_deref().unwrap_or(&event.name);
-        let estimated_duration =
-            estimate_duration_for_category(distance_km, route_name, zwift_score);
-        let diff = (estimated_duration as i32 - target_duration as i32).abs();
-        return diff <= tolerance as i32;
+        return Some(estimate_duration_for_category(
+            distance_km,
+            route_name,
+            zwift_score,
+        ));
     }
 
-    // FALLBACK 2: For Racing Score events with distance=0, try to parse from description
+    // FALLBACK 2: For Racing Score events with distance=0, use the rider's category
+    // distance from the description, else any distance the description gives
     if is_racing_score_event(event) {
-        if let Some(distance_km) = parse_distance_from_description(&event.description) {
-            let route_name = event.route.as_deref().unwrap_or(&event.name);
-            let estimated_duration =
-                estimate_duration_for_category(distance_km, route_name, zwift_score);
-            let diff = (estimated_duration as i32 - target_duration as i32).abs();
-            return diff <= tolerance as i32;
+        let category_distance_km = find_user_subgroup(event, zwift_score)
+            .and_then(|sg| sg.distance_in_meters)
+            .filter(|&d| d > 0.0)
+            .map(|d| d / METERS_PER_KILOMETER);
+        if let Some(distance_km) =
+            category_distance_km.or_else(|| parse_distance_from_description(&event.description))
+        {
+            return Some(estimate_duration_for_category(
+                distance_km,
+                route_name,
+                zwift_score,
+            ));
         }
     }
 
     // FALLBACK 3: Try to guess from event name
-    if let Some(distance_km) = estimate_distance_from_name(&event.name) {
-        let estimated_duration =
-            estimate_duration_for_category(distance_km, &event.name, zwift_score);
-        let diff = (estimated_duration as i32 - target_duration as i32).abs();
-        return diff <= tolerance as i32;
-    }
-
-    // Check subgroups if main event has no distance/duration
-    if !event.event_sub_groups.is_empty() {
-        // Find the subgroup that matches user's category
-        let user_category = get_category_from_score(zwift_score);
-
-        // Check if user's category subgroup matches criteria
-        event.event_sub_groups.iter().any(|subgroup| {
-            // Check if this subgroup is for user's category
-            let is_user_category = category_matches_subgroup(user_category, &subgroup.name);
+    estimate_distance_from_name(&event.name)
+        .map(|distance_km| estimate_duration_for_category(distance_km, &event.name, zwift_score))
+}
 
-            if !is_user_category {
-                return false; // Skip other categories
-            }
+/// Estimate from a subgroup's own duration or distance
+fn estimate_subgroup_duration(
+    event: &ZwiftEvent,
+    subgroup: &EventSubGroup,
+    zwift_score: u32,
+) -> Option<u32> {
+    use crate::constants::METERS_PER_KILOMETER;
+    use crate::duration_estimation::estimate_duration_for_category;
 
-            if let Some(duration) = subgroup.duration_in_minutes {
-                let diff = (duration as i32 - target_duration as i32).abs();
-                diff <= tolerance as i32
-            } else if let Some(distance) = subgroup.distance_in_meters.filter(|&d| d > 0.0) {
+    if let Some(duration) = subgroup.duration_in_minutes {
+        Some(duration)
+    } else {
+        subgroup
+            .distance_in_meters
+            .filter(|&d| d > 0.0)
+            .map(|distance| {
                 let distance_km = distance / METERS_PER_KILOMETER;
                 let route_name = event.route.as_deref().unwrap_or(&event.name);
-                let estimated_duration =
-                    estimate_duration_for_category(distance_km, route_name, zwift_score);
-                let diff = (estimated_duration as i32 - target_duration as i32).abs();
-                diff <= tolerance as i32
-            } else {
-                false
-            }
-        })
-    } else {
-        false
+                estimate_duration_for_category(distance_km, route_name, zwift_score)
+            })
     }
 }
 
@@ -327,6 +386,38 @@ mod tests {
         assert_eq!(stats.duration_no_match(), 1);
     }
 
+    #[test]
+    fn test_count_unknown_routes_and_missing_distance() {
+        let known = {
+            let mut e = create_test_event("Bell Lap Race", "CYCLING", "RACE");
+            e.route_id = Some(1258415487);
+            e.distance_in_meters = None;
+            e
+        };
+        let unknown_with_distance = create_test_event("Unknown Route", "CYCLING", "RACE");
+        let unknown_without_distance = {
+            let mut e = create_test_event("Mystery Race", "CYCLING", "RACE");
+            e.route_id = Some(999_999_999);
+            e.distance_in_meters = None;
+            e
+        };
+        let no_route = {
+            let mut e = create_test_event("Group Ride", "CYCLING", "GROUP_RIDE");
+            e.route_id = None;
+            e.distance_in_meters = Some(0.0);
+            e
+        };
+        let events = vec![
+            known,
+            unknown_with_distance,
+            unknown_without_distance,
+            no_route,
+        ];
+
+        assert_eq!(count_unknown_routes(&events), 2);
+        assert_eq!(count_missing_distance(&events), 2);
+    }
+
     #[test]
     fn test_filter_by_sport() {
         let mut events = vec![
@@ -722,6 +813,8 @@ mod tests {
             category_enforcement: None,
             range_access_label: Some("0-199".to_string()),
             laps: None,
+            start_location: None,
+            event_subgroup_start: None,
         }];
 
         assert!(is_racing_score_event(&event));
@@ -0,0 +1,316 @@
+//! Race results captured from Zwift's event results
+//!
+//! Once an event finishes, Zwift lists every finisher of each subgroup
+//! (`/api/race-results/entries?event_subgroup_id=<id>`, a page at a time via
+//! `start` and `limit`):
+//!
+//! ```json
+//! {"entries": [{"profileId": 1106548, "eventSubgroupId": 7,
+//!               "activityData": {"durationInMilliseconds": 2712000},
+//!               "sensorData": {"avgWatts": 231},
+//!               "profileData": {"weightInGrams": 86000}}]}
+//! ```
+//!
+//! A saved payload, or a bare array of entries, is read the same way. The
+//! rider's entry is combined with the archived event (route, laps, category,
+//! date) into a complete [`RaceResult`]. When only the finish time is known,
+//! [`event_result`] fills in the same details from the event alone.
+
+use crate::database::RaceResult;
+use crate::models::{EventSubGroup, ZwiftEvent};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// One finisher in an event subgroup's results
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultEntry {
+    pub profile_id: u64,
+    #[serde(default)]
+    pub event_subgroup_id: Option<u32>,
+    pub activity_data: ActivityData,
+    #[serde(default)]
+    pub sensor_data: Option<SensorData>,
+    #[serde(default)]
+    pub profile_data: Option<ProfileData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityData {
+    pub duration_in_milliseconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensorData {
+    pub avg_watts: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileData {
+    pub weight_in_grams: Option<u32>,
+}
+
+/// Results as served (`{"entries": [...]}`) or saved as a plain list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ResultsPayload {
+    Entries { entries: Vec<ResultEntry> },
+    List(Vec<ResultEntry>),
+}
+
+impl ResultsPayload {
+    #[must_use]
+    pub fn into_entries(self) -> Vec<ResultEntry> {
+        match self {
+            ResultsPayload::Entries { entries } | ResultsPayload::List(entries) => entries,
+        }
+    }
+}
+
+/// Read a saved results payload
+pub fn load_entries(path: &Path) -> Result<Vec<ResultEntry>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let payload: ResultsPayload = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse {} as event results", path.display()))?;
+    Ok(payload.into_entries())
+}
+
+/// Where and how fast the rider finished
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiderFinish {
+    pub subgroup_id: Option<u32>,
+    /// Position within the subgroup, by finish time
+    pub position: u32,
+    /// Number of finishers in the subgroup
+    pub field_size: u32,
+    pub duration_ms: u64,
+    pub avg_power_w: Option<u32>,
+    pub weight_kg: Option<f64>,
+}
+
+/// Find `profile_id`'s finish among the entries
+///
+/// Position and field size count only finishers of the rider's own subgroup.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn find_rider_finish(entries: &[ResultEntry], profile_id: u64) -> Option<RiderFinish> {
+    let rider = entries
+        .iter()
+        .find(|entry| entry.profile_id == profile_id)?;
+    let subgroup: Vec<&ResultEntry> = entries
+        .iter()
+        .filter(|entry| entry.event_subgroup_id == rider.event_subgroup_id)
+        .collect();
+    let ahead = subgroup
+        .iter()
+        .filter(|entry| {
+            entry.activity_data.duration_in_milliseconds
+                < rider.activity_data.duration_in_milliseconds
+        })
+        .count();
+
+    Some(RiderFinish {
+        subgroup_id: rider.event_subgroup_id,
+        position: u32::try_from(ahead + 1).ok()?,
+        field_size: u32::try_from(subgroup.len()).ok()?,
+        duration_ms: rider.activity_data.duration_in_milliseconds,
+        avg_power_w: rider
+            .sensor_data
+            .as_ref()
+            .and_then(|sensors| sensors.avg_watts)
+            .filter(|watts| *watts > 0.0)
+            .map(|watts| watts.round() as u32),
+        weight_kg: rider
+            .profile_data
+            .as_ref()
+            .and_then(|profile| profile.weight_in_grams)
+            .filter(|grams| *grams > 0)
+            .map(|grams| f64::from(grams) / 1000.0),
+    })
+}
+
+/// A result ready to record, with the laps the event rode
+#[derive(Debug, Clone)]
+pub struct CapturedResult {
+    pub result: RaceResult,
+    pub laps: u32,
+}
+
+/// Combine the rider's finish with the event it was ridden in
+///
+/// The route and laps come from the rider's subgroup, falling back to the
+/// event; the category is the subgroup's letter.
+pub fn capture_result(
+    event: &ZwiftEvent,
+    finish: &RiderFinish,
+    zwift_score: u32,
+) -> Result<CapturedResult> {
+    let subgroup = finish.subgroup_id.and_then(|id| {
+        event
+            .event_sub_groups
+            .iter()
+            .find(|subgroup| subgroup.id == id)
+    });
+    let mut captured = event_result(event, subgroup, zwift_score)?;
+    let result = &mut captured.result;
+    result.actual_minutes = u32::try_from((finish.duration_ms + 30_000) / 60_000)?;
+    result.position = Some(finish.position);
+    result.field_size = Some(finish.field_size);
+    result.avg_power_w = finish.avg_power_w;
+    result.weight_kg = finish.weight_kg;
+    result.source = Some("zwift".to_string());
+    Ok(captured)
+}
+
+/// A result for `event` ridden in `subgroup`, still without a finish time
+///
+/// Fills in the route, laps, category, date and event ID; the route and laps
+/// come from the subgroup when it has its own, otherwise from the event.
+pub fn event_result(
+    event: &ZwiftEvent,
+    subgroup: Option<&EventSubGroup>,
+    zwift_score: u32,
+) -> Result<CapturedResult> {
+    let route_id = subgroup
+        .and_then(|subgroup| subgroup.route_id)
+        .or(event.route_id)
+        .ok_or_else(|| anyhow!("Event {} has no route", event.id))?;
+    let laps = subgroup
+        .and_then(|subgroup| subgroup.laps)
+        .unwrap_or(1)
+        .max(1);
+
+    let result = RaceResult {
+        route_id,
+        event_name: event.name.clone(),
+        zwift_score,
+        race_date: event.event_start.format("%Y-%m-%d").to_string(),
+        category: subgroup.and_then(subgroup_category),
+        event_id: Some(event.id),
+        ..Default::default()
+    };
+    Ok(CapturedResult { result, laps })
+}
+
+/// Finish time typed by a rider, in whole minutes
+///
+/// Accepts `1:02:03`, `45:12` or plain minutes (`45`); seconds are rounded.
+#[must_use]
+pub fn parse_finish_time(text: &str) -> Option<u32> {
+    let parts: Vec<u32> = text
+        .trim()
+        .split(':')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let seconds = match parts.as_slice() {
+        [mins] => mins * 60,
+        [mins, secs] if *secs < 60 => mins * 60 + secs,
+        [hours, mins, secs] if *mins < 60 && *secs < 60 => hours * 3600 + mins * 60 + secs,
+        _ => return None,
+    };
+    Some((seconds + 30) / 60).filter(|minutes| *minutes > 0)
+}
+
+/// Category letter of a subgroup named like "D", "Cat D" or "Category D"
+fn subgroup_category(subgroup: &EventSubGroup) -> Option<String> {
+    let last = subgroup.name.split_whitespace().last()?;
+    let letter = last.chars().next()?.to_ascii_uppercase();
+    (last.len() == 1 && ('A'..='E').contains(&letter)).then(|| letter.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries() -> Vec<ResultEntry> {
+        let payload: ResultsPayload = serde_json::from_value(json!({
+            "entries": [
+                {"profileId": 1, "eventSubgroupId": 7, "activityData": {"durationInMilliseconds": 2_650_000}},
+                {"profileId": 2, "eventSubgroupId": 8, "activityData": {"durationInMilliseconds": 2_000_000}},
+                {"profileId": 1_106_548, "eventSubgroupId": 7,
+                 "activityData": {"durationInMilliseconds": 2_712_000},
+                 "sensorData": {"avgWatts": 231.4},
+                 "profileData": {"weightInGrams": 86_000}},
+                {"profileId": 3, "eventSubgroupId": 7, "activityData": {"durationInMilliseconds": 2_800_000}}
+            ]
+        }))
+        .unwrap();
+        payload.into_entries()
+    }
+
+    fn event() -> ZwiftEvent {
+        serde_json::from_value(json!({
+            "id": 4_567_890,
+            "name": "Stage 1: Downtown Dolphin Race",
+            "eventStart": "2026-10-19T17:00:00Z",
+            "eventType": "RACE",
+            "routeId": 1,
+            "eventSubgroups": [
+                {"id": 7, "name": "Cat D", "routeId": 2_698_009_951_u32, "laps": 2},
+                {"id": 8, "name": "C"}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rider_finish_counts_own_subgroup() {
+        let finish = find_rider_finish(&entries(), 1_106_548).unwrap();
+        assert_eq!(finish.subgroup_id, Some(7));
+        assert_eq!(finish.position, 2);
+        assert_eq!(finish.field_size, 3);
+        assert_eq!(finish.avg_power_w, Some(231));
+        assert_eq!(finish.weight_kg, Some(86.0));
+        assert!(find_rider_finish(&entries(), 99).is_none());
+    }
+
+    #[test]
+    fn test_capture_uses_subgroup_route_and_laps() {
+        let finish = find_rider_finish(&entries(), 1_106_548).unwrap();
+        let captured = capture_result(&event(), &finish, 195).unwrap();
+        assert_eq!(captured.laps, 2);
+        let result = captured.result;
+        assert_eq!(result.route_id, 2_698_009_951);
+        // 45m12s rounds to 45 minutes
+        assert_eq!(result.actual_minutes, 45);
+        assert_eq!(result.race_date, "2026-10-19");
+        assert_eq!(result.category.as_deref(), Some("D"));
+        assert_eq!(result.event_id, Some(4_567_890));
+        assert_eq!(result.source.as_deref(), Some("zwift"));
+
+        // Subgroup without its own route or laps uses the event's
+        let finish = find_rider_finish(&entries(), 2).unwrap();
+        let captured = capture_result(&event(), &finish, 250).unwrap();
+        assert_eq!((captured.result.route_id, captured.laps), (1, 1));
+        assert_eq!(captured.result.category.as_deref(), Some("C"));
+    }
+
+    #[test]
+    fn test_parse_finish_time() {
+        assert_eq!(parse_finish_time("45"), Some(45));
+        assert_eq!(parse_finish_time("45:29"), Some(45));
+        assert_eq!(parse_finish_time(" 45:30 "), Some(46));
+        assert_eq!(parse_finish_time("1:02:03"), Some(62));
+        assert_eq!(parse_finish_time("45:75"), None);
+        assert_eq!(parse_finish_time("0"), None);
+        assert_eq!(parse_finish_time("fast"), None);
+    }
+
+    #[test]
+    fn test_plain_list_payload() {
+        let payload: ResultsPayload = serde_json::from_value(json!([
+            {"profileId": 5, "activityData": {"durationInMilliseconds": 1_800_000}}
+        ]))
+        .unwrap();
+        let entries = payload.into_entries();
+        let finish = find_rider_finish(&entries, 5).unwrap();
+        assert_eq!((finish.position, finish.field_size), (1, 1));
+        assert_eq!(finish.subgroup_id, None);
+    }
+}
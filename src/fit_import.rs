@@ -0,0 +1,371 @@
+//! Import of activity files in Garmin's FIT format
+//!
+//! Zwift, Garmin Connect and most head units export rides as FIT files. Garmin
+//! Connect downloads wrap the FIT file in a ZIP archive, so the `PK\x03\x04`
+//! header is checked first and the archive unwrapped before decoding. Only the
+//! session summary is used: duration, distance, elevation, power and start time.
+
+use crate::database::{RaceResult, RouteData};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use fitparser::profile::MesgNum;
+use fitparser::Value;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Magic bytes at the start of a ZIP archive (Garmin Connect downloads)
+pub const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Relative distance difference still accepted when matching a route
+pub const ROUTE_MATCH_TOLERANCE: f64 = 0.05;
+
+/// Activity type labels used for virtual rides by FIT files and activity exports
+const VIRTUAL_RIDE_TYPES: [&str; 3] = ["virtual_activity", "virtual_ride", "virtual_cycling"];
+
+/// Session summary of a single FIT activity
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FitActivity {
+    pub start_time: Option<DateTime<Utc>>,
+    pub duration_secs: f64,
+    pub distance_m: f64,
+    pub ascent_m: Option<u32>,
+    pub avg_power_w: Option<u32>,
+    pub np_power_w: Option<u32>,
+    pub sport: Option<String>,
+    pub sub_sport: Option<String>,
+    pub manufacturer: Option<String>,
+}
+
+impl FitActivity {
+    /// Whether this is a ride on a trainer in a virtual world
+    ///
+    /// Recognises the FIT `virtual_activity` sub sport, the `virtual_ride` and
+    /// `virtual_cycling` labels used by activity exports, and files written by
+    /// Zwift itself, which often only say `cycling`.
+    #[must_use]
+    pub fn is_virtual_ride(&self) -> bool {
+        let virtual_type = [&self.sport, &self.sub_sport]
+            .into_iter()
+            .flatten()
+            .any(|label| VIRTUAL_RIDE_TYPES.contains(&label.to_lowercase().as_str()));
+        let from_zwift = self
+            .manufacturer
+            .as_deref()
+            .is_some_and(|m| m.eq_ignore_ascii_case("zwift"));
+        virtual_type || (from_zwift && self.sport.as_deref().is_none_or(|s| s == "cycling"))
+    }
+
+    #[must_use]
+    pub fn distance_km(&self) -> f64 {
+        self.distance_m / 1000.0
+    }
+
+    /// Duration rounded to whole minutes, as stored in race results
+    #[must_use]
+    pub fn duration_minutes(&self) -> u32 {
+        (self.duration_secs / 60.0).round() as u32
+    }
+
+    /// Build a race result for this activity on the given route
+    #[must_use]
+    pub fn to_race_result(&self, route_id: u32, event_name: &str, zwift_score: u32) -> RaceResult {
+        let race_date = self
+            .start_time
+            .map_or_else(Local::now, |start| start.with_timezone(&Local))
+            .format("%Y-%m-%d")
+            .to_string();
+        RaceResult {
+            route_id,
+            event_name: event_name.to_string(),
+            actual_minutes: self.duration_minutes(),
+            zwift_score,
+            race_date,
+            avg_power_w: self.avg_power_w,
+            np_power_w: self.np_power_w,
+            source: Some("fit".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Return the FIT payload, unwrapping it first if it is ZIP-compressed
+pub fn unwrap_fit_bytes(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !bytes.starts_with(ZIP_MAGIC) {
+        return Ok(bytes);
+    }
+
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open ZIP-wrapped FIT file")?;
+    let fit_index = (0..archive.len())
+        .find(|&i| {
+            archive
+                .by_index(i)
+                .is_ok_and(|entry| entry.name().to_lowercase().ends_with(".fit"))
+        })
+        .context("ZIP archive does not contain a .fit file")?;
+
+    let mut entry = archive.by_index(fit_index)?;
+    let mut fit = Vec::new();
+    entry
+        .read_to_end(&mut fit)
+        .with_context(|| format!("Failed to extract {}", entry.name()))?;
+    Ok(fit)
+}
+
+/// Decode a FIT file (plain or ZIP-wrapped) into its session summary
+pub fn parse_fit(bytes: Vec<u8>) -> Result<FitActivity> {
+    let fit = unwrap_fit_bytes(bytes)?;
+    let records = fitparser::from_bytes(&fit).context("Failed to decode FIT data")?;
+
+    let mut activity = FitActivity::default();
+    let mut has_session = false;
+    for record in &records {
+        match record.kind() {
+            MesgNum::FileId => {
+                for field in record.fields() {
+                    if field.name() == "manufacturer" {
+                        activity.manufacturer = value_as_string(field.value());
+                    }
+                }
+            }
+            // Multi-sport files have several sessions; the first one is the ride
+            MesgNum::Session if !has_session => {
+                has_session = true;
+                apply_session_fields(&mut activity, record);
+            }
+            _ => {}
+        }
+    }
+
+    if !has_session {
+        bail!("FIT file has no session summary");
+    }
+    Ok(activity)
+}
+
+/// Read and decode a FIT file from disk
+pub fn read_fit_file(path: &Path) -> Result<FitActivity> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_fit(bytes).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn apply_session_fields(activity: &mut FitActivity, session: &fitparser::FitDataRecord) {
+    let mut elapsed_secs = None;
+    let mut timer_secs = None;
+    for field in session.fields() {
+        let value = field.value();
+        match field.name() {
+            "start_time" => {
+                if let Value::Timestamp(start) = value {
+                    activity.start_time = Some(start.with_timezone(&Utc));
+                }
+            }
+            "total_elapsed_time" => elapsed_secs = value_as_f64(value),
+            "total_timer_time" => timer_secs = value_as_f64(value),
+            "total_distance" => activity.distance_m = value_as_f64(value).unwrap_or(0.0),
+            "total_ascent" => activity.ascent_m = value_as_u32(value),
+            "avg_power" => activity.avg_power_w = value_as_u32(value),
+            "normalized_power" => activity.np_power_w = value_as_u32(value),
+            "sport" => activity.sport = value_as_string(value),
+            "sub_sport" => activity.sub_sport = value_as_string(value),
+            _ => {}
+        }
+    }
+    // Timer time excludes auto-pause; fall back to wall-clock time without it
+    activity.duration_secs = timer_secs.or(elapsed_secs).unwrap_or(0.0);
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    value.clone().try_into().ok()
+}
+
+fn value_as_u32(value: &Value) -> Option<u32> {
+    value_as_f64(value).map(|v| v.round() as u32)
+}
+
+fn value_as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Invalid => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// Find the route whose length (including lead-in) is closest to the ridden distance
+///
+/// Returns `None` when no route is within [`ROUTE_MATCH_TOLERANCE`]; multi-lap
+/// races therefore need an explicit route.
+#[must_use]
+pub fn match_route_by_distance(routes: &[RouteData], distance_km: f64) -> Option<&RouteData> {
+    routes
+        .iter()
+        .filter(|route| route.distance_km > 0.0)
+        .map(|route| {
+            let expected = route.distance_km + route.lead_in_distance_km;
+            (route, (distance_km - expected).abs() / expected)
+        })
+        .filter(|(_, error)| *error <= ROUTE_MATCH_TOLERANCE)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(route, _)| route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// FIT epoch (1989-12-31T00:00:00Z) as a Unix timestamp
+    const FIT_EPOCH: i64 = 631_065_600;
+
+    fn fit_crc(data: &[u8]) -> u16 {
+        const TABLE: [u16; 16] = [
+            0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800,
+            0xB401, 0x5000, 0x9C01, 0x8801, 0x4400,
+        ];
+        data.iter().fold(0u16, |mut crc, &byte| {
+            let tmp = TABLE[usize::from(crc & 0xF)];
+            crc = (crc >> 4) & 0x0FFF;
+            crc = crc ^ tmp ^ TABLE[usize::from(byte & 0xF)];
+            let tmp = TABLE[usize::from(crc & 0xF)];
+            crc = (crc >> 4) & 0x0FFF;
+            crc ^ tmp ^ TABLE[usize::from(byte >> 4)]
+        })
+    }
+
+    /// Minimal FIT file with a `file_id` and a single session message
+    fn build_fit(start_unix: i64, sport: u8, sub_sport: u8, manufacturer: u16) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        // file_id definition (local 0, global 0): manufacturer uint16
+        data.extend([0x40, 0, 0, 0, 0, 1, 1, 2, 0x84]);
+        data.push(0x00);
+        data.extend(manufacturer.to_le_bytes());
+
+        // session definition (local 1, global 18)
+        let fields: [(u8, u8, u8); 9] = [
+            (2, 4, 0x86),  // start_time
+            (5, 1, 0x00),  // sport
+            (6, 1, 0x00),  // sub_sport
+            (7, 4, 0x86),  // total_elapsed_time (ms)
+            (8, 4, 0x86),  // total_timer_time (ms)
+            (9, 4, 0x86),  // total_distance (cm)
+            (20, 2, 0x84), // avg_power
+            (22, 2, 0x84), // total_ascent
+            (34, 2, 0x84), // normalized_power
+        ];
+        data.extend([0x41, 0, 0, 18, 0, u8::try_from(fields.len()).unwrap()]);
+        for (num, size, base) in fields {
+            data.extend([num, size, base]);
+        }
+        data.push(0x01);
+        data.extend(u32::try_from(start_unix - FIT_EPOCH).unwrap().to_le_bytes());
+        data.extend([sport, sub_sport]);
+        data.extend(2_760_000u32.to_le_bytes());
+        data.extend(2_730_000u32.to_le_bytes());
+        data.extend(2_245_000u32.to_le_bytes());
+        data.extend(215u16.to_le_bytes());
+        data.extend(301u16.to_le_bytes());
+        data.extend(232u16.to_le_bytes());
+
+        let mut file = vec![14, 0x20];
+        file.extend(2132u16.to_le_bytes());
+        file.extend(u32::try_from(data.len()).unwrap().to_le_bytes());
+        file.extend(b".FIT");
+        let header_crc = fit_crc(&file);
+        file.extend(header_crc.to_le_bytes());
+        file.extend(&data);
+        let crc = fit_crc(&file);
+        file.extend(crc.to_le_bytes());
+        file
+    }
+
+    fn zip_wrap(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(contents).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_parse_fit_session() {
+        // cycling / virtual_activity, manufacturer garmin
+        let activity = parse_fit(build_fit(1_731_067_200, 2, 58, 1)).unwrap();
+
+        assert_eq!(
+            activity.start_time.unwrap().to_rfc3339(),
+            "2024-11-08T12:00:00+00:00"
+        );
+        assert!((activity.duration_secs - 2730.0).abs() < 0.01);
+        assert!((activity.distance_km() - 22.45).abs() < 0.001);
+        assert_eq!(activity.ascent_m, Some(301));
+        assert_eq!(activity.avg_power_w, Some(215));
+        assert_eq!(activity.np_power_w, Some(232));
+        assert_eq!(activity.sport.as_deref(), Some("cycling"));
+        assert_eq!(activity.sub_sport.as_deref(), Some("virtual_activity"));
+        assert!(activity.is_virtual_ride());
+
+        let result = activity.to_race_result(42, "Evening Race", 210);
+        assert_eq!(result.actual_minutes, 46);
+        assert_eq!(result.np_power_w, Some(232));
+        assert_eq!(result.source.as_deref(), Some("fit"));
+    }
+
+    #[test]
+    fn test_parse_zip_wrapped_fit() {
+        let fit = build_fit(1_731_067_200, 2, 58, 1);
+        let zipped = zip_wrap("12345678_ACTIVITY.fit", &fit);
+        assert!(zipped.starts_with(ZIP_MAGIC));
+
+        let activity = parse_fit(zipped).unwrap();
+        assert_eq!(activity.np_power_w, Some(232));
+
+        let no_fit = zip_wrap("readme.txt", b"hello");
+        assert!(parse_fit(no_fit).is_err());
+    }
+
+    #[test]
+    fn test_virtual_ride_detection() {
+        // Outdoor ride: cycling / road
+        let outdoor = parse_fit(build_fit(1_731_067_200, 2, 7, 1)).unwrap();
+        assert!(!outdoor.is_virtual_ride());
+
+        // Zwift's own files only say cycling / generic
+        let zwift = parse_fit(build_fit(1_731_067_200, 2, 0, 260)).unwrap();
+        assert_eq!(zwift.manufacturer.as_deref(), Some("zwift"));
+        assert!(zwift.is_virtual_ride());
+
+        let exported = FitActivity {
+            sport: Some("Virtual_Ride".to_string()),
+            ..Default::default()
+        };
+        assert!(exported.is_virtual_ride());
+    }
+
+    #[test]
+    fn test_match_route_by_distance() {
+        let route = |route_id, distance_km, lead_in_distance_km| RouteData {
+            route_id,
+            distance_km,
+            elevation_m: 100,
+            name: format!("Route {route_id}"),
+            world: "Watopia".to_string(),
+            surface: "road".to_string(),
+            lead_in_distance_km,
+            ..Default::default()
+        };
+        let routes = vec![route(1, 20.0, 2.0), route(2, 23.5, 0.3), route(3, 0.0, 0.0)];
+
+        assert_eq!(
+            match_route_by_distance(&routes, 22.45).map(|r| r.route_id),
+            Some(1)
+        );
+        assert_eq!(
+            match_route_by_distance(&routes, 23.0).map(|r| r.route_id),
+            Some(2)
+        );
+        assert!(match_route_by_distance(&routes, 45.0).is_none());
+    }
+}
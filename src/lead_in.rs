@@ -0,0 +1,163 @@
+//! Lead-in distance for events that start from a non-default pen
+//!
+//! A route's stored lead-in is measured from its default start line. Event
+//! subgroups can be placed at another start location (`startLocation` in the
+//! events API), which can move the lead-in by a kilometre or more. When the
+//! start lines of both locations are known, lie on the same road, and both
+//! the road's length and the direction the route rides it are known, the
+//! difference in road position is converted to distance and applied to the
+//! default lead-in. Otherwise the stored lead-in is used.
+
+use crate::database::{Database, RouteData, StartLine};
+use anyhow::Result;
+
+/// Start location of a route's own start line in the events API
+pub const DEFAULT_START_LOCATION: u32 = 1;
+
+/// Zwift road positions ("road time") span this many units from one end of a road to the other
+///
+/// Road time runs from 5,000 to 1,005,000; the start lines in the
+/// zwift-offline export (`data/zwift_offline_export/start_lines.json`) all
+/// fall between 5,069 and 1,001,990.
+const ROAD_TIME_SPAN: f64 = 1_000_000.0;
+
+/// Lead-in from `start_location`, falling back to the route's stored lead-in
+///
+/// Returns the stored value for the default start, or when the start lines,
+/// road length or riding direction needed for the calculation are missing.
+pub fn lead_in_for_start_location(
+    db: &Database,
+    route: &RouteData,
+    start_location: Option<u32>,
+) -> Result<f64> {
+    let stored = route.lead_in_distance_km;
+    let Some(start_location) = start_location.filter(|loc| *loc != DEFAULT_START_LOCATION) else {
+        return Ok(stored);
+    };
+    let (Some(default_start), Some(pen_start)) = (
+        db.get_start_line(route.route_id, DEFAULT_START_LOCATION)?,
+        db.get_start_line(route.route_id, start_location)?,
+    ) else {
+        return Ok(stored);
+    };
+    let Some(road_length_km) = db.get_road_length(&route.world, pen_start.road)? else {
+        return Ok(stored);
+    };
+    Ok(
+        lead_in_from_start_line(route, &default_start, &pen_start, road_length_km)
+            .unwrap_or(stored),
+    )
+}
+
+/// Lead-in from `pen_start`, given the route's default start line and the road length
+///
+/// A pen behind the default start line adds distance; one ahead of it takes
+/// distance off. Which way is "behind" comes from the start lines' riding
+/// direction. Returns `None` when the two start lines are on different roads
+/// or neither knows the direction.
+#[must_use]
+pub fn lead_in_from_start_line(
+    route: &RouteData,
+    default_start: &StartLine,
+    pen_start: &StartLine,
+    road_length_km: f64,
+) -> Option<f64> {
+    if default_start.road != pen_start.road {
+        return None;
+    }
+    let forward = default_start.forward.or(pen_start.forward)?;
+    let behind_km = (f64::from(default_start.road_time) - f64::from(pen_start.road_time))
+        / ROAD_TIME_SPAN
+        * road_length_km;
+    let behind_km = if forward { behind_km } else { -behind_km };
+    Some((route.lead_in_distance_km + behind_km).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route() -> RouteData {
+        RouteData {
+            route_id: 2_698_009_951,
+            distance_km: 22.9,
+            elevation_m: 80,
+            name: "Downtown Dolphin".to_string(),
+            world: "Crit City".to_string(),
+            surface: "road".to_string(),
+            lead_in_distance_km: 0.4,
+            ..Default::default()
+        }
+    }
+
+    fn start_line(start_location: u32, road: u32, road_time: u32) -> StartLine {
+        StartLine {
+            route_id: 2_698_009_951,
+            start_location,
+            name: "Crit City - Downtown Dolphin".to_string(),
+            road,
+            road_time,
+            forward: Some(true),
+        }
+    }
+
+    #[test]
+    fn test_pen_behind_and_ahead_of_default_start() {
+        let default_start = start_line(1, 3, 600_000);
+        // 12% of an 8 km road behind the default start: +0.96 km
+        let behind = start_line(2, 3, 480_000);
+        let lead_in = lead_in_from_start_line(&route(), &default_start, &behind, 8.0).unwrap();
+        assert!((lead_in - 1.36).abs() < 1e-9);
+
+        // Riding the road the other way, the same pen is ahead; lead-in never goes negative
+        let backward = StartLine {
+            forward: Some(false),
+            ..default_start.clone()
+        };
+        let lead_in = lead_in_from_start_line(&route(), &backward, &behind, 8.0).unwrap();
+        assert_eq!(lead_in, 0.0);
+
+        let other_road = start_line(2, 4, 480_000);
+        assert!(lead_in_from_start_line(&route(), &default_start, &other_road, 8.0).is_none());
+    }
+
+    #[test]
+    fn test_unknown_direction_gives_no_estimate() {
+        let unknown = |start_line: StartLine| StartLine {
+            forward: None,
+            ..start_line
+        };
+        let default_start = unknown(start_line(1, 3, 600_000));
+        let behind = unknown(start_line(2, 3, 480_000));
+        assert!(lead_in_from_start_line(&route(), &default_start, &behind, 8.0).is_none());
+    }
+
+    #[test]
+    fn test_lead_in_for_start_location_falls_back_to_stored_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        let route = route();
+
+        assert_eq!(
+            lead_in_for_start_location(&db, &route, Some(2)).unwrap(),
+            0.4
+        );
+
+        db.save_start_lines(&[start_line(1, 3, 600_000), start_line(2, 3, 450_000)])
+            .unwrap();
+        // Road length still unknown
+        assert_eq!(
+            lead_in_for_start_location(&db, &route, Some(2)).unwrap(),
+            0.4
+        );
+
+        db.set_road_length("Crit City", 3, 8.0).unwrap();
+        let lead_in = lead_in_for_start_location(&db, &route, Some(2)).unwrap();
+        assert!((lead_in - 1.6).abs() < 1e-9);
+        assert_eq!(lead_in_for_start_location(&db, &route, None).unwrap(), 0.4);
+        assert_eq!(
+            lead_in_for_start_location(&db, &route, Some(1)).unwrap(),
+            0.4
+        );
+    }
+}
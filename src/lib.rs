@@ -27,12 +27,21 @@ pub mod cache;
 /// Configuration management for the application
 pub mod config;
 
+/// Credential lookup in the environment, OS keyring or an encrypted file
+pub mod credentials;
+
 /// Database operations for routes and race results
 pub mod database;
 
+/// Named rider profiles sharing one installation
+pub mod profile;
+
 /// Duration estimation utilities
 pub mod duration_estimation;
 
+/// Racing score estimated from race history
+pub mod score_estimate;
+
 /// Enhanced error handling with user-friendly messages
 pub mod errors;
 
@@ -58,6 +67,51 @@ pub mod regression_test;
 /// Route discovery from external sources
 pub mod route_discovery;
 
+/// Sites and saved pages that route discovery reads from
+pub mod route_providers;
+
+/// Portable route bundles for sharing route databases
+pub mod route_bundle;
+
+/// Import of FIT activity files as race results
+pub mod fit_import;
+
+/// Matching of imported activities to known routes
+pub mod route_matcher;
+
+/// Fuzzy matching of free-text route names to known routes
+pub mod route_names;
+
+/// Route mappings inferred from event descriptions
+pub mod description_mappings;
+
+/// Machine-readable output of matching events (JSON, CSV, NDJSON)
+pub mod event_export;
+
+/// iCalendar export of matching events
+pub mod event_calendar;
+
+/// Notifications about newly matching events (stdout, command, webhook)
+pub mod notify;
+
+/// Import of Strava activity exports as race results
+pub mod strava_import;
+
+/// Race results captured from Zwift's event results
+pub mod event_results;
+
+/// Import of ZwiftPower race history as race results
+pub mod zwiftpower_import;
+
+/// ZwiftPower profile page parsing (racing score, category, weight, FTP)
+pub mod zwiftpower_profile;
+
+/// Lead-in distance for events starting from non-default pens
+pub mod lead_in;
+
+/// Events a group of riders can race together
+pub mod team;
+
 
 
 /// OCR constants
@@ -87,6 +141,12 @@ pub mod ocr_ocrs;
 #[cfg(feature = "ocr")]
 pub mod ocr_parallel;
 
+/// Authenticated Zwift API client for the rider's profile and signups
+pub mod zwift_api;
+
 /// Client for importing route data from zwift-offline
 pub mod zwift_offline_client;
 
+/// Live sync of routes and start lines from a zwift-offline server
+pub mod zwift_offline_sync;
+
@@ -8,27 +8,34 @@
 
 mod api;
 mod commands;
-mod config;
-mod database;
-mod route_discovery;
 mod zwiftpower;
 
 use anyhow::Result;
 use chrono::Utc;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use colored::*;
-use config::FullConfig;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use zwift_race_finder::category::*;
+use zwift_race_finder::config::FullConfig;
 use zwift_race_finder::constants::*;
+use zwift_race_finder::database;
 use zwift_race_finder::errors::*;
 use zwift_race_finder::estimation::*;
 use zwift_race_finder::event_analysis::*;
+use zwift_race_finder::event_calendar;
 use zwift_race_finder::event_display::{
     display_filter_stats, log_unknown_route, print_event, print_events_table,
 };
+use zwift_race_finder::event_export::{
+    event_record, write_output, EventRecord, OutputFormat, SearchOutput,
+};
 use zwift_race_finder::event_filtering::*;
 use zwift_race_finder::formatting::*;
 use zwift_race_finder::models::*;
+use zwift_race_finder::notify::{self, Channel, Notifier};
+use zwift_race_finder::profile;
+use zwift_race_finder::team::MemberSpec;
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -65,11 +72,18 @@ struct Args {
     #[arg(long)]
     analyze_descriptions: bool,
 
-    /// Record a race result (format: "route_id,minutes,event_name[,zwift_score]")
+    /// With --analyze-descriptions, save what was found as route aliases,
+    /// multi-lap patterns or new routes (asks first)
+    #[arg(long, requires = "analyze_descriptions")]
+    apply: bool,
+
+    /// Record a race result (format: "route_id,minutes,event_name[,zwift_score][,key=value...]";
+    /// keys: position, field, cat, avg, np, weight, event, date, source, dnf).
+    /// The `record` command fills these in from the event instead
     #[arg(long)]
     record_result: Option<String>,
 
-    /// Discover unknown routes from web sources
+    /// Discover unknown routes from web sources for up to 2 minutes (see the `discover` command)
     #[arg(long)]
     discover_routes: bool,
 
@@ -93,9 +107,363 @@ struct Args {
     #[arg(long)]
     new_routes_only: bool,
 
+    /// Show the events you have signed up for (needs a Zwift API token)
+    #[arg(long)]
+    my_events: bool,
+
     /// Use verbose output format (default: compact table)
     #[arg(short = 'v', long)]
     verbose: bool,
+
+    /// Output format: table, json, csv or ndjson. Other formats write only the
+    /// results to stdout; status messages go to stderr
+    #[arg(long, default_value = "table")]
+    format: OutputFormat,
+
+    /// Also write the matching events to this iCalendar (.ics) file
+    #[arg(long, value_name = "FILE")]
+    ics: Option<PathBuf>,
+
+    /// With --ics, add a reminder this many minutes before each event
+    #[arg(long, value_name = "MINUTES", requires = "ics")]
+    ics_reminder: Option<u32>,
+
+    /// Re-run the search every N minutes and announce only events not reported before
+    /// (table output only)
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    watch: Option<u32>,
+
+    /// With --watch, also run this command with a title and body (e.g. notify-send)
+    #[arg(long, value_name = "COMMAND", requires = "watch")]
+    notify_command: Option<String>,
+
+    /// With --watch, also POST new matches as JSON to this URL
+    #[arg(long, value_name = "URL", requires = "watch")]
+    webhook: Option<String>,
+
+    /// Rider profile to use (default: $ZRF_PROFILE, or the default profile)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Share the route database with other installations
+    Db {
+        #[command(subcommand)]
+        action: DbCommand,
+    },
+
+    /// Import race results from activity files
+    Import {
+        #[command(subcommand)]
+        action: ImportCommand,
+    },
+
+    /// Pull route data from a live source
+    Sync {
+        #[command(subcommand)]
+        source: SyncCommand,
+    },
+
+    /// Look up queued unknown routes on route sites; failed lookups back off exponentially
+    Discover {
+        /// Work through every due route instead of stopping after 2 minutes
+        #[arg(long)]
+        resume: bool,
+
+        /// Stop after looking up this many routes
+        #[arg(long)]
+        limit: Option<usize>,
+
+        #[command(subcommand)]
+        action: Option<DiscoverCommand>,
+    },
+
+    /// Record your finish time in an archived event; picks from recent events when no ID is given
+    Record {
+        /// Event ID (see `import results` for recording from Zwift's results instead)
+        #[arg(long)]
+        event: Option<u64>,
+
+        /// Finish time (h:mm:ss, mm:ss or minutes); asked for when omitted
+        #[arg(long)]
+        time: Option<String>,
+
+        /// Zwift Racing Score at the time (default: score history, Zwift profile, then an estimate)
+        #[arg(long)]
+        zwift_score: Option<u32>,
+
+        /// Show what would be recorded without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Chart the racing score recorded from ZwiftPower over time
+    ScoreHistory {
+        /// Number of most recent readings to show
+        #[arg(long, default_value = "30")]
+        limit: usize,
+    },
+
+    /// Find races where every teammate fits the same (or a neighbouring) pen
+    Team {
+        /// Riders: profile names or scores, e.g. alice,bob=245,180
+        #[arg(required = true, value_delimiter = ',')]
+        members: Vec<MemberSpec>,
+
+        /// Allow pens one category apart (e.g. C and D)
+        #[arg(long)]
+        adjacent: bool,
+
+        /// Event type to search (race, fondo, group, workout, tt, all)
+        #[arg(short = 'e', long, default_value = "race")]
+        event_type: String,
+
+        /// Days ahead to search (the public API only returns the next ~12 hours)
+        #[arg(long, default_value = "1")]
+        days: u32,
+
+        /// Only events every rider finishes in about this many minutes
+        #[arg(short = 'd', long)]
+        duration: Option<u32>,
+
+        /// Tolerance for --duration in minutes
+        #[arg(short = 't', long, default_value = "30")]
+        tolerance: u32,
+    },
+
+    /// Store ZwiftPower credentials in the OS keyring or an encrypted file
+    Credentials {
+        #[command(subcommand)]
+        action: CredentialsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum CredentialsCommand {
+    /// Store a credential (zwiftpower-profile-id, zwiftpower-session-id or zwift-api-token)
+    Set {
+        /// Credential name
+        name: zwift_race_finder::credentials::Credential,
+
+        /// Value; read from stdin when omitted, keeping it out of shell history
+        value: Option<String>,
+    },
+
+    /// Show where a credential comes from, masking secret values
+    Get {
+        /// Credential name; all credentials when omitted
+        name: Option<zwift_race_finder::credentials::Credential>,
+
+        /// Print secret values in full
+        #[arg(long)]
+        show: bool,
+    },
+
+    /// Remove a stored credential
+    Clear {
+        /// Credential name; all credentials when omitted
+        name: Option<zwift_race_finder::credentials::Credential>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum DiscoverCommand {
+    /// Summarise the queue: routes due, waiting after failures, and found
+    Status,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum SyncCommand {
+    /// Fetch routes, start lines and events from a zwift-offline server
+    ZwiftOffline {
+        /// Server base URL, e.g. http://localhost:8080
+        #[arg(long)]
+        url: String,
+
+        /// Show the route diff without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum DbCommand {
+    /// Export routes, aliases and multi-lap patterns to a bundle (.json or .toml)
+    Export {
+        /// Bundle file to write
+        file: PathBuf,
+
+        /// Also export recorded race results
+        #[arg(long)]
+        include_results: bool,
+    },
+
+    /// Import a bundle into the local database
+    Import {
+        /// Bundle file to read
+        file: PathBuf,
+
+        /// How to resolve routes that differ: mine, theirs or newest
+        #[arg(long, default_value = "mine")]
+        conflict: zwift_race_finder::route_bundle::ConflictStrategy,
+
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Record where an event start location (pen) puts riders on a route
+    StartLine {
+        /// Route ID
+        route_id: u32,
+
+        /// Start location as given by event subgroups (1 is the route's default start)
+        #[arg(long)]
+        start_location: u32,
+
+        /// Road the start line is on
+        #[arg(long)]
+        road: u32,
+
+        /// Position along the road in Zwift road-time units
+        #[arg(long)]
+        road_time: u32,
+
+        /// Direction the route rides the road: towards increasing road time
+        /// (forward) or decreasing (reverse)
+        #[arg(long, value_parser = ["forward", "reverse"])]
+        direction: Option<String>,
+    },
+
+    /// Record the length of a road, used to turn start line positions into distances
+    RoadLength {
+        /// World name as stored with routes, e.g. Watopia
+        world: String,
+
+        /// Road ID
+        road: u32,
+
+        /// Road length in kilometers
+        length_km: f64,
+    },
+
+    /// Show route mappings written by tools such as --analyze-descriptions --apply
+    Audit {
+        /// How many of the most recent entries to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ImportCommand {
+    /// Import FIT activity files (plain or Garmin ZIP-wrapped) as race results
+    Fit {
+        /// FIT files to import
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Route ID to record the results against (default: match by distance)
+        #[arg(long)]
+        route_id: Option<u32>,
+
+        /// Event name to record (default: the file name)
+        #[arg(long)]
+        event_name: Option<String>,
+
+        /// Zwift Racing Score at the time of the races (default: score history on each race date, then an estimate)
+        #[arg(long)]
+        zwift_score: Option<u32>,
+
+        /// Show what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Import a Strava activity export (JSON), matching each ride to a route
+    Strava {
+        /// Activity JSON file, e.g. from tools/import/strava/strava_fetch_activities.sh
+        file: PathBuf,
+
+        /// Zwift Racing Score at the time of the races (default: score history on each race date, then an estimate)
+        #[arg(long)]
+        zwift_score: Option<u32>,
+
+        /// Also import activities whose name does not mention a race
+        #[arg(long)]
+        all: bool,
+
+        /// Show what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Import saved ZwiftPower results (profile JSON, extractor JSON or profile page HTML)
+    Zwiftpower {
+        /// Saved ZwiftPower files
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Zwift Racing Score at the time of the races (default: score history on each race date, then an estimate)
+        #[arg(long)]
+        zwift_score: Option<u32>,
+
+        /// Show what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Record your finish in a past event from its results (saved payload or the Zwift API)
+    Results {
+        /// Event ID, as shown by the search or on zwift.com/events
+        event_id: u64,
+
+        /// Saved results payload (JSON); fetched from the Zwift API when omitted
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Your Zwift profile ID (default: from the Zwift API token or ZwiftPower profile ID)
+        #[arg(long)]
+        rider_id: Option<u64>,
+
+        /// Zwift Racing Score at the time of the race (default: score history, Zwift profile, then an estimate)
+        #[arg(long)]
+        zwift_score: Option<u32>,
+
+        /// Show what would be recorded without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// List imported activities waiting for a route to be confirmed
+    Review,
+
+    /// Record a queued activity against a route, or drop it from the queue
+    Resolve {
+        /// Queue entry ID (see `import review`)
+        id: i64,
+
+        /// Route to record the result against (default: the best candidate)
+        #[arg(long, conflicts_with = "dismiss")]
+        route_id: Option<u32>,
+
+        /// Number of laps ridden (default: from the candidate)
+        #[arg(long, conflicts_with = "dismiss")]
+        laps: Option<u32>,
+
+        /// Remove the entry without recording a result
+        #[arg(long)]
+        dismiss: bool,
+    },
 }
 
 // Zwift route database - route_id is the primary key for all calculations
@@ -227,8 +595,6 @@ fn generate_filter_description(args: &Args, min_duration: u32, max_duration: u32
     parts.join(" | ")
 }
 
-
-
 fn filter_events(
     mut events: Vec<ZwiftEvent>,
     args: &Args,
@@ -286,6 +652,10 @@ fn filter_events(
         .retain(|event| event_matches_duration(event, args.duration, args.tolerance, zwift_score));
     stats.duration_filtered = (pre_duration - events.len()) as u32;
 
+    // Data quality of the events that are left
+    stats.unknown_routes = count_unknown_routes(&events);
+    stats.missing_distance = count_missing_distance(&events);
+
     if args.debug {
         eprintln!("Debug: {} events after duration filter", events.len());
         eprintln!(
@@ -297,17 +667,244 @@ fn filter_events(
     (events, stats)
 }
 
+/// Re-run the search every `interval_minutes`, announcing matches not reported before
+///
+/// Runs until interrupted. New matches are marked reported once printed; a
+/// failed fetch, or a command or webhook that failed, is retried at the next
+/// check. With `--ics` the calendar is rewritten after every check.
+async fn watch_events(args: &Args, zwift_score: u32, interval_minutes: u32) -> Result<()> {
+    let db = database::Database::new()?;
+    let notifier = Notifier::new(args.notify_command.clone(), args.webhook.clone());
+    let interval = std::time::Duration::from_secs(u64::from(interval_minutes) * 60);
+    let mut undelivered: HashMap<Channel, Vec<EventRecord>> = HashMap::new();
+    println!(
+        "👀 Watching for new matches every {} min (Ctrl-C to stop)",
+        interval_minutes
+    );
 
+    loop {
+        match api::fetch_events().await {
+            Ok(events) => {
+                let _ = db.archive_events(&events);
+                let (filtered, _) = filter_events(events, args, zwift_score);
+                let new_events = db.unreported_events(&filtered)?;
+                println!(
+                    "[{}] {} matching, {} new",
+                    chrono::Local::now().format("%H:%M"),
+                    filtered.len(),
+                    new_events.len()
+                );
 
+                if let Some(path) = &args.ics {
+                    let records: Vec<EventRecord> = filtered
+                        .iter()
+                        .map(|event| event_record(event, zwift_score))
+                        .collect();
+                    event_calendar::write_calendar(path, &records, Utc::now(), args.ics_reminder)?;
+                }
 
+                let records: Vec<EventRecord> = new_events
+                    .iter()
+                    .map(|event| event_record(event, zwift_score))
+                    .collect();
+                notify::announce(&records);
+                db.mark_events_reported(&new_events)?;
+
+                // A channel that failed gets its missed events again with the next batch,
+                // unless they have started by now
+                let now = Utc::now();
+                for channel in notifier.channels() {
+                    let mut batch = undelivered.remove(&channel).unwrap_or_default();
+                    batch.retain(|event| event.start > now);
+                    batch.extend(records.iter().cloned());
+                    if let Err(e) = notifier.deliver(channel, &batch).await {
+                        eprintln!(
+                            "{} The {} failed, will retry at the next check: {:#}",
+                            "⚠️  Warning:".yellow(),
+                            channel,
+                            e
+                        );
+                        undelivered.insert(channel, batch);
+                    }
+                }
+            }
+            Err(e) => eprintln!(
+                "{} Couldn't fetch events, will retry at the next check: {}",
+                "⚠️  Warning:".yellow(),
+                e
+            ),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
 
-
+/// Check option combinations clap can't express
+fn validate_args(args: &Args) -> Result<()> {
+    if args.watch.is_some() && args.format.is_machine_readable() {
+        anyhow::bail!(
+            "--watch prints a table; it can't be combined with a machine-readable --format"
+        );
+    }
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    validate_args(&args)?;
+
+    // Machine-readable formats keep stdout for the results
+    let machine_output = args.format.is_machine_readable();
+    macro_rules! status {
+        ($($arg:tt)*) => {
+            if machine_output {
+                eprintln!($($arg)*);
+            } else {
+                println!($($arg)*);
+            }
+        };
+    }
 
-    println!("🚴 {} {}", "Zwift Race Finder".bold(), "v0.1.0".dimmed());
+    status!("🚴 {} {}", "Zwift Race Finder".bold(), "v0.1.0".dimmed());
+
+    let rider = args
+        .profile
+        .clone()
+        .or_else(|| std::env::var("ZRF_PROFILE").ok().filter(|p| !p.is_empty()));
+    if let Some(rider) = rider {
+        profile::set_active(&rider)?;
+        status!("👤 Profile: {}", rider.cyan());
+    }
+
+    // Handle subcommands first
+    if let Some(command) = &args.command {
+        match command {
+            Command::Db { action } => match action {
+                DbCommand::Export {
+                    file,
+                    include_results,
+                } => commands::export_route_bundle(file, *include_results)?,
+                DbCommand::Import {
+                    file,
+                    conflict,
+                    dry_run,
+                } => commands::import_route_bundle(file, *conflict, *dry_run)?,
+                DbCommand::StartLine {
+                    route_id,
+                    start_location,
+                    road,
+                    road_time,
+                    direction,
+                } => commands::add_start_line(
+                    *route_id,
+                    *start_location,
+                    *road,
+                    *road_time,
+                    direction.as_deref().map(|direction| direction == "forward"),
+                )?,
+                DbCommand::RoadLength {
+                    world,
+                    road,
+                    length_km,
+                } => commands::set_road_length(world, *road, *length_km)?,
+                DbCommand::Audit { limit } => commands::show_mapping_audit(*limit)?,
+            },
+            Command::Import { action } => match action {
+                ImportCommand::Fit {
+                    files,
+                    route_id,
+                    event_name,
+                    zwift_score,
+                    dry_run,
+                } => commands::import_fit_files(
+                    files,
+                    *route_id,
+                    event_name.as_deref(),
+                    *zwift_score,
+                    *dry_run,
+                )?,
+                ImportCommand::Strava {
+                    file,
+                    zwift_score,
+                    all,
+                    dry_run,
+                } => commands::import_strava_activities(file, *zwift_score, *all, *dry_run)?,
+                ImportCommand::Zwiftpower {
+                    files,
+                    zwift_score,
+                    dry_run,
+                } => commands::import_zwiftpower_files(files, *zwift_score, *dry_run)?,
+                ImportCommand::Results {
+                    event_id,
+                    file,
+                    rider_id,
+                    zwift_score,
+                    dry_run,
+                } => {
+                    commands::import_event_result(
+                        *event_id,
+                        file.as_deref(),
+                        *rider_id,
+                        *zwift_score,
+                        *dry_run,
+                    )
+                    .await?;
+                }
+                ImportCommand::Review => commands::show_review_queue()?,
+                ImportCommand::Resolve {
+                    id,
+                    route_id,
+                    laps,
+                    dismiss,
+                } => commands::resolve_review_item(*id, *route_id, *laps, *dismiss)?,
+            },
+            Command::Discover {
+                action: Some(DiscoverCommand::Status),
+                ..
+            } => commands::show_discovery_status()?,
+            Command::Discover {
+                resume,
+                limit,
+                action: None,
+            } => commands::discover_unknown_routes(*resume, *limit).await?,
+            Command::Sync { source } => match source {
+                SyncCommand::ZwiftOffline { url, dry_run } => {
+                    commands::sync_zwift_offline(url, *dry_run).await?;
+                }
+            },
+            Command::Record {
+                event,
+                time,
+                zwift_score,
+                dry_run,
+            } => {
+                commands::record_event_result(*event, time.as_deref(), *zwift_score, *dry_run)
+                    .await?
+            }
+            Command::ScoreHistory { limit } => commands::show_score_history(*limit)?,
+            Command::Team {
+                members,
+                adjacent,
+                event_type,
+                days,
+                duration,
+                tolerance,
+            } => {
+                commands::find_team_events(
+                    members, *adjacent, event_type, *days, *duration, *tolerance,
+                )
+                .await?;
+            }
+            Command::Credentials { action } => match action {
+                CredentialsCommand::Set { name, value } => {
+                    commands::set_credential(*name, value.as_deref())?;
+                }
+                CredentialsCommand::Get { name, show } => commands::show_credentials(*name, *show)?,
+                CredentialsCommand::Clear { name } => commands::clear_credentials(*name)?,
+            },
+        }
+        return Ok(());
+    }
 
     // Handle special commands first
     if args.show_unknown_routes {
@@ -316,17 +913,17 @@ async fn main() -> Result<()> {
     }
 
     if args.analyze_descriptions {
-        commands::analyze_event_descriptions().await?;
+        commands::analyze_event_descriptions(args.apply).await?;
         return Ok(());
     }
 
     if let Some(result_str) = args.record_result {
-        commands::record_race_result(&result_str)?;
+        commands::record_race_result(&result_str).await?;
         return Ok(());
     }
 
     if args.discover_routes {
-        commands::discover_unknown_routes().await?;
+        commands::discover_unknown_routes(false, None).await?;
         return Ok(());
     }
 
@@ -340,6 +937,11 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.my_events {
+        commands::show_my_events(args.zwift_score, args.verbose).await?;
+        return Ok(());
+    }
+
     // Load configuration
     let config = match FullConfig::load() {
         Ok(cfg) => cfg,
@@ -355,6 +957,15 @@ async fn main() -> Result<()> {
             FullConfig::default()
         }
     };
+    let active_profile = profile::active();
+    if !profile::is_default(active_profile) && !config.config.profiles.contains_key(active_profile)
+    {
+        eprintln!(
+            "{} No [profiles.{}] section in config.toml; using built-in rider defaults",
+            "⚠️  Warning:".yellow(),
+            active_profile
+        );
+    }
 
     // Apply config defaults to args where not specified
     // For duration and tolerance, we need to check if they were explicitly set
@@ -393,20 +1004,36 @@ async fn main() -> Result<()> {
 
     // Show what stats we're using
     if args.zwift_score.is_some() {
-        println!("Using provided Zwift Score: {}", zwift_score);
+        status!("Using provided Zwift Score: {}", zwift_score);
+    } else if let Some(estimate) = &user_stats.estimate {
+        status!(
+            "Using estimated Zwift Score: {} (Cat {}, {} confidence from {} race results)",
+            zwift_score.to_string().yellow(),
+            estimate.category,
+            estimate.confidence,
+            estimate.results_used
+        );
+        status!(
+            "{}",
+            format!(
+                "💡 Your results fit the {}-{} band; pass --zwift-score or configure your stats in config.toml to override",
+                estimate.band.0, estimate.band.1
+            )
+            .dimmed()
+        );
     } else if user_stats.username == "User" {
         // Using defaults - provide guidance
-        println!(
+        status!(
             "Using default Zwift Score: {} (Cat {})",
             zwift_score.to_string().yellow(),
             get_category_from_score(zwift_score)
         );
-        println!(
+        status!(
             "{}",
             "💡 Tip: For personalized results, configure your stats in config.toml".dimmed()
         );
     } else {
-        println!(
+        status!(
             "Using {} stats: Zwift Score {} (Cat {})",
             user_stats.username.green(),
             zwift_score.to_string().yellow(),
@@ -417,30 +1044,58 @@ async fn main() -> Result<()> {
     let min_duration = duration.saturating_sub(tolerance);
     let max_duration = duration + tolerance;
 
-    println!(
+    status!(
         "Looking for events {} to {}...\n",
         format_duration(min_duration).yellow(),
         format_duration(max_duration).yellow()
     );
 
+    // Create modified args with config-based defaults
+    let mut effective_args = args.clone();
+    effective_args.duration = duration;
+    effective_args.tolerance = tolerance;
+    effective_args.days = days;
+
+    if let Some(interval) = args.watch {
+        return watch_events(&effective_args, zwift_score, interval).await;
+    }
+
     let events = api::fetch_events().await?;
+    // Keep the events so results can be recorded against them once they finish
+    if let Ok(db) = database::Database::new() {
+        let _ = db.archive_events(&events);
+    }
 
     if events.is_empty() {
         no_events_in_time_range(1).display();
+        let output = SearchOutput::new(&[], zwift_score, &FilterStats::default(), 0);
+        // An empty calendar, so subscribed calendars drop events that are gone
+        if let Some(path) = &args.ics {
+            event_calendar::write_calendar(
+                path,
+                &output.events,
+                output.summary.generated_at,
+                args.ics_reminder,
+            )?;
+            status!("📅 Wrote 0 events to {}", path.display());
+        }
+        if machine_output {
+            write_output(&mut std::io::stdout().lock(), args.format, &output)?;
+        }
         return Ok(());
     }
 
-    println!("Fetched {} upcoming events", events.len());
+    status!("Fetched {} upcoming events", events.len());
 
     // Notify if API returns unexpected number of events
     if events.len() > 250 {
-        println!(
+        status!(
             "\n{} Zwift API returned {} events (expected ~200)",
             "🎉 Unexpected:".green(),
             events.len()
         );
-        println!("   The API may have been updated to return more data!");
-        println!("   Please report this at: https://github.com/anthropics/claude-code/issues");
+        status!("   The API may have been updated to return more data!");
+        status!("   Please report this at: https://github.com/anthropics/claude-code/issues");
     }
 
     // Display the actual time range covered by the fetched events
@@ -452,7 +1107,7 @@ async fn main() -> Result<()> {
         let earliest_local = earliest_start.with_timezone(&chrono::Local);
         let latest_local = latest_start.with_timezone(&chrono::Local);
 
-        println!(
+        status!(
             "Events from {} to {}",
             earliest_local.format("%b %d, %l:%M %p").to_string().trim(),
             latest_local.format("%b %d, %l:%M %p").to_string().trim()
@@ -461,12 +1116,12 @@ async fn main() -> Result<()> {
 
     // Warn about API limitation when requesting multiple days
     if days > 1 {
-        println!(
+        status!(
             "\n{} Zwift API only returns ~12 hours of events (200 max)",
             "⚠️  Note:".yellow()
         );
-        println!("   Multi-day searches may not show all available events.");
-        println!("   For best results, search specific time windows throughout the day.");
+        status!("   Multi-day searches may not show all available events.");
+        status!("   For best results, search specific time windows throughout the day.");
     }
 
     // Count events by type for informative output
@@ -474,12 +1129,11 @@ async fn main() -> Result<()> {
 
     // Display event type summary
     if !event_counts.is_empty() {
-        print!("Found: ");
         let formatted_counts: Vec<String> = event_counts
             .iter()
             .map(|(event_type, count)| format_event_type(event_type, *count))
             .collect();
-        println!("{}", formatted_counts.join(", "));
+        status!("Found: {}", formatted_counts.join(", "));
     }
 
     // Debug: show race data
@@ -490,16 +1144,16 @@ async fn main() -> Result<()> {
             .take(5)
             .collect();
 
-        println!("\nDebug: First 5 races:");
+        status!("\nDebug: First 5 races:");
         for event in races {
-            println!("  Name: {}", event.name);
-            println!("  Route ID: {:?}", event.route_id);
-            println!("  Distance: {:?} meters", event.distance_in_meters);
-            println!("  Duration: {:?} minutes", event.duration_in_minutes);
-            println!("  Subgroups: {} groups", event.event_sub_groups.len());
+            status!("  Name: {}", event.name);
+            status!("  Route ID: {:?}", event.route_id);
+            status!("  Distance: {:?} meters", event.distance_in_meters);
+            status!("  Duration: {:?} minutes", event.duration_in_minutes);
+            status!("  Subgroups: {} groups", event.event_sub_groups.len());
             if !event.event_sub_groups.is_empty() {
                 for sg in &event.event_sub_groups {
-                    println!(
+                    status!(
                         "    - {}: dist={:?}m, dur={:?}min, laps={:?}, range={:?}",
                         sg.name,
                         sg.distance_in_meters,
@@ -509,7 +1163,7 @@ async fn main() -> Result<()> {
                     );
                 }
             }
-            println!();
+            status!();
         }
     }
 
@@ -521,7 +1175,7 @@ async fn main() -> Result<()> {
             .count();
 
         if race_count > 0 {
-            println!("\nDebug: Found {} races, checking first few:", race_count);
+            status!("\nDebug: Found {} races, checking first few:", race_count);
             let sample_races: Vec<_> = events
                 .iter()
                 .filter(|e| e.sport.to_uppercase() == "CYCLING" && e.event_type == "RACE")
@@ -529,21 +1183,24 @@ async fn main() -> Result<()> {
                 .collect();
 
             for event in sample_races {
-                println!(
+                status!(
                     "  '{}': route_id={:?}, dist={:?}m",
-                    event.name, event.route_id, event.distance_in_meters
+                    event.name,
+                    event.route_id,
+                    event.distance_in_meters
                 );
 
                 // Show what duration we would estimate
                 if let Some(route_id) = event.route_id {
                     if let Some(route_data) = get_route_data(route_id) {
                         let est = estimate_duration_from_route_id(route_id, zwift_score);
-                        println!(
+                        status!(
                             "    → Known route: {} km, would estimate {:?} min",
-                            route_data.distance_km, est
+                            route_data.distance_km,
+                            est
                         );
                     } else {
-                        println!("    → Unknown route {}", route_id);
+                        status!("    → Unknown route {}", route_id);
                     }
                 }
             }
@@ -556,7 +1213,7 @@ async fn main() -> Result<()> {
             .iter()
             .filter(|e| e.sport == "CYCLING" && e.event_type == "RACE")
             .count();
-        println!("Found {} cycling races", race_count);
+        status!("Found {} cycling races", race_count);
     }
 
     // Log unknown routes (discovery will be done separately)
@@ -566,13 +1223,30 @@ async fn main() -> Result<()> {
         }
     }
 
-    // Create modified args with config-based defaults
-    let mut effective_args = args.clone();
-    effective_args.duration = duration;
-    effective_args.tolerance = tolerance;
-    effective_args.days = days;
-
     let (filtered, filter_stats) = filter_events(events.clone(), &effective_args, zwift_score);
+    let output = SearchOutput::new(&filtered, zwift_score, &filter_stats, events.len());
+
+    if let Some(path) = &args.ics {
+        event_calendar::write_calendar(
+            path,
+            &output.events,
+            output.summary.generated_at,
+            args.ics_reminder,
+        )?;
+        status!(
+            "📅 Wrote {} events to {}",
+            output.events.len(),
+            path.display()
+        );
+    }
+
+    if machine_output {
+        write_output(&mut std::io::stdout().lock(), args.format, &output)?;
+        if filtered.is_empty() {
+            eprintln!("{}", "No matching events found!".red());
+        }
+        return Ok(());
+    }
 
     if filtered.is_empty() {
         println!("\n{}", "No matching events found!".red());
@@ -642,6 +1316,13 @@ mod tests {
                 debug: false,
                 show_unknown_routes: false,
                 analyze_descriptions: false,
+                format: OutputFormat::Table,
+                ics: None,
+                ics_reminder: None,
+                watch: None,
+                notify_command: None,
+                webhook: None,
+                apply: false,
                 record_result: None,
                 discover_routes: false,
                 tags: vec![],
@@ -649,7 +1330,10 @@ mod tests {
                 mark_complete: None,
                 show_progress: false,
                 new_routes_only: false,
+                my_events: false,
                 verbose: false,
+                profile: None,
+                command: None,
             }
         }
     }
@@ -673,6 +1357,96 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_race_result_input_details() {
+        let (result, zwift_score) = commands::parse_race_result_input(
+            "3379779247,45,3R Volcano Flat,212,position=14,field=58,cat=c,avg=205,np=231,weight=72.5,event=4512345",
+        )
+        .unwrap();
+        assert_eq!(zwift_score, Some(212));
+        assert_eq!(result.route_id, 3379779247);
+        assert_eq!(result.actual_minutes, 45);
+        assert_eq!(result.event_name, "3R Volcano Flat");
+        assert_eq!(result.zwift_score, 212);
+        assert_eq!(result.position, Some(14));
+        assert_eq!(result.field_size, Some(58));
+        assert_eq!(result.category.as_deref(), Some("C"));
+        assert_eq!(result.avg_power_w, Some(205));
+        assert_eq!(result.np_power_w, Some(231));
+        assert_eq!(result.weight_kg, Some(72.5));
+        assert_eq!(result.event_id, Some(4512345));
+        assert_eq!(result.source.as_deref(), Some("manual"));
+        assert!(!result.dnf);
+
+        // Old format still works, including commas in the event name
+        let (result, zwift_score) =
+            commands::parse_race_result_input("42,30,Race, with comma").unwrap();
+        assert_eq!(result.event_name, "Race, with comma");
+        assert_eq!(zwift_score, None);
+
+        let (result, _) =
+            commands::parse_race_result_input("42,30,Hilly Race,position=dnf").unwrap();
+        assert!(result.dnf);
+        assert!(commands::parse_race_result_input("42,30,Race,bogus=1").is_err());
+        assert!(commands::parse_race_result_input("42,30,Race,np=lots").is_err());
+    }
+
+    #[test]
+    fn test_parse_race_result_input_name_with_separators() {
+        let (result, _) = commands::parse_race_result_input(
+            "42,30,Race, 2=1 Points Rules, Stage 3,212,position=5,dnf",
+        )
+        .unwrap();
+        assert_eq!(result.event_name, "Race, 2=1 Points Rules, Stage 3");
+        assert_eq!(result.zwift_score, 212);
+        assert_eq!(result.position, Some(5));
+        assert!(result.dnf);
+
+        // A name that is only key=value-shaped is still the name
+        let (result, _) = commands::parse_race_result_input("42,30,a=b,cat=B").unwrap();
+        assert_eq!(result.event_name, "a=b");
+        assert_eq!(result.category.as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn test_estimate_used_without_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = database::Database::open(&dir.path().join("races.db")).unwrap();
+        for minutes in [26, 28, 30] {
+            db.add_race_result(&database::RaceResult {
+                route_id: 1_258_415_487,
+                event_name: "Bell Lap Crit".to_string(),
+                actual_minutes: minutes,
+                zwift_score: 0,
+                race_date: "2026-10-01".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        let estimate = zwift_race_finder::score_estimate::estimate_from_db(&db).unwrap();
+
+        // No config file: nothing configured, so the recorded results decide
+        let stats = zwiftpower::fallback_stats(&FullConfig::default(), || {
+            zwift_race_finder::score_estimate::estimate_from_db(&db)
+        });
+        assert_eq!(stats.zwift_score, estimate.zwift_score);
+        assert_eq!(stats.category, estimate.category);
+        assert!(stats.estimate.is_some());
+
+        // Without results either, the usual default
+        let stats = zwiftpower::fallback_stats(&FullConfig::default(), || None);
+        assert_eq!((stats.zwift_score, stats.category.as_str()), (195, "D"));
+    }
+
+    #[test]
+    fn test_watch_rejects_machine_readable_format() {
+        let check = |argv: &[&str]| validate_args(&Args::try_parse_from(argv).unwrap());
+        assert!(check(&["zwift-race-finder", "--watch", "30"]).is_ok());
+        assert!(check(&["zwift-race-finder", "--watch", "30", "--format", "table"]).is_ok());
+        assert!(check(&["zwift-race-finder", "--watch", "30", "--format", "json"]).is_err());
+        assert!(check(&["zwift-race-finder", "--format", "json"]).is_ok());
+    }
+
     #[test]
     fn test_filters_out_running_events() {
         let events = vec![
@@ -690,6 +1464,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -697,7 +1478,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let (filtered, _) = filter_events(events, &args, 195);
@@ -724,6 +1508,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -731,7 +1522,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let (filtered, _) = filter_events(events, &args, 195);
@@ -944,6 +1738,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -951,7 +1752,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let (filtered, _) = filter_events(events.clone(), &args, 195);
@@ -1031,6 +1835,8 @@ mod tests {
                 category_enforcement: None,
                 range_access_label: Some("0-199".to_string()),
                 laps: None,
+                start_location: None,
+                event_subgroup_start: None,
             }],
             sport: "CYCLING".to_string(),
             tags: vec![],
@@ -1045,6 +1851,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -1052,7 +1865,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let events = vec![racing_score_event.clone()];
@@ -1107,6 +1923,8 @@ mod tests {
                     category_enforcement: None,
                     range_access_label: Some("0-650".to_string()),
                     laps: None,
+                    start_location: None,
+                    event_subgroup_start: None,
                 }],
                 sport: "CYCLING".to_string(),
                 tags: vec![],
@@ -1122,6 +1940,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -1129,7 +1954,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let (filtered, _) = filter_events(events, &args, 195);
@@ -1152,6 +1980,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -1159,7 +1994,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let desc = generate_filter_description(&args, 20, 40);
@@ -1203,6 +2041,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -1210,7 +2055,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let suggestions = generate_no_results_suggestions(&args);
@@ -1234,6 +2082,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -1241,7 +2096,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let suggestions = generate_no_results_suggestions(&args);
@@ -1264,6 +2122,13 @@ mod tests {
             debug: false,
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             tags: vec![],
@@ -1271,7 +2136,10 @@ mod tests {
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
+            profile: None,
+            command: None,
         };
 
         let suggestions = generate_no_results_suggestions(&args);
@@ -1296,13 +2164,23 @@ mod tests {
             exclude_tags: vec![],
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
             debug: false,
+            profile: None,
+            command: None,
         };
         let suggestions = generate_no_results_suggestions(&race_args);
         assert!(!suggestions.is_empty());
@@ -1320,13 +2198,23 @@ mod tests {
             exclude_tags: vec![],
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
             debug: false,
+            profile: None,
+            command: None,
         };
         let suggestions = generate_no_results_suggestions(&tt_args);
         assert!(!suggestions.is_empty());
@@ -1343,13 +2231,23 @@ mod tests {
             exclude_tags: vec![],
             show_unknown_routes: false,
             analyze_descriptions: false,
+            format: OutputFormat::Table,
+            ics: None,
+            ics_reminder: None,
+            watch: None,
+            notify_command: None,
+            webhook: None,
+            apply: false,
             record_result: None,
             discover_routes: false,
             mark_complete: None,
             show_progress: false,
             new_routes_only: false,
+            my_events: false,
             verbose: false,
             debug: false,
+            profile: None,
+            command: None,
         };
         let suggestions = generate_no_results_suggestions(&generic_args);
         assert!(!suggestions.is_empty());
@@ -3,6 +3,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::score_estimate::ScoreEstimate;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ZwiftEvent {
@@ -51,6 +53,12 @@ pub struct EventSubGroup {
     pub category_enforcement: Option<bool>,
     pub range_access_label: Option<String>,
     pub laps: Option<u32>,
+    /// Start location (pen); 1 is the route's default start line
+    #[serde(default)]
+    pub start_location: Option<u32>,
+    /// When this pen starts; pens of one event may be staggered
+    #[serde(default)]
+    pub event_subgroup_start: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -58,6 +66,9 @@ pub struct UserStats {
     pub zwift_score: u32,
     pub category: String,
     pub username: String,
+    /// Set when the score was estimated from race history rather than looked up
+    #[serde(skip)]
+    pub estimate: Option<ScoreEstimate>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -110,6 +121,8 @@ mod tests {
                 category_enforcement: None,
                 range_access_label: None, // No range label for traditional events
                 laps: None,
+                start_location: None,
+                event_subgroup_start: None,
             }],
             sport: "CYCLING".to_string(),
             tags: vec![],
@@ -139,6 +152,8 @@ mod tests {
                 category_enforcement: None,
                 range_access_label: Some("0-199".to_string()), // This indicates Racing Score
                 laps: None,
+                start_location: None,
+                event_subgroup_start: None,
             }],
             sport: "CYCLING".to_string(),
             tags: vec![],
@@ -166,4 +181,15 @@ mod tests {
 
         assert!(!is_racing_score_event(&no_subgroups_event));
     }
+    #[test]
+    fn test_subgroup_start_location() {
+        let subgroup: EventSubGroup = serde_json::from_str(
+            r#"{"id": 1, "name": "A", "routeId": 2698009951, "laps": 3, "startLocation": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(subgroup.start_location, Some(2));
+
+        let subgroup: EventSubGroup = serde_json::from_str(r#"{"id": 1, "name": "A"}"#).unwrap();
+        assert_eq!(subgroup.start_location, None);
+    }
 }
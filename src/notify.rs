@@ -0,0 +1,200 @@
+//! Notifications about newly matching events
+//!
+//! `--watch` announces each new match on stdout and, when configured, runs a
+//! desktop notification command and posts to a webhook. The command gets the
+//! title and the body as its last two arguments, so `notify-send` works as
+//! is; the webhook receives JSON with a `text` summary (what Slack-style
+//! incoming webhooks display) and the events in the `--format json` schema.
+
+use crate::event_export::{EventRecord, SCHEMA_VERSION};
+use crate::formatting::format_duration;
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::Serialize;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// How long a webhook gets to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A configured way of announcing matches besides stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Command,
+    Webhook,
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Channel::Command => "notification command",
+            Channel::Webhook => "webhook",
+        })
+    }
+}
+
+/// Where new matches are announced besides stdout
+#[derive(Debug, Clone, Default)]
+pub struct Notifier {
+    /// Program (and leading arguments) run with the title and body appended
+    pub command: Option<String>,
+    /// URL that gets a JSON POST
+    pub webhook: Option<String>,
+}
+
+/// Body of a webhook POST
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub schema_version: u32,
+    pub text: String,
+    pub events: &'a [EventRecord],
+}
+
+impl Notifier {
+    #[must_use]
+    pub fn new(command: Option<String>, webhook: Option<String>) -> Self {
+        Notifier { command, webhook }
+    }
+
+    /// The channels configured, in the order they are tried
+    #[must_use]
+    pub fn channels(&self) -> Vec<Channel> {
+        let mut channels = Vec::new();
+        if self.command.is_some() {
+            channels.push(Channel::Command);
+        }
+        if self.webhook.is_some() {
+            channels.push(Channel::Webhook);
+        }
+        channels
+    }
+
+    /// Announce `events` everywhere configured
+    ///
+    /// Every channel is tried even if an earlier one fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns the failures if the command or the webhook failed.
+    pub async fn notify(&self, events: &[EventRecord]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        announce(events);
+
+        let mut failures = Vec::new();
+        for channel in self.channels() {
+            if let Err(e) = self.deliver(channel, events).await {
+                failures.push(format!("{e:#}"));
+            }
+        }
+        if !failures.is_empty() {
+            bail!(failures.join("; "));
+        }
+        Ok(())
+    }
+
+    /// Send `events` to one channel
+    ///
+    /// Does nothing when there are no events or the channel isn't configured,
+    /// so a channel that failed can be retried on its own.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command or the webhook failed.
+    pub async fn deliver(&self, channel: Channel, events: &[EventRecord]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let title = notification_title(events);
+        let body = notification_body(events);
+        match channel {
+            Channel::Command => match &self.command {
+                Some(command) => run_command(command, &title, &body).await,
+                None => Ok(()),
+            },
+            Channel::Webhook => match &self.webhook {
+                Some(url) => post_webhook(url, &title, &body, events).await,
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Print `events` to stdout
+pub fn announce(events: &[EventRecord]) {
+    if events.is_empty() {
+        return;
+    }
+    println!("🔔 {}", notification_title(events));
+    for line in notification_body(events).lines() {
+        println!("   {line}");
+    }
+}
+
+/// "1 new matching event" / "3 new matching events"
+#[must_use]
+pub fn notification_title(events: &[EventRecord]) -> String {
+    match events.len() {
+        1 => "1 new matching event".to_string(),
+        n => format!("{n} new matching events"),
+    }
+}
+
+/// One line per event: local start, name, route and estimate
+#[must_use]
+pub fn notification_body(events: &[EventRecord]) -> String {
+    events
+        .iter()
+        .map(|event| {
+            let mut line = format!(
+                "{} {}",
+                event.start.with_timezone(&Local).format("%a %H:%M"),
+                event.name
+            );
+            if let Some(name) = event.route.as_ref().and_then(|r| r.name.as_deref()) {
+                let _ = write!(line, " ({name})");
+            }
+            if let Some(minutes) = event.estimated_minutes {
+                let _ = write!(line, ", ~{}", format_duration(minutes));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn run_command(command: &str, title: &str, body: &str) -> Result<()> {
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("empty notification command");
+    };
+    let status = tokio::process::Command::new(program)
+        .args(words)
+        .arg(title)
+        .arg(body)
+        .status()
+        .await
+        .with_context(|| format!("failed to run '{program}'"))?;
+    if !status.success() {
+        bail!("'{program}' exited with {status}");
+    }
+    Ok(())
+}
+
+async fn post_webhook(url: &str, title: &str, body: &str, events: &[EventRecord]) -> Result<()> {
+    let payload = WebhookPayload {
+        schema_version: SCHEMA_VERSION,
+        text: format!("{title}\n{body}"),
+        events,
+    };
+    reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("webhook {url} failed"))?;
+    Ok(())
+}
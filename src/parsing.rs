@@ -1,5 +1,6 @@
 //! Parsing utilities for extracting data from event descriptions and names
 
+use lazy_static::lazy_static;
 use regex::Regex;
 
 // Parse distance and elevation from event description
@@ -110,6 +111,64 @@ pub fn parse_lap_count(name: &str) -> Option<u32> {
     }
 }
 
+lazy_static! {
+    /// "C,D: 2 laps", "Cat A - 45.6 km (4 laps)", "B = 30 km"
+    static ref CATEGORY_COURSE: Regex = Regex::new(
+        r"(?:\b(?i:cat(?:egory)?\.?|group)\s*)?\b([A-E](?:\s*(?:,|/|&|\+|and)\s*[A-E])*)\b\+?\s*[:=|\-–]\s*(?:(\d+)\s*(?i:laps?)\b(?:\s*[(,/\-]?\s*(\d+(?:\.\d+)?)\s*(?i:(km|mi(?:les?)?))\b)?|(\d+(?:\.\d+)?)\s*(?i:(km|mi(?:les?)?))\b(?:\s*[(,/\-]?\s*(\d+)\s*(?i:laps?)\b)?)",
+    )
+    .unwrap();
+}
+
+/// Laps and distance an event description gives for some of its categories
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryCourse {
+    /// Categories the entry applies to, e.g. `['C', 'D']` for "C,D: 2 laps"
+    pub categories: Vec<char>,
+    pub laps: Option<u32>,
+    pub distance_km: Option<f64>,
+}
+
+/// Parse per-category lap counts and distances from an event description
+///
+/// Handles lists like "A: 4 laps / B: 3 laps / C,D: 2 laps" and tables like
+/// "Cat A - 45.6 km (4 laps)". A category listed twice keeps its first entry.
+#[must_use]
+pub fn parse_category_courses(description: &str) -> Vec<CategoryCourse> {
+    let to_km = |value: Option<regex::Match>, unit: Option<regex::Match>| {
+        let distance: f64 = value?.as_str().parse().ok()?;
+        Some(if unit?.as_str().to_lowercase().starts_with("mi") {
+            distance * 1.60934
+        } else {
+            distance
+        })
+    };
+
+    let mut courses: Vec<CategoryCourse> = Vec::new();
+    for caps in CATEGORY_COURSE.captures_iter(description) {
+        let categories: Vec<char> = caps[1]
+            .chars()
+            .filter(|c| ('A'..='E').contains(c))
+            .filter(|c| !courses.iter().any(|course| course.categories.contains(c)))
+            .collect();
+        if categories.is_empty() {
+            continue;
+        }
+        let laps = caps
+            .get(2)
+            .or_else(|| caps.get(7))
+            .and_then(|laps| laps.as_str().parse().ok())
+            .filter(|&laps| laps > 0);
+        let distance_km =
+            to_km(caps.get(3), caps.get(4)).or_else(|| to_km(caps.get(5), caps.get(6)));
+        courses.push(CategoryCourse {
+            categories,
+            laps,
+            distance_km,
+        });
+    }
+    courses
+}
+
 /// Estimate distance from common race name patterns
 pub fn estimate_distance_from_name(name: &str) -> Option<f64> {
     // First try to parse explicit distance from name
@@ -322,4 +381,29 @@ mod tests {
         // Test that km is preferred over miles
         assert_eq!(parse_distance_from_name("10km or 6.2mi"), Some(10.0));
     }
+
+    #[test]
+    fn test_parse_category_courses() {
+        let courses = parse_category_courses("Race distances: A: 4 laps / B: 3 laps / C,D: 2 laps");
+        assert_eq!(courses.len(), 3);
+        assert_eq!(courses[0].categories, vec!['A']);
+        assert_eq!(courses[0].laps, Some(4));
+        assert_eq!(courses[2].categories, vec!['C', 'D']);
+        assert_eq!(courses[2].laps, Some(2));
+        assert_eq!(courses[2].distance_km, None);
+
+        let table = "Distance: 45.6 km\nCat A - 45.6 km (4 laps)\nCat B - 34.2km (3 laps)\nCat C & D - 10 miles";
+        let courses = parse_category_courses(table);
+        assert_eq!(courses.len(), 3);
+        assert_eq!(courses[0].distance_km, Some(45.6));
+        assert_eq!(courses[0].laps, Some(4));
+        assert_eq!(courses[1].categories, vec!['B']);
+        assert_eq!(courses[1].distance_km, Some(34.2));
+        assert_eq!(courses[2].categories, vec!['C', 'D']);
+        assert!((courses[2].distance_km.unwrap() - 16.0934).abs() < 0.001);
+
+        // Plain text and single distances aren't per-category
+        assert!(parse_category_courses("Elevation: 200 m. Distance: 30 km, 3 laps").is_empty());
+        assert!(parse_category_courses("Join us for a fun ride!").is_empty());
+    }
 }
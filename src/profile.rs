@@ -0,0 +1,62 @@
+//! Named rider profiles sharing one installation
+//!
+//! Routes are shared, but each rider has their own racing score source,
+//! physical stats, route completion, results and stats cache. The profile
+//! chosen with `--profile` is set once at startup; [`crate::database::Database`],
+//! [`crate::cache`], [`crate::config`] and [`crate::credentials`] read it from here.
+
+use anyhow::{bail, Result};
+use std::sync::OnceLock;
+
+/// Profile used when `--profile` is not given; owns all data from before profiles existed
+pub const DEFAULT_PROFILE: &str = "default";
+
+static ACTIVE: OnceLock<String> = OnceLock::new();
+
+/// Select the profile for the rest of the run
+pub fn set_active(name: &str) -> Result<()> {
+    validate_name(name)?;
+    if ACTIVE.set(name.to_string()).is_err() && active() != name {
+        bail!("Profile already set to '{}'", active());
+    }
+    Ok(())
+}
+
+/// The selected profile, or [`DEFAULT_PROFILE`]
+#[must_use]
+pub fn active() -> &'static str {
+    ACTIVE.get().map_or(DEFAULT_PROFILE, String::as_str)
+}
+
+/// Whether `name` is the profile that owns pre-profile data
+#[must_use]
+pub fn is_default(name: &str) -> bool {
+    name == DEFAULT_PROFILE
+}
+
+/// Profile names end up in file names and credential keys, so keep them simple
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!("Invalid profile name '{name}': use up to 32 letters, digits, '-' or '_'");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("alice").is_ok());
+        assert!(validate_name("team_b-2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../alice").is_err());
+        assert!(validate_name("alice smith").is_err());
+    }
+}
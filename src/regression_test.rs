@@ -31,9 +31,14 @@ mod tests {
         );
         println!("{}", "-".repeat(80));
 
-        // Limit to first 500 results for faster testing, excluding test races
+        // Limit to first 500 results for faster testing, excluding test races,
+        // DNFs and races ridden outside the rider's own category
         let test_results: Vec<_> = results.into_iter()
-            .filter(|r| r.route_id != 9999 && !r.event_name.starts_with("Test Race"))
+            .filter(|r| {
+                r.route_id != 9999
+                    && !r.event_name.starts_with("Test Race")
+                    && r.usable_for_calibration()
+            })
             .take(500)
             .collect();
 
@@ -48,17 +53,18 @@ mod tests {
                 let mut base_prediction =
                     estimate_duration_from_route_id(result.route_id, result.zwift_score);
 
-                // Apply multi-lap multiplier if known
-                let lap_count = multi_lap_cache
-                    .entry(result.event_name.clone())
-                    .or_insert_with(|| {
-                        db.get_multi_lap_info(&result.event_name)
-                            .unwrap_or(None)
-                    });
+                // Apply multi-lap multiplier if known, preferring the laps
+                // recorded with the result
+                let lap_count = result.laps.or_else(|| {
+                    *multi_lap_cache
+                        .entry(result.event_name.clone())
+                        .or_insert_with(|| {
+                            db.get_multi_lap_info(&result.event_name).unwrap_or(None)
+                        })
+                });
 
                 if let Some(laps) = lap_count {
-                    base_prediction =
-                        base_prediction.map(|d| (d as f64 * (*laps) as f64) as u32);
+                    base_prediction = base_prediction.map(|d| (d as f64 * laps as f64) as u32);
                 }
 
                 base_prediction
@@ -154,8 +160,9 @@ mod tests {
         println!("{}", "-".repeat(80));
 
         for result in results.iter() {
-            // Skip placeholder routes for now
-            if result.route_id == 9999 {
+            // Skip placeholder routes for now, and results that don't reflect
+            // a normal race effort (DNFs, wrong category)
+            if result.route_id == 9999 || !result.usable_for_calibration() {
                 continue;
             }
 
@@ -169,17 +176,18 @@ mod tests {
                 let mut base_prediction =
                     estimate_duration_from_route_id(result.route_id, result.zwift_score);
 
-                // Apply multi-lap multiplier if known
-                let lap_count = multi_lap_cache
-                    .entry(result.event_name.clone())
-                    .or_insert_with(|| {
-                        db.get_multi_lap_info(&result.event_name)
-                            .unwrap_or(None)
-                    });
+                // Apply multi-lap multiplier if known, preferring the laps
+                // recorded with the result
+                let lap_count = result.laps.or_else(|| {
+                    *multi_lap_cache
+                        .entry(result.event_name.clone())
+                        .or_insert_with(|| {
+                            db.get_multi_lap_info(&result.event_name).unwrap_or(None)
+                        })
+                });
 
                 if let Some(laps) = lap_count {
-                    base_prediction =
-                        base_prediction.map(|d| (d as f64 * (*laps) as f64) as u32);
+                    base_prediction = base_prediction.map(|d| (d as f64 * laps as f64) as u32);
                 }
 
                 base_prediction
@@ -295,7 +303,7 @@ mod tests {
 
         for result in results
             .iter()
-            .filter(|r| r.route_id != 9999 && !r.event_name.starts_with("Test Race"))
+            .filter(|r| r.route_id != 9999 && !r.event_name.starts_with("Test Race") && !r.dnf)
         {
             route_times
                 .entry(result.route_id)
@@ -0,0 +1,960 @@
+//! Portable route bundles for sharing route data between databases
+//!
+//! A bundle is a versioned, diff-friendly snapshot of routes, route aliases,
+//! multi-lap patterns and (optionally) race results. Entries are sorted by key
+//! and every section carries a SHA-256 checksum, so a curated route pack can be
+//! kept in git and a truncated or mis-merged file is rejected on import.
+
+use crate::database::{
+    Database, RaceResult, RouteData, RouteProvenance, RouteSegment, RouteSource, SegmentType,
+};
+use crate::profile::{self, DEFAULT_PROFILE};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Current bundle format version
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// File format of a bundle on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleFormat {
+    Json,
+    Toml,
+}
+
+impl BundleFormat {
+    /// Pick the format from a file extension (`.toml` is TOML, anything else JSON)
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => BundleFormat::Toml,
+            _ => BundleFormat::Json,
+        }
+    }
+}
+
+/// How to resolve an entry that exists both locally and in the bundle with different data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Keep the local entry
+    KeepMine,
+    /// Replace the local entry with the bundle's
+    TakeTheirs,
+    /// Keep whichever route was updated most recently; entries without
+    /// timestamps (aliases, multi-lap patterns) keep the local value
+    Newest,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mine" | "keep-mine" => Ok(ConflictStrategy::KeepMine),
+            "theirs" | "take-theirs" => Ok(ConflictStrategy::TakeTheirs),
+            "newest" => Ok(ConflictStrategy::Newest),
+            other => Err(format!(
+                "unknown conflict strategy '{other}' (expected mine, theirs or newest)"
+            )),
+        }
+    }
+}
+
+/// A route entry in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleRoute {
+    pub route_id: u32,
+    pub name: String,
+    pub world: String,
+    pub surface: String,
+    pub distance_km: f64,
+    pub elevation_m: u32,
+    pub lead_in_distance_km: f64,
+    pub lead_in_elevation_m: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_in_distance_free_ride_km: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_in_elevation_free_ride_m: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_in_distance_meetups_km: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_in_elevation_meetups_m: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    /// Last update time of the source row (`YYYY-MM-DD HH:MM:SS`, UTC)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// Where the exporter's data came from (see `RouteSource`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<String>,
+}
+
+impl BundleRoute {
+    fn from_route(
+        route: &RouteData,
+        updated_at: Option<String>,
+        provenance: Option<RouteProvenance>,
+    ) -> Self {
+        let provenance =
+            provenance.unwrap_or_else(|| RouteProvenance::unverified(RouteSource::Unknown));
+        BundleRoute {
+            route_id: route.route_id,
+            name: route.name.clone(),
+            world: route.world.clone(),
+            surface: route.surface.clone(),
+            distance_km: route.distance_km,
+            elevation_m: route.elevation_m,
+            lead_in_distance_km: route.lead_in_distance_km,
+            lead_in_elevation_m: route.lead_in_elevation_m,
+            lead_in_distance_free_ride_km: route.lead_in_distance_free_ride_km,
+            lead_in_elevation_free_ride_m: route.lead_in_elevation_free_ride_m,
+            lead_in_distance_meetups_km: route.lead_in_distance_meetups_km,
+            lead_in_elevation_meetups_m: route.lead_in_elevation_meetups_m,
+            slug: route.slug.clone(),
+            updated_at,
+            source: Some(provenance.source.to_string()),
+            confidence: Some(provenance.confidence),
+            verified_at: provenance.verified_at,
+        }
+    }
+
+    /// Provenance carried by the bundle; bundles from older versions have none
+    fn provenance(&self) -> RouteProvenance {
+        let source = self
+            .source
+            .as_deref()
+            .map_or(RouteSource::Unknown, RouteSource::parse);
+        RouteProvenance {
+            source,
+            confidence: self
+                .confidence
+                .unwrap_or_else(|| source.default_confidence()),
+            verified_at: self.verified_at.clone(),
+        }
+    }
+
+    fn to_route(&self) -> RouteData {
+        RouteData {
+            route_id: self.route_id,
+            distance_km: self.distance_km,
+            elevation_m: self.elevation_m,
+            name: self.name.clone(),
+            world: self.world.clone(),
+            surface: self.surface.clone(),
+            lead_in_distance_km: self.lead_in_distance_km,
+            lead_in_elevation_m: self.lead_in_elevation_m,
+            lead_in_distance_free_ride_km: self.lead_in_distance_free_ride_km,
+            lead_in_elevation_free_ride_m: self.lead_in_elevation_free_ride_m,
+            lead_in_distance_meetups_km: self.lead_in_distance_meetups_km,
+            lead_in_elevation_meetups_m: self.lead_in_elevation_meetups_m,
+            slug: self.slug.clone(),
+        }
+    }
+
+    /// Compare route data, ignoring timestamps and provenance
+    fn same_data(&self, other: &BundleRoute) -> bool {
+        let strip = |route: &BundleRoute| BundleRoute {
+            updated_at: None,
+            source: None,
+            confidence: None,
+            verified_at: None,
+            ..route.clone()
+        };
+        strip(self) == strip(other)
+    }
+}
+
+/// A route alias entry in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleAlias {
+    pub alias_route_id: u32,
+    pub canonical_route_id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// A multi-lap event pattern entry in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleMultiLap {
+    pub event_name_pattern: String,
+    pub route_id: u32,
+    pub lap_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// A route segment entry in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleSegment {
+    pub route_id: u32,
+    /// `kom`, `sprint` or `climb`
+    pub segment_type: String,
+    pub name: String,
+    pub start_km: f64,
+    pub length_km: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_gradient: Option<f64>,
+}
+
+impl BundleSegment {
+    fn from_segment(route_id: u32, segment: &RouteSegment) -> Self {
+        BundleSegment {
+            route_id,
+            segment_type: segment.segment_type.as_str().to_string(),
+            name: segment.name.clone(),
+            start_km: segment.start_km,
+            length_km: segment.length_km,
+            avg_gradient: segment.avg_gradient,
+        }
+    }
+
+    fn to_segment(&self) -> RouteSegment {
+        RouteSegment {
+            segment_type: SegmentType::parse(&self.segment_type).unwrap_or(SegmentType::Climb),
+            name: self.name.clone(),
+            start_km: self.start_km,
+            length_km: self.length_km,
+            avg_gradient: self.avg_gradient,
+        }
+    }
+}
+
+/// A race result entry in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleRaceResult {
+    pub route_id: u32,
+    pub event_name: String,
+    pub actual_minutes: u32,
+    pub zwift_score: u32,
+    pub race_date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field_size: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_power_w: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub np_power_w: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight_kg: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dnf: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub laps: Option<u32>,
+    /// Profile that raced; omitted for the default profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rider: Option<String>,
+}
+
+impl BundleRaceResult {
+    fn from_result(r: RaceResult) -> Self {
+        BundleRaceResult {
+            route_id: r.route_id,
+            event_name: r.event_name,
+            actual_minutes: r.actual_minutes,
+            zwift_score: r.zwift_score,
+            race_date: r.race_date,
+            notes: r.notes,
+            position: r.position,
+            field_size: r.field_size,
+            category: r.category,
+            avg_power_w: r.avg_power_w,
+            np_power_w: r.np_power_w,
+            weight_kg: r.weight_kg,
+            event_id: r.event_id,
+            source: r.source,
+            dnf: r.dnf,
+            laps: r.laps,
+            rider: r.rider.filter(|rider| !profile::is_default(rider)),
+        }
+    }
+
+    fn to_result(&self) -> RaceResult {
+        RaceResult {
+            id: None,
+            route_id: self.route_id,
+            event_name: self.event_name.clone(),
+            actual_minutes: self.actual_minutes,
+            zwift_score: self.zwift_score,
+            race_date: self.race_date.clone(),
+            notes: self.notes.clone(),
+            position: self.position,
+            field_size: self.field_size,
+            category: self.category.clone(),
+            avg_power_w: self.avg_power_w,
+            np_power_w: self.np_power_w,
+            weight_kg: self.weight_kg,
+            event_id: self.event_id,
+            source: self.source.clone(),
+            dnf: self.dnf,
+            laps: self.laps,
+            rider: Some(
+                self.rider
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+            ),
+        }
+    }
+}
+
+/// A complete route bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteBundle {
+    pub format_version: u32,
+    pub exported_at: String,
+    /// SHA-256 of each section's canonical JSON, keyed by section name
+    pub checksums: BTreeMap<String, String>,
+    #[serde(default)]
+    pub routes: Vec<BundleRoute>,
+    #[serde(default)]
+    pub aliases: Vec<BundleAlias>,
+    #[serde(default)]
+    pub multi_lap_patterns: Vec<BundleMultiLap>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<BundleSegment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub race_results: Vec<BundleRaceResult>,
+}
+
+fn section_checksum<T: Serialize>(section: &[T]) -> Result<String> {
+    let canonical = serde_json::to_string(section)?;
+    let digest = Sha256::digest(canonical.as_bytes());
+    Ok(digest.iter().fold(String::with_capacity(64), |mut hex, b| {
+        use std::fmt::Write;
+        let _ = write!(hex, "{b:02x}");
+        hex
+    }))
+}
+
+impl RouteBundle {
+    fn compute_checksums(&self) -> Result<BTreeMap<String, String>> {
+        let mut checksums = BTreeMap::new();
+        checksums.insert("routes".to_string(), section_checksum(&self.routes)?);
+        checksums.insert("aliases".to_string(), section_checksum(&self.aliases)?);
+        checksums.insert(
+            "multi_lap_patterns".to_string(),
+            section_checksum(&self.multi_lap_patterns)?,
+        );
+        if !self.segments.is_empty() {
+            checksums.insert("segments".to_string(), section_checksum(&self.segments)?);
+        }
+        if !self.race_results.is_empty() {
+            checksums.insert(
+                "race_results".to_string(),
+                section_checksum(&self.race_results)?,
+            );
+        }
+        Ok(checksums)
+    }
+
+    /// Build a bundle from the contents of a database
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be read
+    pub fn from_database(db: &Database, include_results: bool) -> Result<Self> {
+        let mut routes = Vec::new();
+        for route in db.get_all_routes()? {
+            let updated_at = db.get_route_updated_at(route.route_id)?;
+            let provenance = db.get_route_provenance(route.route_id)?;
+            routes.push(BundleRoute::from_route(&route, updated_at, provenance));
+        }
+        routes.sort_by_key(|r| r.route_id);
+
+        let mut segments = Vec::new();
+        for route in &routes {
+            for segment in db.get_route_segments(route.route_id)? {
+                segments.push(BundleSegment::from_segment(route.route_id, &segment));
+            }
+        }
+
+        let aliases = db
+            .get_route_aliases()?
+            .into_iter()
+            .map(|(alias_route_id, canonical_route_id, notes)| BundleAlias {
+                alias_route_id,
+                canonical_route_id,
+                notes,
+            })
+            .collect();
+
+        let multi_lap_patterns = db
+            .get_multi_lap_patterns()?
+            .into_iter()
+            .map(
+                |(event_name_pattern, route_id, lap_count, notes)| BundleMultiLap {
+                    event_name_pattern,
+                    route_id,
+                    lap_count,
+                    notes,
+                },
+            )
+            .collect();
+
+        let mut race_results: Vec<BundleRaceResult> = if include_results {
+            db.get_all_race_results()?
+                .into_iter()
+                .map(BundleRaceResult::from_result)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        race_results.sort_by(|a, b| {
+            (&a.race_date, &a.event_name, a.route_id).cmp(&(
+                &b.race_date,
+                &b.event_name,
+                b.route_id,
+            ))
+        });
+
+        let mut bundle = RouteBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            checksums: BTreeMap::new(),
+            routes,
+            aliases,
+            multi_lap_patterns,
+            segments,
+            race_results,
+        };
+        bundle.checksums = bundle.compute_checksums()?;
+        Ok(bundle)
+    }
+
+    /// Check the format version and section checksums
+    ///
+    /// # Errors
+    /// Returns an error naming the first section whose checksum doesn't match
+    pub fn verify(&self) -> Result<()> {
+        if self.format_version > BUNDLE_FORMAT_VERSION {
+            bail!(
+                "Bundle format version {} is newer than supported version {}",
+                self.format_version,
+                BUNDLE_FORMAT_VERSION
+            );
+        }
+
+        let actual = self.compute_checksums()?;
+        for (section, checksum) in &actual {
+            match self.checksums.get(section) {
+                Some(expected) if expected == checksum => {}
+                Some(_) => bail!("Checksum mismatch in '{section}' section"),
+                None => bail!("Missing checksum for '{section}' section"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the bundle in the given format
+    ///
+    /// # Errors
+    /// Returns an error if serialization fails
+    pub fn render(&self, format: BundleFormat) -> Result<String> {
+        Ok(match format {
+            BundleFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+            BundleFormat::Toml => toml::to_string_pretty(self)?,
+        })
+    }
+
+    /// Parse a bundle in the given format
+    ///
+    /// # Errors
+    /// Returns an error if the content is not a valid bundle
+    pub fn parse(content: &str, format: BundleFormat) -> Result<Self> {
+        Ok(match format {
+            BundleFormat::Json => serde_json::from_str(content)?,
+            BundleFormat::Toml => toml::from_str(content)?,
+        })
+    }
+
+    /// Write the bundle to a file, choosing the format from the extension
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let content = self.render(BundleFormat::from_path(path))?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write bundle to {}", path.display()))
+    }
+
+    /// Read and verify a bundle from a file, choosing the format from the extension
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, parsed or verified
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bundle from {}", path.display()))?;
+        let bundle = Self::parse(&content, BundleFormat::from_path(path))
+            .map_err(|e| anyhow!("Invalid bundle {}: {e}", path.display()))?;
+        bundle.verify()?;
+        Ok(bundle)
+    }
+}
+
+/// Per-section import counts
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SectionCounts {
+    /// New entries written
+    pub added: u32,
+    /// Conflicting entries replaced with the bundle's data
+    pub updated: u32,
+    /// Conflicting entries where the local data was kept
+    pub kept: u32,
+    /// Entries identical in both
+    pub unchanged: u32,
+}
+
+/// Result of merging a bundle into a database
+#[derive(Debug, Default, Clone)]
+pub struct ImportSummary {
+    pub routes: SectionCounts,
+    pub aliases: SectionCounts,
+    pub multi_lap_patterns: SectionCounts,
+    /// Counted per route, since a route's segments are replaced as a set
+    pub segments: SectionCounts,
+    pub race_results: SectionCounts,
+}
+
+/// Decide whether the bundle's version of a route wins a conflict
+fn theirs_wins(strategy: ConflictStrategy, mine: Option<&str>, theirs: Option<&str>) -> bool {
+    match strategy {
+        ConflictStrategy::KeepMine => false,
+        ConflictStrategy::TakeTheirs => true,
+        ConflictStrategy::Newest => match (mine, theirs) {
+            (Some(mine), Some(theirs)) => theirs > mine,
+            (None, Some(_)) => true,
+            _ => false,
+        },
+    }
+}
+
+/// Merge bundle routes, never replacing local data with less trusted data
+fn import_routes(
+    db: &Database,
+    routes: &[BundleRoute],
+    strategy: ConflictStrategy,
+    dry_run: bool,
+    counts: &mut SectionCounts,
+) -> Result<()> {
+    for theirs in routes {
+        let existing = db.get_route_direct(theirs.route_id)?;
+        let write = match existing {
+            None => {
+                counts.added += 1;
+                true
+            }
+            Some(route) => {
+                let mine_updated = db.get_route_updated_at(route.route_id)?;
+                let mine_provenance = db.get_route_provenance(route.route_id)?;
+                let mine = BundleRoute::from_route(&route, mine_updated, mine_provenance);
+                if mine.same_data(theirs) {
+                    counts.unchanged += 1;
+                    false
+                } else if mine.provenance().confidence > theirs.provenance().confidence {
+                    // Never trade trusted data for less trusted data
+                    counts.kept += 1;
+                    false
+                } else if theirs_wins(
+                    strategy,
+                    mine.updated_at.as_deref(),
+                    theirs.updated_at.as_deref(),
+                ) {
+                    counts.updated += 1;
+                    true
+                } else {
+                    counts.kept += 1;
+                    false
+                }
+            }
+        };
+
+        if write && !dry_run {
+            db.add_route(&theirs.to_route(), &theirs.provenance())?;
+            if let Some(updated_at) = &theirs.updated_at {
+                db.set_route_updated_at(theirs.route_id, updated_at)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge bundle segments route by route; a route's segment list is
+/// replaced as a whole when the bundle's list wins
+fn import_segments(
+    db: &Database,
+    segments: &[BundleSegment],
+    strategy: ConflictStrategy,
+    dry_run: bool,
+    counts: &mut SectionCounts,
+) -> Result<()> {
+    let mut by_route: BTreeMap<u32, Vec<RouteSegment>> = BTreeMap::new();
+    for segment in segments {
+        by_route
+            .entry(segment.route_id)
+            .or_default()
+            .push(segment.to_segment());
+    }
+
+    for (route_id, theirs) in by_route {
+        let mine = db.get_route_segments(route_id)?;
+        let write = if mine.is_empty() {
+            counts.added += 1;
+            true
+        } else if mine == theirs {
+            counts.unchanged += 1;
+            false
+        } else if strategy == ConflictStrategy::TakeTheirs {
+            counts.updated += 1;
+            true
+        } else {
+            counts.kept += 1;
+            false
+        };
+
+        if write && !dry_run {
+            db.replace_route_segments(route_id, &theirs)?;
+        }
+    }
+    Ok(())
+}
+
+/// Merge a verified bundle into a database
+///
+/// With `dry_run` set, nothing is written but the returned summary reports
+/// what would have changed.
+///
+/// # Errors
+/// Returns an error if the database cannot be read or written
+pub fn import_bundle(
+    db: &Database,
+    bundle: &RouteBundle,
+    strategy: ConflictStrategy,
+    dry_run: bool,
+) -> Result<ImportSummary> {
+    // All or nothing: a failure part way leaves the database as it was
+    db.in_transaction(|db| import_bundle_entries(db, bundle, strategy, dry_run))
+}
+
+fn import_bundle_entries(
+    db: &Database,
+    bundle: &RouteBundle,
+    strategy: ConflictStrategy,
+    dry_run: bool,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+
+    import_routes(db, &bundle.routes, strategy, dry_run, &mut summary.routes)?;
+
+    let local_aliases: BTreeMap<u32, (u32, Option<String>)> = db
+        .get_route_aliases()?
+        .into_iter()
+        .map(|(alias, canonical, notes)| (alias, (canonical, notes)))
+        .collect();
+    for alias in &bundle.aliases {
+        let write = match local_aliases.get(&alias.alias_route_id) {
+            None => {
+                summary.aliases.added += 1;
+                true
+            }
+            Some((canonical, notes))
+                if *canonical == alias.canonical_route_id && *notes == alias.notes =>
+            {
+                summary.aliases.unchanged += 1;
+                false
+            }
+            Some(_) if strategy == ConflictStrategy::TakeTheirs => {
+                summary.aliases.updated += 1;
+                true
+            }
+            Some(_) => {
+                summary.aliases.kept += 1;
+                false
+            }
+        };
+
+        if write && !dry_run {
+            db.add_route_alias(
+                alias.alias_route_id,
+                alias.canonical_route_id,
+                alias.notes.as_deref(),
+            )?;
+        }
+    }
+
+    let local_patterns: BTreeMap<String, (u32, u32, Option<String>)> = db
+        .get_multi_lap_patterns()?
+        .into_iter()
+        .map(|(pattern, route_id, laps, notes)| (pattern, (route_id, laps, notes)))
+        .collect();
+    for pattern in &bundle.multi_lap_patterns {
+        let write = match local_patterns.get(&pattern.event_name_pattern) {
+            None => {
+                summary.multi_lap_patterns.added += 1;
+                true
+            }
+            Some((route_id, laps, notes))
+                if *route_id == pattern.route_id
+                    && *laps == pattern.lap_count
+                    && *notes == pattern.notes =>
+            {
+                summary.multi_lap_patterns.unchanged += 1;
+                false
+            }
+            Some(_) if strategy == ConflictStrategy::TakeTheirs => {
+                summary.multi_lap_patterns.updated += 1;
+                true
+            }
+            Some(_) => {
+                summary.multi_lap_patterns.kept += 1;
+                false
+            }
+        };
+
+        if write && !dry_run {
+            db.add_multi_lap_pattern(
+                &pattern.event_name_pattern,
+                pattern.route_id,
+                pattern.lap_count,
+                pattern.notes.as_deref(),
+            )?;
+        }
+    }
+
+    import_segments(
+        db,
+        &bundle.segments,
+        strategy,
+        dry_run,
+        &mut summary.segments,
+    )?;
+
+    // Race results have no identity beyond their content, so they are only
+    // ever appended: an identical result already in the database is skipped.
+    for r in &bundle.race_results {
+        let result = r.to_result();
+        if db.has_race_result(&result)? {
+            summary.race_results.unchanged += 1;
+        } else {
+            summary.race_results.added += 1;
+            if !dry_run {
+                db.add_race_result(&result)?;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_route(route_id: u32, distance_km: f64) -> RouteData {
+        RouteData {
+            route_id,
+            distance_km,
+            elevation_m: 100,
+            name: format!("Route {route_id}"),
+            world: "Watopia".to_string(),
+            surface: "road".to_string(),
+            lead_in_distance_km: 0.5,
+            ..Default::default()
+        }
+    }
+
+    fn scraped() -> RouteProvenance {
+        RouteProvenance::unverified(RouteSource::WhatsOnZwift)
+    }
+
+    #[test]
+    fn test_conflict_strategy_from_str() {
+        assert_eq!("mine".parse(), Ok(ConflictStrategy::KeepMine));
+        assert_eq!("take-theirs".parse(), Ok(ConflictStrategy::TakeTheirs));
+        assert_eq!("Newest".parse(), Ok(ConflictStrategy::Newest));
+        assert!("latest".parse::<ConflictStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_bundle_round_trip_json_and_toml() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        db.add_route_alias(111, 1_258_415_487, Some("event-only variant"))
+            .unwrap();
+        db.add_multi_lap_pattern("3R Racing", 2_143_464_829, 3, None)
+            .unwrap();
+
+        let bundle = RouteBundle::from_database(&db, false).unwrap();
+        assert!(bundle.verify().is_ok());
+        assert_eq!(bundle.aliases.len(), 1);
+        assert_eq!(bundle.multi_lap_patterns.len(), 1);
+
+        for name in ["pack.json", "pack.toml"] {
+            let path = dir.path().join(name);
+            bundle.write_to_file(&path).unwrap();
+            let loaded = RouteBundle::read_from_file(&path).unwrap();
+            assert_eq!(loaded.routes, bundle.routes);
+            assert_eq!(loaded.aliases, bundle.aliases);
+            assert_eq!(loaded.multi_lap_patterns, bundle.multi_lap_patterns);
+        }
+    }
+
+    #[test]
+    fn test_tampered_bundle_fails_verification() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        let mut bundle = RouteBundle::from_database(&db, false).unwrap();
+        bundle.routes[0].distance_km += 1.0;
+
+        let err = bundle.verify().unwrap_err();
+        assert!(err.to_string().contains("routes"));
+    }
+
+    #[test]
+    fn test_import_conflict_strategies() {
+        let dir = TempDir::new().unwrap();
+        let theirs_db = Database::open(&dir.path().join("theirs.db")).unwrap();
+        theirs_db
+            .add_route(&test_route(42, 12.0), &scraped())
+            .unwrap();
+        theirs_db
+            .set_route_updated_at(42, "2026-01-02 00:00:00")
+            .unwrap();
+        theirs_db
+            .add_route(&test_route(43, 8.0), &scraped())
+            .unwrap();
+        let bundle = RouteBundle::from_database(&theirs_db, false).unwrap();
+
+        let mine_db = Database::open(&dir.path().join("mine.db")).unwrap();
+        mine_db
+            .add_route(&test_route(42, 10.0), &scraped())
+            .unwrap();
+        mine_db
+            .set_route_updated_at(42, "2026-01-01 00:00:00")
+            .unwrap();
+
+        // Dry run writes nothing
+        let summary = import_bundle(&mine_db, &bundle, ConflictStrategy::TakeTheirs, true).unwrap();
+        assert_eq!(summary.routes.added, 1);
+        assert_eq!(summary.routes.updated, 1);
+        assert!(mine_db.get_route(43).unwrap().is_none());
+
+        // Keep mine leaves the conflicting route alone but adds new ones
+        let summary = import_bundle(&mine_db, &bundle, ConflictStrategy::KeepMine, false).unwrap();
+        assert_eq!(summary.routes.kept, 1);
+        assert_eq!(mine_db.get_route(42).unwrap().unwrap().distance_km, 10.0);
+        assert!(mine_db.get_route(43).unwrap().is_some());
+
+        // Newest takes the bundle's route because it was updated later
+        let summary = import_bundle(&mine_db, &bundle, ConflictStrategy::Newest, false).unwrap();
+        assert_eq!(summary.routes.updated, 1);
+        assert_eq!(mine_db.get_route(42).unwrap().unwrap().distance_km, 12.0);
+
+        // Importing again is a no-op
+        let summary = import_bundle(&mine_db, &bundle, ConflictStrategy::Newest, false).unwrap();
+        assert_eq!(summary.routes.added + summary.routes.updated, 0);
+    }
+
+    #[test]
+    fn test_import_keeps_higher_confidence_routes() {
+        let dir = TempDir::new().unwrap();
+        let theirs_db = Database::open(&dir.path().join("theirs.db")).unwrap();
+        theirs_db
+            .add_route(
+                &test_route(42, 12.0),
+                &RouteProvenance::unverified(RouteSource::ZwiftInsider),
+            )
+            .unwrap();
+        let bundle = RouteBundle::from_database(&theirs_db, false).unwrap();
+
+        let mine_db = Database::open(&dir.path().join("mine.db")).unwrap();
+        mine_db
+            .add_route(
+                &test_route(42, 10.0),
+                &RouteProvenance::verified_now(RouteSource::ZwiftOffline),
+            )
+            .unwrap();
+
+        let summary =
+            import_bundle(&mine_db, &bundle, ConflictStrategy::TakeTheirs, false).unwrap();
+        assert_eq!(summary.routes.kept, 1);
+        assert_eq!(mine_db.get_route(42).unwrap().unwrap().distance_km, 10.0);
+        assert_eq!(
+            mine_db.get_route_provenance(42).unwrap().unwrap().source,
+            RouteSource::ZwiftOffline
+        );
+    }
+
+    #[test]
+    fn test_import_race_results_skips_duplicates() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        db.add_race_result(&RaceResult {
+            id: None,
+            route_id: 1_258_415_487,
+            event_name: "Crit City Race".to_string(),
+            actual_minutes: 28,
+            zwift_score: 195,
+            race_date: "2026-03-01".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let bundle = RouteBundle::from_database(&db, true).unwrap();
+        assert_eq!(bundle.race_results.len(), 1);
+
+        let summary = import_bundle(&db, &bundle, ConflictStrategy::KeepMine, false).unwrap();
+        assert_eq!(summary.race_results.added, 0);
+        assert_eq!(summary.race_results.unchanged, 1);
+    }
+
+    #[test]
+    fn test_failed_import_writes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let theirs_db = Database::open(&dir.path().join("theirs.db")).unwrap();
+        theirs_db
+            .add_route(&test_route(43, 8.0), &scraped())
+            .unwrap();
+        theirs_db
+            .add_route_alias(111, 43, Some("event-only variant"))
+            .unwrap();
+        theirs_db
+            .add_race_result(&RaceResult {
+                id: None,
+                route_id: 43,
+                event_name: "Crit City Race".to_string(),
+                actual_minutes: 28,
+                zwift_score: 195,
+                race_date: "2026-03-01".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let bundle = RouteBundle::from_database(&theirs_db, true).unwrap();
+
+        // Race results, written last, can't be stored
+        let path = dir.path().join("mine.db");
+        let mine_db = Database::open(&path).unwrap();
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute("DROP TABLE race_results", [])
+            .unwrap();
+
+        assert!(import_bundle(&mine_db, &bundle, ConflictStrategy::TakeTheirs, false).is_err());
+        assert!(mine_db.get_route(43).unwrap().is_none());
+        assert!(mine_db.get_route_aliases().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+//! Matching of recorded activities to known routes
+//!
+//! Activities imported from Strava and other exports only carry a distance,
+//! an elevation gain and a free-text name. Every route in the database is
+//! scored against those, trying whole lap multiples, and the best candidate is
+//! accepted only when it is both strong and clearly ahead of the runner-up.
+//! Everything else is left for a human to review.
+
+use crate::database::RouteData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Highest lap count tried when matching a route
+pub const MAX_LAPS: u32 = 10;
+
+/// Minimum score for a candidate to be accepted without review
+pub const AUTO_MATCH_SCORE: f64 = 0.8;
+
+/// Minimum score lead over the runner-up for an automatic match
+pub const AMBIGUITY_MARGIN: f64 = 0.1;
+
+/// Candidates scoring below this are not worth showing
+pub const MIN_CANDIDATE_SCORE: f64 = 0.4;
+
+/// Name similarity required for an automatic match when the activity names its route
+///
+/// Keeps e.g. "Volcano Flat" from silently matching "Volcano Flat Reverse".
+pub const STATED_ROUTE_MIN_SIMILARITY: f64 = 0.8;
+
+/// Relative distance error at which the distance score drops to zero
+const DISTANCE_TOLERANCE: f64 = 0.08;
+
+const DISTANCE_WEIGHT: f64 = 0.5;
+const ELEVATION_WEIGHT: f64 = 0.2;
+const NAME_WEIGHT: f64 = 0.3;
+
+/// Words that appear in activity names but say nothing about the route
+const STOP_WORDS: [&str; 12] = [
+    "zwift", "race", "racing", "on", "in", "the", "of", "and", "a", "to", "lap", "laps",
+];
+
+/// What is known about a ridden activity
+#[derive(Debug, Clone, Copy)]
+pub struct ActivityProfile<'a> {
+    pub distance_km: f64,
+    pub elevation_m: Option<f64>,
+    /// Free-text activity name
+    pub name: &'a str,
+    /// Route named by the activity, e.g. `Volcano Flat`
+    pub stated_route: Option<&'a str>,
+}
+
+/// A route that could be the one ridden, with the lap count that fits best
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteCandidate {
+    pub route_id: u32,
+    pub route_name: String,
+    pub laps: u32,
+    pub score: f64,
+    /// How well the route name fits the activity (0.0 - 1.0)
+    #[serde(default)]
+    pub name_score: f64,
+}
+
+/// Result of matching an activity against the route table
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchOutcome {
+    /// One candidate is a confident, unambiguous match
+    Matched(RouteCandidate),
+    /// Several plausible candidates, or one that is not convincing enough
+    Ambiguous(Vec<RouteCandidate>),
+    /// Nothing comes close
+    NoMatch,
+}
+
+/// Score every route against the activity, best first
+///
+/// Each route is tried at 1..=[`MAX_LAPS`] laps (lead-in ridden once) and
+/// keeps its best-scoring lap count. Routes without a known distance and
+/// candidates below [`MIN_CANDIDATE_SCORE`] are dropped.
+#[must_use]
+pub fn score_routes(routes: &[RouteData], activity: &ActivityProfile) -> Vec<RouteCandidate> {
+    let activity_tokens = name_tokens(activity.stated_route.unwrap_or(activity.name));
+    let mut candidates: Vec<RouteCandidate> = routes
+        .iter()
+        .filter(|route| route.distance_km > 0.0)
+        .filter_map(|route| {
+            let name = if activity.stated_route.is_some() {
+                name_similarity(route_name_without_world(route), &activity_tokens)
+            } else {
+                name_coverage(&route.name, &activity_tokens)
+            };
+            (1..=MAX_LAPS)
+                .filter_map(|laps| {
+                    score_laps(route, laps, activity, name).map(|score| (laps, score))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(laps, score)| RouteCandidate {
+                    route_id: route.route_id,
+                    route_name: route.name.clone(),
+                    laps,
+                    score,
+                    name_score: name,
+                })
+        })
+        .filter(|candidate| candidate.score >= MIN_CANDIDATE_SCORE)
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// Decide whether the best candidate can be used without review
+#[must_use]
+pub fn decide(candidates: Vec<RouteCandidate>, activity: &ActivityProfile) -> MatchOutcome {
+    let Some(best) = candidates.first() else {
+        return MatchOutcome::NoMatch;
+    };
+    let clear_lead = candidates
+        .get(1)
+        .is_none_or(|second| best.score - second.score >= AMBIGUITY_MARGIN);
+    let name_agrees =
+        activity.stated_route.is_none() || best.name_score >= STATED_ROUTE_MIN_SIMILARITY;
+
+    if best.score >= AUTO_MATCH_SCORE && clear_lead && name_agrees {
+        MatchOutcome::Matched(best.clone())
+    } else {
+        MatchOutcome::Ambiguous(candidates)
+    }
+}
+
+/// Score one route ridden `laps` times, or `None` if the distance is too far off
+fn score_laps(
+    route: &RouteData,
+    laps: u32,
+    activity: &ActivityProfile,
+    name_score: f64,
+) -> Option<f64> {
+    let laps_f = f64::from(laps);
+    let expected_km = route.lead_in_distance_km + route.distance_km * laps_f;
+    let distance_error = (activity.distance_km - expected_km).abs() / expected_km;
+    let distance = 1.0 - distance_error / DISTANCE_TOLERANCE;
+    if distance <= 0.0 {
+        return None;
+    }
+
+    let (mut score, mut weight) = (distance * DISTANCE_WEIGHT, DISTANCE_WEIGHT);
+    // Routes imported without elevation data store 0m; don't count that against them
+    if let Some(ridden_m) = activity.elevation_m.filter(|_| route.elevation_m > 0) {
+        let expected_climb =
+            f64::from(route.lead_in_elevation_m) + f64::from(route.elevation_m) * laps_f;
+        // Small climbs vary a lot between recordings, so allow at least 50m
+        let elevation = 1.0 - (ridden_m - expected_climb).abs() / expected_climb.max(50.0);
+        score += elevation.max(0.0) * ELEVATION_WEIGHT;
+        weight += ELEVATION_WEIGHT;
+    }
+    score += name_score * NAME_WEIGHT;
+    weight += NAME_WEIGHT;
+
+    Some(score / weight)
+}
+
+/// Fraction of the route name's words that appear in the activity name
+fn name_coverage(route_name: &str, activity_tokens: &HashSet<String>) -> f64 {
+    let route_tokens = name_tokens(route_name);
+    if route_tokens.is_empty() {
+        return 0.0;
+    }
+    let shared = route_tokens.intersection(activity_tokens).count();
+    shared as f64 / route_tokens.len() as f64
+}
+
+/// Route name without a leading `<world> - ` (zwift-offline names look like that)
+fn route_name_without_world(route: &RouteData) -> &str {
+    route
+        .name
+        .split_once(" - ")
+        .filter(|(prefix, _)| prefix.eq_ignore_ascii_case(&route.world))
+        .map_or(&route.name, |(_, rest)| rest)
+}
+
+/// Jaccard similarity of the route name and the route named by the activity
+///
+/// Unlike coverage this penalises extra words, so "Reverse" variants lose.
+fn name_similarity(route_name: &str, stated_tokens: &HashSet<String>) -> f64 {
+    let route_tokens = name_tokens(route_name);
+    let union = route_tokens.union(stated_tokens).count();
+    if union == 0 {
+        return 0.0;
+    }
+    route_tokens.intersection(stated_tokens).count() as f64 / union as f64
+}
+
+fn name_tokens(name: &str) -> HashSet<String> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !STOP_WORDS.contains(token))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(route_id: u32, name: &str, distance_km: f64, elevation_m: u32) -> RouteData {
+        RouteData {
+            route_id,
+            distance_km,
+            elevation_m,
+            name: name.to_string(),
+            world: "Watopia".to_string(),
+            surface: "road".to_string(),
+            lead_in_distance_km: 0.4,
+            lead_in_elevation_m: 2,
+            ..Default::default()
+        }
+    }
+
+    fn routes() -> Vec<RouteData> {
+        vec![
+            route(1, "Coast Crusher", 14.6, 70),
+            route(2, "Eastern Eight", 54.4, 410),
+            route(3, "Flat Route", 10.3, 63),
+            route(4, "Volcano Flat", 12.3, 45),
+            route(5, "Big Flat 8", 29.7, 180),
+        ]
+    }
+
+    #[test]
+    fn test_confident_match_uses_lap_multiple() {
+        let activity = ActivityProfile {
+            distance_km: 44.3,
+            elevation_m: Some(213.0),
+            name: "Zwift - Race: EVR Winter Series (D) on Coast Crusher in Watopia",
+            stated_route: Some("Coast Crusher"),
+        };
+        let candidates = score_routes(&routes(), &activity);
+        match decide(candidates, &activity) {
+            MatchOutcome::Matched(candidate) => {
+                assert_eq!(candidate.route_id, 1);
+                assert_eq!(candidate.laps, 3);
+            }
+            other => panic!("expected a match, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_name_breaks_distance_ties() {
+        let activity = ActivityProfile {
+            distance_km: 54.9,
+            elevation_m: Some(412.0),
+            name: "Zwift - Race: Zwift Epic Race (D) on Eastern Eight in Watopia",
+            stated_route: Some("Eastern Eight"),
+        };
+        let candidates = score_routes(&routes(), &activity);
+        assert_eq!(candidates[0].route_id, 2);
+        assert!(matches!(
+            decide(candidates, &activity),
+            MatchOutcome::Matched(_)
+        ));
+    }
+
+    #[test]
+    fn test_unnamed_activity_needs_review() {
+        // 37km fits 3x Volcano Flat and nearly fits other routes; nothing in the name helps
+        let activity = ActivityProfile {
+            distance_km: 37.3,
+            elevation_m: Some(138.0),
+            name: "Evening ride",
+            stated_route: None,
+        };
+        let candidates = score_routes(&routes(), &activity);
+        assert!(!candidates.is_empty());
+        assert!(matches!(
+            decide(candidates, &activity),
+            MatchOutcome::Ambiguous(_)
+        ));
+    }
+
+    #[test]
+    fn test_no_match_when_distance_is_off() {
+        let activity = ActivityProfile {
+            distance_km: 3.0,
+            elevation_m: None,
+            name: "Warm up",
+            stated_route: None,
+        };
+        assert_eq!(
+            decide(score_routes(&routes(), &activity), &activity),
+            MatchOutcome::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_stated_route_rejects_reverse_variant() {
+        let mut routes = routes();
+        routes[3].name = "Watopia - Volcano Flat Reverse".to_string();
+        let activity = ActivityProfile {
+            distance_km: 12.7,
+            elevation_m: Some(47.0),
+            name: "Zwift - TT: Club Series (C) on Volcano Flat in Watopia",
+            stated_route: Some("Volcano Flat"),
+        };
+        let candidates = score_routes(&routes, &activity);
+        assert_eq!(candidates[0].route_id, 4);
+        assert!(matches!(
+            decide(candidates.clone(), &activity),
+            MatchOutcome::Ambiguous(_)
+        ));
+
+        // The world prefix itself does not count against an exact route name
+        routes[3].name = "Watopia - Volcano Flat".to_string();
+        let candidates = score_routes(&routes, &activity);
+        assert!(matches!(
+            decide(candidates, &activity),
+            MatchOutcome::Matched(_)
+        ));
+    }
+}
@@ -0,0 +1,368 @@
+//! Fuzzy matching of free-text route names to known routes
+//!
+//! Event names and descriptions name their route loosely: "Stage 4: Three
+//! Village Loop || Advanced", "Volcano Circ. x3", "Coast Crusher (Cat B)".
+//! Names are normalised (series prefixes, category and lap suffixes,
+//! abbreviations), compared word by word with some tolerance for typos and
+//! nudged towards routes in the world the name mentions. Candidates come back
+//! ranked with a score so callers can decide how sure they need to be.
+
+use crate::database::RouteData;
+use crate::route_providers::{detect_world, extract_route_name};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Candidates scoring below this are not worth showing
+pub const MIN_NAME_SCORE: f64 = 0.5;
+
+/// Minimum score for a name match to be used without review
+pub const CONFIDENT_NAME_SCORE: f64 = 0.85;
+
+/// Minimum score lead over the runner-up for a confident match
+pub const NAME_AMBIGUITY_MARGIN: f64 = 0.1;
+
+/// Share of the score from route words found in the name (the rest is the reverse)
+const COVERAGE_WEIGHT: f64 = 0.75;
+
+/// Score adjustment when the name points at the route's world, or another one
+const WORLD_BONUS: f64 = 0.05;
+const WORLD_PENALTY: f64 = 0.1;
+
+/// Similarity given to a word that abbreviates another ("circ" for "circuit")
+const PREFIX_SIMILARITY: f64 = 0.9;
+
+/// Edit-distance similarity below which two words are considered different
+const MIN_TYPO_SIMILARITY: f64 = 0.75;
+
+/// Words that appear in event names but say nothing about the route
+const STOP_WORDS: [&str; 17] = [
+    "zwift", "race", "racing", "ride", "series", "stage", "the", "of", "on", "in", "and", "a",
+    "to", "lap", "laps", "advanced", "beginner",
+];
+
+/// Common abbreviations and their full words
+const ABBREVIATIONS: [(&str, &str); 5] = [
+    ("circ", "circuit"),
+    ("rev", "reverse"),
+    ("mtn", "mountain"),
+    ("mt", "mountain"),
+    ("fig", "figure"),
+];
+
+lazy_static! {
+    /// `(Cat B)`, `(B)`, `Cat B`, `(A+)`
+    static ref CATEGORY: Regex =
+        Regex::new(r"(?i)\((?:cat\.?\s*)?[a-e]\+?\)|\bcat\.?\s*[a-e]\+?\b").unwrap();
+
+    /// `x3`, `3x`, `3 laps`, `lap 2`
+    static ref LAP_COUNT: Regex =
+        Regex::new(r"(?i)\bx\s*\d+\b|\b\d+\s*x\b|\b\d+\s*laps?\b|\blaps?\s*\d+\b").unwrap();
+
+    /// `24.5 km`, `15mi`
+    static ref DISTANCE: Regex = Regex::new(r"(?i)\b\d+(?:\.\d+)?\s*(?:km|mi)\b").unwrap();
+}
+
+/// A known route whose name resembles the one being looked up
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameMatch {
+    pub route_id: u32,
+    pub route_name: String,
+    pub world: String,
+    /// How closely the names match (0.0 - 1.0)
+    pub score: f64,
+}
+
+/// Reduce an event or route name to the words that identify the route
+///
+/// "Stage 4: Volcano Circ. x3 (Cat B)" becomes "volcano circuit".
+#[must_use]
+pub fn normalize_route_name(name: &str) -> String {
+    name_tokens(&extract_route_name(name)).join(" ")
+}
+
+/// Score every route against `name`, best first
+///
+/// Candidates below [`MIN_NAME_SCORE`] are dropped.
+#[must_use]
+pub fn rank_routes(name: &str, routes: &[RouteData]) -> Vec<NameMatch> {
+    let query = name_tokens(&extract_route_name(name));
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let world_hint = detect_world(name);
+
+    let mut matches: Vec<NameMatch> = routes
+        .iter()
+        .filter_map(|route| {
+            let route_tokens = name_tokens(route_name_without_world(route));
+            if route_tokens.is_empty() {
+                return None;
+            }
+            let coverage = token_overlap(&route_tokens, &query);
+            let precision = token_overlap(&query, &route_tokens);
+            let mut score = COVERAGE_WEIGHT * coverage + (1.0 - COVERAGE_WEIGHT) * precision;
+            if let Some(hint) = world_hint {
+                if world_slug(&route.world) == hint {
+                    score += WORLD_BONUS;
+                } else if !route.world.is_empty() {
+                    score -= WORLD_PENALTY;
+                }
+            }
+            let score = score.clamp(0.0, 1.0);
+            (score >= MIN_NAME_SCORE).then(|| NameMatch {
+                route_id: route.route_id,
+                route_name: route.name.clone(),
+                world: route.world.clone(),
+                score,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.route_name.len().cmp(&b.route_name.len()))
+    });
+    matches
+}
+
+/// The route `name` refers to, if one match is both strong and unambiguous
+///
+/// Requires [`CONFIDENT_NAME_SCORE`] and a lead of [`NAME_AMBIGUITY_MARGIN`]
+/// over the runner-up, so "Volcano Flat" never silently becomes
+/// "Volcano Flat Reverse".
+#[must_use]
+pub fn best_route_match(name: &str, routes: &[RouteData]) -> Option<NameMatch> {
+    confident_match(rank_routes(name, routes))
+}
+
+/// The top of an already ranked candidate list, if it is confident
+#[must_use]
+pub fn confident_match(ranked: Vec<NameMatch>) -> Option<NameMatch> {
+    let mut ranked = ranked.into_iter();
+    let best = ranked.next()?;
+    let runner_up = ranked.next().map_or(0.0, |m| m.score);
+    (best.score >= CONFIDENT_NAME_SCORE && best.score - runner_up >= NAME_AMBIGUITY_MARGIN)
+        .then_some(best)
+}
+
+/// Name to match for an `unknown_routes` entry
+///
+/// Entries logged with a route parsed from the event description read
+/// "<event> -> <route> (<n> laps)"; the parsed route is the better guess.
+#[must_use]
+pub fn unknown_route_query(event_name: &str) -> &str {
+    event_name
+        .rsplit_once(" -> ")
+        .map_or(event_name, |(_, route)| route)
+}
+
+/// Average over `from` of each word's best similarity to a word in `to`
+#[allow(clippy::cast_precision_loss)]
+fn token_overlap(from: &[String], to: &[String]) -> f64 {
+    if from.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = from
+        .iter()
+        .map(|a| {
+            to.iter()
+                .map(|b| token_similarity(a, b))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    total / from.len() as f64
+}
+
+/// Similarity of two words: equal, one abbreviating the other, or a typo apart
+#[allow(clippy::cast_precision_loss)]
+fn token_similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if shorter.len() >= 3 && longer.starts_with(shorter) {
+        return PREFIX_SIMILARITY;
+    }
+    if shorter.len() < 4 {
+        return 0.0;
+    }
+    let longest = a.chars().count().max(b.chars().count());
+    let similarity = 1.0 - edit_distance(a, b) as f64 / longest as f64;
+    if similarity >= MIN_TYPO_SIMILARITY {
+        similarity
+    } else {
+        0.0
+    }
+}
+
+/// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Lowercase words of a name without category, lap and distance markers
+fn name_tokens(name: &str) -> Vec<String> {
+    let name = CATEGORY.replace_all(name, " ");
+    let name = LAP_COUNT.replace_all(&name, " ");
+    let name = DISTANCE.replace_all(&name, " ");
+
+    let mut tokens: Vec<String> = Vec::new();
+    for word in name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(word))
+    {
+        let word = ABBREVIATIONS
+            .iter()
+            .find(|(short, _)| *short == word)
+            .map_or(word, |(_, full)| full);
+        if !tokens.iter().any(|token| token == word) {
+            tokens.push(word.to_string());
+        }
+    }
+    tokens
+}
+
+/// Route name without a leading `<world> - ` (zwift-offline names look like that)
+fn route_name_without_world(route: &RouteData) -> &str {
+    route
+        .name
+        .split_once(" - ")
+        .filter(|(prefix, _)| prefix.eq_ignore_ascii_case(&route.world))
+        .map_or(&route.name, |(_, rest)| rest)
+}
+
+/// World name in the form [`detect_world`] returns, e.g. "Makuri Islands" -> "makuri-islands"
+fn world_slug(world: &str) -> String {
+    world.trim().to_lowercase().replace(' ', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(route_id: u32, name: &str, world: &str) -> RouteData {
+        RouteData {
+            route_id,
+            distance_km: 10.0,
+            elevation_m: 50,
+            name: name.to_string(),
+            world: world.to_string(),
+            surface: "road".to_string(),
+            lead_in_distance_km: 0.4,
+            lead_in_elevation_m: 2,
+            ..Default::default()
+        }
+    }
+
+    fn routes() -> Vec<RouteData> {
+        vec![
+            route(1, "Volcano Circuit", "Watopia"),
+            route(2, "Volcano Circuit CCW", "Watopia"),
+            route(3, "Volcano Flat", "Watopia"),
+            route(4, "Volcano Flat Reverse", "Watopia"),
+            route(5, "Three Village Loop", "Makuri Islands"),
+            route(6, "Watopia - Coast Crusher", "Watopia"),
+            route(7, "Triple Loops", "London"),
+        ]
+    }
+
+    #[test]
+    fn test_normalize_route_name() {
+        assert_eq!(
+            normalize_route_name("Stage 4: Volcano Circ. x3 (Cat B)"),
+            "volcano circuit"
+        );
+        assert_eq!(
+            normalize_route_name("Stage 4: Three Village Loop || Advanced"),
+            "three village loop"
+        );
+        assert_eq!(
+            normalize_route_name("Figure 8 - 2 laps (24.5 km)"),
+            "figure 8"
+        );
+    }
+
+    #[test]
+    fn test_rank_routes_handles_decorated_names() {
+        let routes = routes();
+
+        let best = best_route_match("Stage 4: Three Village Loop || Advanced", &routes).unwrap();
+        assert_eq!(best.route_id, 5);
+        assert!((best.score - 1.0).abs() < 1e-9);
+
+        let best = best_route_match("Volcano Circ. x3", &routes).unwrap();
+        assert_eq!(best.route_id, 1);
+
+        // World prefixes on stored names don't count against the match
+        let best = best_route_match("EVR Winter Series Coast Crusher (Cat C)", &routes).unwrap();
+        assert_eq!(best.route_id, 6);
+
+        // Typos are tolerated
+        let best = best_route_match("Three Vilage Loop", &routes).unwrap();
+        assert_eq!(best.route_id, 5);
+    }
+
+    #[test]
+    fn test_rank_routes_prefers_exact_variant() {
+        let ranked = rank_routes("Volcano Flat", &routes());
+        assert_eq!(ranked[0].route_id, 3);
+        assert_eq!(ranked[1].route_id, 4);
+        assert!(ranked[0].score > ranked[1].score);
+        assert!(best_route_match("Volcano Flat", &routes()).is_some());
+    }
+
+    #[test]
+    fn test_world_hint_breaks_ties() {
+        let routes = vec![
+            route(1, "Triple Loops", "London"),
+            route(2, "Triple Loops", "Watopia"),
+        ];
+        let ranked = rank_routes("London Triple Loops", &routes);
+        assert_eq!(ranked[0].route_id, 1);
+        assert!(ranked[0].score > ranked[1].score);
+    }
+
+    #[test]
+    fn test_unrelated_or_vague_names_are_not_confident() {
+        let routes = routes();
+        assert!(rank_routes("Mystery Race", &routes).is_empty());
+        // Plausible for several routes, so not good enough on its own
+        assert!(best_route_match("Volcano", &routes).is_none());
+        assert!(!rank_routes("Volcano", &routes).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_route_query() {
+        assert_eq!(
+            unknown_route_query("Sunday Race -> Volcano Circuit (3 laps)"),
+            "Volcano Circuit (3 laps)"
+        );
+        assert_eq!(unknown_route_query("Sunday Race"), "Sunday Race");
+        let best = best_route_match(
+            unknown_route_query("Sunday Race -> Volcano Circuit (3 laps)"),
+            &routes(),
+        )
+        .unwrap();
+        assert_eq!(best.route_id, 1);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("volcano", "volcano"), 0);
+        assert_eq!(edit_distance("volcanoe", "volcano"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,762 @@
+//! Sites and saved pages that route discovery reads route data from
+//!
+//! A [`RouteProvider`] knows where a source keeps its route pages and how to
+//! read them: [`RouteProvider::candidate_pages`] lists the pages worth trying
+//! for an event and [`RouteProvider::parse_route`] reads one once fetched.
+//! Fetching (and caching responses) is left to
+//! [`RouteDiscovery`](crate::route_discovery::RouteDiscovery), so parsers can
+//! be tested against saved HTML. [`LocalPages`] reads pages saved to a
+//! directory, letting discovery run without network access.
+
+use crate::database::{RouteSegment, RouteSource, SegmentType};
+use crate::route_discovery::DiscoveredRoute;
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use std::path::PathBuf;
+
+/// Where a page can be read from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PageLocation {
+    Url(String),
+    File(PathBuf),
+}
+
+impl std::fmt::Display for PageLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageLocation::Url(url) => write!(f, "{url}"),
+            PageLocation::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A source of route pages
+pub trait RouteProvider: Send + Sync {
+    /// Name shown in progress messages
+    fn name(&self) -> &'static str;
+
+    /// Pages that may describe the route raced in `event_name`, most likely first
+    fn candidate_pages(&self, event_name: &str) -> Vec<PageLocation>;
+
+    /// Read the route from a fetched page
+    ///
+    /// # Errors
+    /// Returns an error if the page isn't a route page or lacks distance or elevation
+    fn parse_route(
+        &self,
+        page: &PageLocation,
+        html: &str,
+        event_name: &str,
+    ) -> Result<DiscoveredRoute>;
+
+    /// Route pages linked from a page that wasn't one itself, such as a route listing
+    fn linked_pages(&self, _html: &str, _event_name: &str) -> Vec<PageLocation> {
+        Vec::new()
+    }
+}
+
+/// The providers used when none are configured: whatsonzwift.com, then zwiftinsider.com
+#[must_use]
+pub fn default_providers() -> Vec<Box<dyn RouteProvider>> {
+    vec![Box::new(WhatsOnZwift), Box::new(ZwiftInsider)]
+}
+
+/// whatsonzwift.com, whose route pages live under `/world/<world>/route/<slug>`
+pub struct WhatsOnZwift;
+
+const WHATSONZWIFT_WORLDS: [&str; 5] =
+    ["watopia", "makuri-islands", "london", "new-york", "france"];
+
+impl RouteProvider for WhatsOnZwift {
+    fn name(&self) -> &'static str {
+        "whatsonzwift.com"
+    }
+
+    fn candidate_pages(&self, event_name: &str) -> Vec<PageLocation> {
+        let slug = route_slug(&extract_route_name(event_name));
+
+        // The world named in the event is tried first
+        let mut worlds = WHATSONZWIFT_WORLDS.to_vec();
+        if let Some(world) = detect_world(event_name) {
+            worlds.retain(|&w| w != world);
+            worlds.insert(0, world);
+        }
+
+        worlds
+            .into_iter()
+            .map(|world| {
+                PageLocation::Url(format!(
+                    "https://whatsonzwift.com/world/{world}/route/{slug}"
+                ))
+            })
+            .collect()
+    }
+
+    fn parse_route(
+        &self,
+        page: &PageLocation,
+        html: &str,
+        event_name: &str,
+    ) -> Result<DiscoveredRoute> {
+        // Unknown slugs come back as a search or 404 page rather than an error status
+        if !(html.contains("Distance:") && html.contains("Elevation:")) {
+            bail!("Not a whatsonzwift.com route page: {page}");
+        }
+        let mut route = parse_whatsonzwift_route(html, event_name)?;
+        // The URL names the world more reliably than the page text
+        if let PageLocation::Url(url) = page {
+            if let Some(world) = url
+                .split("/world/")
+                .nth(1)
+                .and_then(|rest| rest.split('/').next())
+            {
+                route.world = format_world_name(world);
+            }
+        }
+        Ok(route)
+    }
+}
+
+/// zwiftinsider.com, whose route pages live under `/route/<slug>/`
+pub struct ZwiftInsider;
+
+const ZWIFTINSIDER_ROUTES_URL: &str = "https://zwiftinsider.com/routes/";
+
+impl RouteProvider for ZwiftInsider {
+    fn name(&self) -> &'static str {
+        "zwiftinsider.com"
+    }
+
+    fn candidate_pages(&self, event_name: &str) -> Vec<PageLocation> {
+        let slug = route_slug(&extract_route_name(event_name));
+        vec![
+            PageLocation::Url(format!("https://zwiftinsider.com/route/{slug}/")),
+            // When the slug guess fails, the listing links to every route
+            PageLocation::Url(ZWIFTINSIDER_ROUTES_URL.to_string()),
+        ]
+    }
+
+    fn parse_route(
+        &self,
+        page: &PageLocation,
+        html: &str,
+        event_name: &str,
+    ) -> Result<DiscoveredRoute> {
+        if matches!(page, PageLocation::Url(url) if url == ZWIFTINSIDER_ROUTES_URL)
+            || !(html.contains("Length") || html.contains("Distance"))
+        {
+            bail!("Not a zwiftinsider.com route page: {page}");
+        }
+        parse_zwiftinsider_route(html, event_name)
+    }
+
+    fn linked_pages(&self, html: &str, event_name: &str) -> Vec<PageLocation> {
+        let cleaned_name = extract_route_name(event_name);
+        let mut pages = Vec::new();
+
+        // Route links containing a significant word of the route name
+        for word in cleaned_name.split_whitespace().filter(|w| w.len() > 3) {
+            let pattern = format!(
+                r#"href="(?:https://zwiftinsider\.com)?(/route/[^"]*{}[^"]*)"#,
+                regex::escape(&word.to_lowercase())
+            );
+            let Ok(regex) = Regex::new(&pattern) else {
+                continue;
+            };
+            for captures in regex.captures_iter(html) {
+                let page = PageLocation::Url(format!("https://zwiftinsider.com{}", &captures[1]));
+                if !pages.contains(&page) {
+                    pages.push(page);
+                }
+            }
+        }
+
+        pages
+    }
+}
+
+/// Route pages saved to a directory as `<route-slug>.html`
+///
+/// Pages saved from whatsonzwift.com or zwiftinsider.com are read with that
+/// site's parser and keep it as the route's source.
+pub struct LocalPages {
+    dir: PathBuf,
+}
+
+impl LocalPages {
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl RouteProvider for LocalPages {
+    fn name(&self) -> &'static str {
+        "saved pages"
+    }
+
+    fn candidate_pages(&self, event_name: &str) -> Vec<PageLocation> {
+        let slug = route_slug(&extract_route_name(event_name));
+        vec![PageLocation::File(self.dir.join(format!("{slug}.html")))]
+    }
+
+    fn parse_route(
+        &self,
+        page: &PageLocation,
+        html: &str,
+        event_name: &str,
+    ) -> Result<DiscoveredRoute> {
+        if html.contains("whatsonzwift.com")
+            || (html.contains("Distance:") && html.contains("Elevation:"))
+        {
+            WhatsOnZwift.parse_route(page, html, event_name)
+        } else {
+            ZwiftInsider.parse_route(page, html, event_name)
+        }
+    }
+}
+
+/// Detect the world an event is held in from its name, as a whatsonzwift.com slug
+#[must_use]
+pub fn detect_world(event_name: &str) -> Option<&'static str> {
+    let event_lower = event_name.to_lowercase();
+
+    // Direct world mentions
+    if event_lower.contains("makuri")
+        || event_lower.contains("neokyo")
+        || event_lower.contains("yumezi")
+    {
+        return Some("makuri-islands");
+    }
+
+    if event_lower.contains("london")
+        || event_lower.contains("box hill")
+        || event_lower.contains("keith hill")
+    {
+        return Some("london");
+    }
+
+    if event_lower.contains("new york")
+        || event_lower.contains("central park")
+        || event_lower.contains("knickerbocker")
+    {
+        return Some("new-york");
+    }
+
+    if event_lower.contains("france")
+        || event_lower.contains("ventoux")
+        || event_lower.contains("casse-pattes")
+    {
+        return Some("france");
+    }
+
+    if event_lower.contains("richmond") || event_lower.contains("virginia") {
+        return Some("richmond");
+    }
+
+    if event_lower.contains("innsbruck") || event_lower.contains("austria") {
+        return Some("innsbruck");
+    }
+
+    if event_lower.contains("yorkshire") || event_lower.contains("harrogate") {
+        return Some("yorkshire");
+    }
+
+    if event_lower.contains("paris") || event_lower.contains("champs") {
+        return Some("paris");
+    }
+
+    if event_lower.contains("scotland") || event_lower.contains("glasgow") {
+        return Some("scotland");
+    }
+
+    // Watopia-specific routes (most common, check last)
+    if event_lower.contains("alpe")
+        || event_lower.contains("epic")
+        || event_lower.contains("jungle")
+        || event_lower.contains("volcano")
+        || event_lower.contains("titan")
+        || event_lower.contains("fuego")
+    {
+        return Some("watopia");
+    }
+
+    // Common event series with known worlds
+    if event_lower.contains("tour de zwift") {
+        // Tour de Zwift rotates but often starts in Watopia
+        return Some("watopia");
+    }
+
+    None
+}
+
+/// Extract the actual route name from event names
+#[must_use]
+pub fn extract_route_name(event_name: &str) -> String {
+    let name = event_name.to_string();
+
+    // Remove common prefixes
+    let name = if let Some(idx) = name.find(':') {
+        name[idx + 1..].trim().to_string()
+    } else {
+        name
+    };
+
+    // Remove suffixes after || or |
+    let name = if let Some(idx) = name.find("||") {
+        name[..idx].trim().to_string()
+    } else if let Some(idx) = name.find('|') {
+        name[..idx].trim().to_string()
+    } else {
+        name
+    };
+
+    name
+}
+
+/// URL slug for a route name, e.g. "Volcano Circuit (CCW)" -> "volcano-circuit-ccw"
+#[must_use]
+pub fn route_slug(route_name: &str) -> String {
+    route_name
+        .to_lowercase()
+        .replace(' ', "-")
+        .replace(['\'', '(', ')'], "")
+}
+
+/// Format world name for display
+pub(crate) fn format_world_name(world_slug: &str) -> String {
+    match world_slug {
+        "makuri-islands" => "Makuri Islands".to_string(),
+        "new-york" => "New York".to_string(),
+        _ => {
+            // Capitalize first letter of each word
+            world_slug
+                .split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        None => String::new(),
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+}
+
+/// Parse route data from whatsonzwift.com HTML
+fn parse_whatsonzwift_route(html: &str, event_name: &str) -> Result<DiscoveredRoute> {
+    // Extract route ID from the page
+    // whatsonzwift.com includes route IDs in various places:
+    // 1. In JavaScript: routeId: 123
+    // 2. In data attributes: data-route-id="123"
+    // 3. In API calls: /api/routes/123
+    let route_id = if let Ok(route_id_regex) =
+        Regex::new(r#"(?:routeId:\s*|data-route-id="|/api/routes/)(\d+)"#)
+    {
+        route_id_regex
+            .captures(html)
+            .and_then(|cap| cap.get(1))
+            .and_then(|m| m.as_str().parse::<u32>().ok())
+            .unwrap_or(9999)
+    } else {
+        9999
+    };
+
+    // Extract distance (e.g., "Distance: 10.6km")
+    let distance_regex = Regex::new(r"Distance:\s*([0-9.]+)\s*km")?;
+    let distance_km = distance_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .and_then(|m| m.as_str().parse::<f64>().ok())
+        .ok_or_else(|| anyhow!("Could not parse distance"))?;
+
+    // Extract elevation (e.g., "Elevation: 145m")
+    let elevation_regex = Regex::new(r"Elevation:\s*([0-9,]+)\s*m")?;
+    let elevation_str = elevation_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().replace(',', ""))
+        .ok_or_else(|| anyhow!("Could not parse elevation"))?;
+    let elevation_m = elevation_str.parse::<u32>()?;
+
+    // Extract world (e.g., "World: Makuri Islands")
+    let world_regex = Regex::new(r"World:\s*([^<]+)")?;
+    let world = world_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().trim().to_string())
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    // Extract route name from title or heading
+    let name_regex = Regex::new(r"<h1[^>]*>([^<]+)</h1>")?;
+    let name = name_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().trim().to_string())
+        .unwrap_or_else(|| event_name.to_string());
+
+    // Determine surface type (default to road, check for gravel/mixed indicators)
+    let surface = if html.contains("gravel") || html.contains("Gravel") {
+        "gravel".to_string()
+    } else if html.contains("mixed surface") || html.contains("Mixed") {
+        "mixed".to_string()
+    } else {
+        "road".to_string()
+    };
+
+    Ok(DiscoveredRoute {
+        route_id,
+        name,
+        distance_km,
+        elevation_m,
+        world,
+        surface,
+        source: RouteSource::WhatsOnZwift,
+        segments: parse_whatsonzwift_segments(html),
+    })
+}
+
+/// Parse route data from zwiftinsider.com HTML
+fn parse_zwiftinsider_route(html: &str, event_name: &str) -> Result<DiscoveredRoute> {
+    // Zwift Insider uses slightly different format
+    // Look for route stats table or info box
+
+    // Extract distance
+    let distance_regex = Regex::new(r"(?:Length|Distance)[:\s]*([0-9.]+)\s*km")?;
+    let distance_km = distance_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .and_then(|m| m.as_str().parse::<f64>().ok())
+        .ok_or_else(|| anyhow!("Could not parse distance"))?;
+
+    // Extract elevation
+    let elevation_regex = Regex::new(r"(?:Elevation|Gain)[:\s]*([0-9,]+)\s*m")?;
+    let elevation_str = elevation_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().replace(',', ""))
+        .ok_or_else(|| anyhow!("Could not parse elevation"))?;
+    let elevation_m = elevation_str.parse::<u32>()?;
+
+    // Extract world
+    let world_regex = Regex::new(r"(?:World|Location)[:\s]*([^<\n]+)")?;
+    let world = world_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().trim().to_string())
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    // Extract route name
+    let name_regex = Regex::new(r"<h1[^>]*>([^<]+)</h1>")?;
+    let name = name_regex
+        .captures(html)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().trim().to_string())
+        .unwrap_or_else(|| event_name.to_string());
+
+    // Determine surface type
+    let surface = if html.contains("gravel") || html.contains("dirt") {
+        "gravel".to_string()
+    } else if html.contains("mixed") {
+        "mixed".to_string()
+    } else {
+        "road".to_string()
+    };
+
+    Ok(DiscoveredRoute {
+        route_id: 9999, // Placeholder
+        name,
+        distance_km,
+        elevation_m,
+        world,
+        surface,
+        source: RouteSource::ZwiftInsider,
+        segments: Vec::new(),
+    })
+}
+
+/// Parse the segments table from a whatsonzwift.com route page
+///
+/// Looks for a table whose header has length and gradient columns, e.g.
+/// `Type | Segment | Starts at | Length | Avg. grade`, and maps columns by
+/// header text so reordered or missing columns still parse. Rows whose type
+/// can't be told from the type column or the segment name are skipped.
+#[must_use]
+pub fn parse_whatsonzwift_segments(html: &str) -> Vec<RouteSegment> {
+    let (Ok(table_regex), Ok(row_regex), Ok(cell_regex), Ok(tag_regex)) = (
+        Regex::new(r"(?is)<table[^>]*>(.*?)</table>"),
+        Regex::new(r"(?is)<tr[^>]*>(.*?)</tr>"),
+        Regex::new(r"(?is)<t[hd][^>]*>(.*?)</t[hd]>"),
+        Regex::new(r"<[^>]+>"),
+    ) else {
+        return Vec::new();
+    };
+
+    let cells = |row: &str| -> Vec<String> {
+        cell_regex
+            .captures_iter(row)
+            .map(|cap| {
+                tag_regex
+                    .replace_all(&cap[1], " ")
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    };
+
+    for table in table_regex.captures_iter(html) {
+        let mut rows = row_regex.captures_iter(&table[1]);
+        let Some(header) = rows.next().map(|cap| {
+            cells(&cap[1])
+                .into_iter()
+                .map(|h| h.to_lowercase())
+                .collect::<Vec<_>>()
+        }) else {
+            continue;
+        };
+        let column = |keywords: &[&str]| {
+            header
+                .iter()
+                .position(|h| keywords.iter().any(|k| h.contains(k)))
+        };
+
+        let (Some(length_col), Some(gradient_col)) =
+            (column(&["length"]), column(&["grade", "gradient"]))
+        else {
+            continue;
+        };
+        let type_col = column(&["type"]);
+        let name_col = column(&["segment", "name"]);
+        let start_col = column(&["start", "at km", "position"]);
+
+        let segments: Vec<RouteSegment> = rows
+            .filter_map(|row| {
+                let row = cells(&row[1]);
+                let cell = |col: Option<usize>| col.and_then(|c| row.get(c)).map(String::as_str);
+
+                let name = cell(name_col).filter(|n| !n.is_empty())?.to_string();
+                let segment_type = cell(type_col)
+                    .and_then(SegmentType::parse)
+                    .or_else(|| SegmentType::parse(&name))?;
+
+                Some(RouteSegment {
+                    segment_type,
+                    name,
+                    start_km: cell(start_col).and_then(parse_distance_km).unwrap_or(0.0),
+                    length_km: cell(Some(length_col)).and_then(parse_distance_km)?,
+                    avg_gradient: cell(Some(gradient_col)).and_then(parse_gradient),
+                })
+            })
+            .collect();
+
+        if !segments.is_empty() {
+            return segments;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Parse "12.3 km", "400 m" or a bare number (taken as km) into kilometers
+fn parse_distance_km(text: &str) -> Option<f64> {
+    let regex = Regex::new(r"(?i)(-?[0-9][0-9,]*(?:\.[0-9]+)?)\s*(km|m)?\b").ok()?;
+    let captures = regex.captures(text)?;
+    let value = captures[1].replace(',', "").parse::<f64>().ok()?;
+    match captures.get(2).map(|m| m.as_str().to_lowercase()) {
+        Some(unit) if unit == "m" => Some(value / 1000.0),
+        _ => Some(value),
+    }
+}
+
+/// Parse a gradient such as "4.5%" or "-1.2 %"
+fn parse_gradient(text: &str) -> Option<f64> {
+    let regex = Regex::new(r"(-?[0-9]+(?:\.[0-9]+)?)\s*%").ok()?;
+    regex.captures(text)?[1].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHATSONZWIFT_ROUTE: &str =
+        include_str!("../tests/fixtures/route_discovery/whatsonzwift_route.html");
+    const ZWIFTINSIDER_ROUTE: &str =
+        include_str!("../tests/fixtures/route_discovery/zwiftinsider_route.html");
+    const ZWIFTINSIDER_ROUTES: &str =
+        include_str!("../tests/fixtures/route_discovery/zwiftinsider_routes.html");
+
+    fn url(url: &str) -> PageLocation {
+        PageLocation::Url(url.to_string())
+    }
+
+    #[test]
+    fn test_whatsonzwift_fixture() {
+        let page = url("https://whatsonzwift.com/world/makuri-islands/route/three-village-loop");
+        let route = WhatsOnZwift
+            .parse_route(&page, WHATSONZWIFT_ROUTE, "Stage 2: Three Village Loop")
+            .unwrap();
+        assert_eq!(route.route_id, 1_777_771_771);
+        assert_eq!(route.name, "Three Village Loop");
+        assert!((route.distance_km - 10.6).abs() < 1e-9);
+        assert_eq!(route.elevation_m, 93);
+        assert_eq!(route.world, "Makuri Islands");
+        assert_eq!(route.surface, "road");
+        assert_eq!(route.source, RouteSource::WhatsOnZwift);
+        assert_eq!(route.segments.len(), 2);
+
+        // A search page for an unknown slug isn't a route
+        assert!(WhatsOnZwift
+            .parse_route(&page, ZWIFTINSIDER_ROUTES, "Three Village Loop")
+            .is_err());
+    }
+
+    #[test]
+    fn test_whatsonzwift_candidates_start_with_detected_world() {
+        let pages = WhatsOnZwift.candidate_pages("Stage 4: Neokyo All-Nighter | Advanced");
+        assert_eq!(pages.len(), WHATSONZWIFT_WORLDS.len());
+        assert_eq!(
+            pages[0],
+            url("https://whatsonzwift.com/world/makuri-islands/route/neokyo-all-nighter")
+        );
+    }
+
+    #[test]
+    fn test_zwiftinsider_fixture() {
+        let page = url("https://zwiftinsider.com/route/volcano-circuit/");
+        let route = ZwiftInsider
+            .parse_route(&page, ZWIFTINSIDER_ROUTE, "Volcano Circuit")
+            .unwrap();
+        assert_eq!(route.route_id, 9999);
+        assert_eq!(route.name, "Volcano Circuit");
+        assert!((route.distance_km - 4.1).abs() < 1e-9);
+        assert_eq!(route.elevation_m, 25);
+        assert_eq!(route.world, "Watopia");
+        assert_eq!(route.source, RouteSource::ZwiftInsider);
+    }
+
+    #[test]
+    fn test_zwiftinsider_listing_links_matching_routes() {
+        let listing = url(ZWIFTINSIDER_ROUTES_URL);
+        assert!(ZwiftInsider
+            .parse_route(&listing, ZWIFTINSIDER_ROUTES, "Volcano Circuit")
+            .is_err());
+        assert_eq!(
+            ZwiftInsider.linked_pages(ZWIFTINSIDER_ROUTES, "Race: Volcano Circuit CCW"),
+            vec![
+                url("https://zwiftinsider.com/route/volcano-circuit/"),
+                url("https://zwiftinsider.com/route/volcano-circuit-ccw/"),
+                url("https://zwiftinsider.com/route/volcano-flat/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_pages_use_the_saved_sites_parser() {
+        let local = LocalPages::new("/saved");
+        assert_eq!(
+            local.candidate_pages("Stage 2: Three Village Loop || Advanced"),
+            vec![PageLocation::File(PathBuf::from(
+                "/saved/three-village-loop.html"
+            ))]
+        );
+
+        let page = PageLocation::File(PathBuf::from("/saved/three-village-loop.html"));
+        let route = local
+            .parse_route(&page, WHATSONZWIFT_ROUTE, "Three Village Loop")
+            .unwrap();
+        assert_eq!(route.source, RouteSource::WhatsOnZwift);
+        // Without a URL the page's own world is kept
+        assert_eq!(route.world, "Makuri Islands");
+
+        let route = local
+            .parse_route(&page, ZWIFTINSIDER_ROUTE, "Volcano Circuit")
+            .unwrap();
+        assert_eq!(route.source, RouteSource::ZwiftInsider);
+    }
+
+    #[test]
+    fn test_format_world_name() {
+        assert_eq!(format_world_name("watopia"), "Watopia");
+        assert_eq!(format_world_name("london"), "London");
+        assert_eq!(format_world_name("new-york"), "New York");
+        assert_eq!(format_world_name("innsbruck"), "Innsbruck");
+        assert_eq!(format_world_name("yorkshire"), "Yorkshire");
+        assert_eq!(format_world_name("france"), "France");
+        assert_eq!(format_world_name("paris"), "Paris");
+        assert_eq!(format_world_name("makuri-islands"), "Makuri Islands");
+        assert_eq!(format_world_name("richmond"), "Richmond");
+        assert_eq!(format_world_name("scotland"), "Scotland");
+        assert_eq!(format_world_name("unknown"), "Unknown");
+    }
+
+    #[test]
+    fn test_parse_whatsonzwift_segments() {
+        let html = r#"
+            <p>Distance: 29.6 km</p>
+            <table class="stats"><tr><th>Stat</th><th>Value</th></tr></table>
+            <h2>Segments</h2>
+            <table class="segments">
+                <tr><th>Type</th><th>Segment</th><th>Starts at</th><th>Length</th><th>Avg. grade</th></tr>
+                <tr><td><span class="badge">Sprint</span></td><td><a href="/segment/watopia-sprint">Watopia Sprint</a></td><td>3.2 km</td><td>400 m</td><td>0.3%</td></tr>
+                <tr><td>KOM</td><td>Volcano KOM</td><td>12.8km</td><td>3.7 km</td><td>3.4 %</td></tr>
+                <tr><td></td><td>Hilly KOM</td><td>20.1 km</td><td>0.9 km</td><td>-</td></tr>
+                <tr><td>Info</td><td>Lap banner</td><td>0 km</td><td>0 km</td><td></td></tr>
+            </table>
+        "#;
+
+        let segments = parse_whatsonzwift_segments(html);
+        assert_eq!(segments.len(), 3);
+
+        assert_eq!(segments[0].segment_type, SegmentType::Sprint);
+        assert_eq!(segments[0].name, "Watopia Sprint");
+        assert!((segments[0].start_km - 3.2).abs() < 1e-9);
+        assert!((segments[0].length_km - 0.4).abs() < 1e-9);
+        assert_eq!(segments[0].avg_gradient, Some(0.3));
+
+        assert_eq!(segments[1].segment_type, SegmentType::Kom);
+        assert_eq!(segments[1].avg_gradient, Some(3.4));
+
+        // Type inferred from the name when the type cell is empty
+        assert_eq!(segments[2].segment_type, SegmentType::Kom);
+        assert_eq!(segments[2].avg_gradient, None);
+
+        assert!(parse_whatsonzwift_segments("<p>No segments here</p>").is_empty());
+    }
+
+    #[test]
+    fn test_parse_whatsonzwift_route_edge_cases() {
+        // Test with missing distance
+        let html = r#"
+            <div>
+                <p>Elevation: 500m</p>
+            </div>
+        "#;
+        let result = parse_whatsonzwift_route(html, "Test Route");
+        assert!(result.is_err());
+
+        // Test with missing elevation
+        let html = r#"
+            <div>
+                <p>Distance: 25.5km</p>
+            </div>
+        "#;
+        let result = parse_whatsonzwift_route(html, "Test Route");
+        assert!(result.is_err());
+
+        // Test with valid data but different formats
+        let html = r#"
+            <div>
+                <p>Distance: 25.5 km</p>
+                <p>Elevation: 500 m</p>
+            </div>
+        "#;
+        let result = parse_whatsonzwift_route(html, "Test Route");
+        assert!(result.is_ok());
+        let route = result.unwrap();
+        assert_eq!(route.distance_km, 25.5);
+        assert_eq!(route.elevation_m, 500);
+    }
+}
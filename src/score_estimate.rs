@@ -0,0 +1,276 @@
+//! Racing score estimated from recorded race results
+//!
+//! Without ZwiftPower or a configured score, the finder would fall back to a
+//! fixed default. Instead, each recorded result on a known route is turned
+//! into an average speed and compared with the speed the duration model
+//! predicts for every category band on that route's terrain. The band whose
+//! predictions best explain the results is offered as the starting score.
+
+use crate::category::get_category_speed;
+use crate::database::{Database, RaceResult, RouteData};
+use crate::duration_estimation::{
+    get_route_difficulty_multiplier, get_route_difficulty_multiplier_from_elevation_and_category,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Category bands as (category, lowest score, highest score, score offered)
+///
+/// The bands follow `get_category_from_score`; the top one ends at 1000, the
+/// highest racing score Zwift gives.
+const SCORE_BANDS: &[(&str, u32, u32, u32)] = &[
+    ("E", 0, 99, 50),
+    ("D", 100, 199, 150),
+    ("C", 200, 299, 250),
+    ("B", 300, 399, 350),
+    ("A", 400, 599, 500),
+    ("A++", 600, 1000, 650),
+];
+
+/// Average speeds outside this range (km/h) point at a wrong route or distance
+const PLAUSIBLE_SPEED_KMH: (f64, f64) = (10.0, 60.0);
+
+/// How far the results can be trusted to pin down the band
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EstimateConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for EstimateConfidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EstimateConfidence::Low => "low",
+            EstimateConfidence::Medium => "medium",
+            EstimateConfidence::High => "high",
+        })
+    }
+}
+
+/// The score band that best explains a rider's race results
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreEstimate {
+    /// Score offered as the default (the middle of the band)
+    pub zwift_score: u32,
+    /// Category of the band
+    pub category: &'static str,
+    /// Lowest and highest score in the band
+    pub band: (u32, u32),
+    pub confidence: EstimateConfidence,
+    /// Number of results the estimate is based on
+    pub results_used: usize,
+}
+
+/// Estimate the active rider's score from their results in the user's database
+///
+/// Returns `None` when the database can't be opened or no result is usable.
+#[must_use]
+pub fn estimate_from_history() -> Option<ScoreEstimate> {
+    estimate_from_db(&Database::new().ok()?)
+}
+
+/// Estimate the rider's score from their results in `db`
+#[must_use]
+pub fn estimate_from_db(db: &Database) -> Option<ScoreEstimate> {
+    let results: Vec<RaceResult> = db
+        .get_rider_race_results()
+        .ok()?
+        .into_iter()
+        .map(|result| {
+            // Results recorded before laps were stored fall back to the event's lap count
+            let laps = result
+                .laps
+                .or_else(|| db.get_multi_lap_info(&result.event_name).ok().flatten());
+            RaceResult { laps, ..result }
+        })
+        .collect();
+    let routes = db.get_all_routes().ok()?;
+    estimate_from_results(&results, &routes)
+}
+
+/// Find the score band whose predicted speeds best fit the results
+///
+/// The distance ridden is the route times the result's laps (one when not
+/// recorded) plus the lead-in. Results are skipped when they are DNFs, on a
+/// route not in `routes`, or imply an implausible speed (usually a multi-lap
+/// race whose laps weren't recorded). Each band is scored by the mean squared log ratio of actual to
+/// predicted speed, so being 10% fast or 10% slow counts the same on any
+/// terrain.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn estimate_from_results(
+    results: &[RaceResult],
+    routes: &[RouteData],
+) -> Option<ScoreEstimate> {
+    let routes: HashMap<u32, &RouteData> = routes.iter().map(|r| (r.route_id, r)).collect();
+
+    // Log speed ratio against every band, one row per usable result
+    let ratios: Vec<Vec<f64>> = results
+        .iter()
+        .filter(|result| !result.dnf && result.actual_minutes > 0)
+        .filter_map(|result| {
+            let route = routes.get(&result.route_id)?;
+            let laps = f64::from(result.laps.unwrap_or(1).max(1));
+            let distance_km = route.distance_km * laps + route.lead_in_distance_km;
+            let speed = distance_km / (f64::from(result.actual_minutes) / 60.0);
+            if !(PLAUSIBLE_SPEED_KMH.0..=PLAUSIBLE_SPEED_KMH.1).contains(&speed) {
+                return None;
+            }
+            Some(
+                SCORE_BANDS
+                    .iter()
+                    .map(|(category, ..)| (speed / predicted_speed(route, category)).ln())
+                    .collect(),
+            )
+        })
+        .collect();
+    if ratios.is_empty() {
+        return None;
+    }
+
+    let band =
+        closest_band((0..SCORE_BANDS.len()).map(|band| {
+            ratios.iter().map(|row| row[band].powi(2)).sum::<f64>() / ratios.len() as f64
+        }));
+    // Share of results that on their own would pick the same band
+    let agreeing = ratios
+        .iter()
+        .filter(|row| closest_band(row.iter().map(|r| r.abs())) == band)
+        .count();
+    let agreement = agreeing as f64 / ratios.len() as f64;
+
+    let confidence = match (ratios.len(), agreement) {
+        (10.., a) if a >= 0.7 => EstimateConfidence::High,
+        (3.., a) if a >= 0.5 => EstimateConfidence::Medium,
+        _ => EstimateConfidence::Low,
+    };
+    let (category, low, high, zwift_score) = SCORE_BANDS[band];
+    Some(ScoreEstimate {
+        zwift_score,
+        category,
+        band: (low, high),
+        confidence,
+        results_used: ratios.len(),
+    })
+}
+
+/// Index of the band with the smallest error
+fn closest_band(errors: impl Iterator<Item = f64>) -> usize {
+    errors
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(band, _)| band)
+}
+
+/// Race speed the duration model expects from `category` on this route
+fn predicted_speed(route: &RouteData, category: &str) -> f64 {
+    let multiplier = if route.elevation_m > 0 {
+        get_route_difficulty_multiplier_from_elevation_and_category(
+            route.distance_km,
+            route.elevation_m,
+            category,
+        )
+    } else {
+        get_route_difficulty_multiplier(&route.name)
+    };
+    get_category_speed(category) * multiplier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(route_id: u32, distance_km: f64, elevation_m: u32) -> RouteData {
+        RouteData {
+            route_id,
+            distance_km,
+            elevation_m,
+            name: format!("Route {route_id}"),
+            world: "Watopia".to_string(),
+            surface: "road".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn result(route_id: u32, actual_minutes: u32) -> RaceResult {
+        RaceResult {
+            route_id,
+            event_name: "Race".to_string(),
+            actual_minutes,
+            zwift_score: 195,
+            race_date: "2025-06-01".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Minutes a `category` rider is predicted to take on `route`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn minutes_for(route: &RouteData, category: &str) -> u32 {
+        (route.distance_km / predicted_speed(route, category) * 60.0).round() as u32
+    }
+
+    #[test]
+    fn test_consistent_results_give_high_confidence() {
+        let flat = route(1, 30.0, 60);
+        let hilly = route(2, 25.0, 550);
+        let routes = vec![flat.clone(), hilly.clone()];
+        let results: Vec<_> = (0..6)
+            .flat_map(|_| {
+                [
+                    result(1, minutes_for(&flat, "C")),
+                    result(2, minutes_for(&hilly, "C")),
+                ]
+            })
+            .collect();
+
+        let estimate = estimate_from_results(&results, &routes).unwrap();
+        assert_eq!(estimate.category, "C");
+        assert_eq!(estimate.zwift_score, 250);
+        assert_eq!(estimate.band, (200, 299));
+        assert_eq!(estimate.confidence, EstimateConfidence::High);
+        assert_eq!(estimate.results_used, 12);
+    }
+
+    #[test]
+    fn test_terrain_is_taken_into_account() {
+        // Slow on a mountain route is still a B rider's time
+        let mountain = route(3, 20.0, 900);
+        let estimate =
+            estimate_from_results(&[result(3, minutes_for(&mountain, "B"))], &[mountain]).unwrap();
+        assert_eq!(estimate.category, "B");
+        assert_eq!(estimate.confidence, EstimateConfidence::Low);
+    }
+
+    #[test]
+    fn test_unusable_results_are_skipped() {
+        let flat = route(1, 30.0, 60);
+        let dnf = RaceResult {
+            dnf: true,
+            ..result(1, 20)
+        };
+        // Unknown route, and a multi-lap time against a single lap (under 10 km/h)
+        let results = [dnf, result(99, 50), result(1, 200)];
+        assert!(estimate_from_results(&results, &[flat]).is_none());
+    }
+
+    #[test]
+    fn test_recorded_laps_are_ridden_distance() {
+        let flat = route(1, 10.0, 20);
+        let three_laps = RaceResult {
+            laps: Some(3),
+            ..result(1, 3 * minutes_for(&flat, "B"))
+        };
+        let estimate = estimate_from_results(&[three_laps], &[flat]).unwrap();
+        assert_eq!(estimate.category, "B");
+    }
+
+    #[test]
+    fn test_fastest_riders_get_top_band() {
+        let flat = route(1, 40.0, 80);
+        let estimate =
+            estimate_from_results(&[result(1, minutes_for(&flat, "A++"))], &[flat]).unwrap();
+        assert_eq!(estimate.category, "A++");
+        assert_eq!(estimate.band, (600, 1000));
+    }
+}
@@ -0,0 +1,265 @@
+//! Import of Strava activity exports as race results
+//!
+//! Reads the activity JSON written by `tools/import/strava/strava_fetch_activities.sh`
+//! (or any list of Strava API activity summaries). Zwift uploads name activities
+//! `Zwift - Race: <event> (<cat>) on <route> in <world>`, which is parsed for the
+//! event name and category; the route itself is found by [`crate::route_matcher`].
+
+use crate::database::RaceResult;
+use crate::route_matcher::ActivityProfile;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// One activity summary from the Strava API
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StravaActivity {
+    pub id: u64,
+    pub name: String,
+    /// UTC start time, e.g. `2025-06-02T09:30:14Z`
+    pub start_date: String,
+    #[serde(default)]
+    pub start_date_local: Option<String>,
+    /// Distance in meters
+    #[serde(default)]
+    pub distance: f64,
+    /// Moving time in seconds
+    #[serde(default)]
+    pub moving_time: u64,
+    /// Elapsed time in seconds
+    #[serde(default)]
+    pub elapsed_time: u64,
+    #[serde(default)]
+    pub total_elevation_gain: Option<f64>,
+    #[serde(default)]
+    pub average_watts: Option<f64>,
+    /// Strava's weighted average power, its equivalent of normalized power
+    #[serde(default)]
+    pub weighted_average_watts: Option<f64>,
+    /// Legacy activity type (`VirtualRide`, `Ride`, ...)
+    #[serde(default, rename = "type")]
+    pub activity_type: Option<String>,
+    #[serde(default)]
+    pub sport_type: Option<String>,
+}
+
+/// Parts of a Zwift-generated activity name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZwiftActivityName {
+    pub event_name: String,
+    pub category: Option<String>,
+    pub route_name: Option<String>,
+    pub world: Option<String>,
+}
+
+impl StravaActivity {
+    /// Whether this is a virtual ride
+    ///
+    /// The fetch script already keeps only `VirtualRide` activities and drops
+    /// the type field, so activities without a type are accepted.
+    #[must_use]
+    pub fn is_virtual_ride(&self) -> bool {
+        let types = [&self.activity_type, &self.sport_type];
+        types.iter().all(|t| t.is_none())
+            || types
+                .into_iter()
+                .flatten()
+                .any(|t| t.eq_ignore_ascii_case("VirtualRide"))
+    }
+
+    /// Whether the activity name says it was a race or time trial
+    #[must_use]
+    pub fn is_race(&self) -> bool {
+        let name = self.name.to_lowercase();
+        name.contains("race") || name.starts_with("zwift - tt") || name.contains("time trial")
+    }
+
+    #[must_use]
+    pub fn distance_km(&self) -> f64 {
+        self.distance / 1000.0
+    }
+
+    #[must_use]
+    pub fn parsed_name(&self) -> ZwiftActivityName {
+        parse_zwift_activity_name(&self.name)
+    }
+
+    /// Input for the route matcher; `parsed` supplies the route named in the title
+    #[must_use]
+    pub fn profile<'a>(&'a self, parsed: &'a ZwiftActivityName) -> ActivityProfile<'a> {
+        ActivityProfile {
+            distance_km: self.distance_km(),
+            elevation_m: self.total_elevation_gain,
+            name: &self.name,
+            stated_route: parsed.route_name.as_deref(),
+        }
+    }
+
+    /// Build a race result for this activity (the route is set by the caller)
+    #[must_use]
+    pub fn to_race_result(&self, zwift_score: u32) -> RaceResult {
+        let parsed = self.parsed_name();
+        let seconds = if self.moving_time > 0 {
+            self.moving_time
+        } else {
+            self.elapsed_time
+        };
+        // Dates are stored in the rider's local time, like manually recorded results
+        let race_date = self
+            .start_date_local
+            .as_deref()
+            .unwrap_or(&self.start_date)
+            .chars()
+            .take(10)
+            .collect();
+
+        RaceResult {
+            event_name: parsed.event_name,
+            actual_minutes: u32::try_from((seconds + 30) / 60).unwrap_or(u32::MAX),
+            zwift_score,
+            race_date,
+            category: parsed.category,
+            avg_power_w: self.average_watts.map(|w| w.round() as u32),
+            np_power_w: self.weighted_average_watts.map(|w| w.round() as u32),
+            source: Some("strava".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Split a Zwift activity name into event, category, route and world
+///
+/// `Zwift - Race: EVR Winter Series (D) on Coast Crusher in Watopia` gives the
+/// event `EVR Winter Series`, category `D`, route `Coast Crusher` and world
+/// `Watopia`. Names that do not follow the pattern become the event name as-is.
+#[must_use]
+pub fn parse_zwift_activity_name(name: &str) -> ZwiftActivityName {
+    let mut rest = name.trim();
+    if let Some(stripped) = rest.strip_prefix("Zwift - ") {
+        rest = stripped;
+        for kind in ["Race:", "TT:", "Group Ride:", "Workout:"] {
+            if let Some(stripped) = rest.strip_prefix(kind) {
+                rest = stripped.trim_start();
+                break;
+            }
+        }
+    }
+
+    let mut parsed = ZwiftActivityName::default();
+    if let Some(on) = rest.rfind(" on ") {
+        let (event, location) = (&rest[..on], &rest[on + 4..]);
+        if let Some(in_pos) = location.rfind(" in ") {
+            parsed.route_name = Some(location[..in_pos].trim().to_string());
+            parsed.world = Some(location[in_pos + 4..].trim().to_string());
+            rest = event;
+        }
+    }
+
+    let event = rest.trim();
+    let category = event.rfind('(').and_then(|open| {
+        let inner = event[open + 1..].strip_suffix(')')?;
+        let cat = inner.trim().trim_start_matches("Cat ").trim();
+        let valid = matches!(cat.trim_end_matches('+'), "A" | "B" | "C" | "D" | "E");
+        valid.then(|| (open, cat.to_string()))
+    });
+    match category {
+        Some((open, cat)) => {
+            parsed.event_name = event[..open].trim().to_string();
+            parsed.category = Some(cat);
+        }
+        None => parsed.event_name = event.to_string(),
+    }
+    parsed
+}
+
+/// Load a JSON array of Strava activities
+pub fn load_activities(path: &Path) -> Result<Vec<StravaActivity>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("{} is not a Strava activity list", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zwift_activity_name() {
+        let parsed = parse_zwift_activity_name(
+            "Zwift - Race: Zwift Epic Race - Eastern Eight (D) on Eastern Eight in Watopia",
+        );
+        assert_eq!(parsed.event_name, "Zwift Epic Race - Eastern Eight");
+        assert_eq!(parsed.category.as_deref(), Some("D"));
+        assert_eq!(parsed.route_name.as_deref(), Some("Eastern Eight"));
+        assert_eq!(parsed.world.as_deref(), Some("Watopia"));
+
+        let parsed = parse_zwift_activity_name(
+            "Zwift - Race: Stage 2: Makuri May: Turf N Surf (E) on Turf N Surf in Makuri Islands",
+        );
+        assert_eq!(parsed.event_name, "Stage 2: Makuri May: Turf N Surf");
+        assert_eq!(parsed.world.as_deref(), Some("Makuri Islands"));
+
+        // Manually renamed activities keep their name and category
+        let parsed =
+            parse_zwift_activity_name("3R Volcano Flat Race - 3 Laps (36.6km/22.7mi 138m) (D)");
+        assert_eq!(
+            parsed.event_name,
+            "3R Volcano Flat Race - 3 Laps (36.6km/22.7mi 138m)"
+        );
+        assert_eq!(parsed.category.as_deref(), Some("D"));
+        assert_eq!(parsed.route_name, None);
+
+        let parsed = parse_zwift_activity_name(
+            "Zwift - Race: Herd Summer Racing League on Coast Crusher in Watopia",
+        );
+        assert_eq!(parsed.event_name, "Herd Summer Racing League");
+        assert_eq!(parsed.category, None);
+    }
+
+    #[test]
+    fn test_activity_to_race_result() {
+        let json = r#"[{
+            "id": 14671146801,
+            "name": "Zwift - Race: Zwift Epic Race - Eastern Eight (D) on Eastern Eight in Watopia",
+            "start_date": "2025-06-02T23:30:14Z",
+            "start_date_local": "2025-06-03T00:30:14Z",
+            "distance": 54870,
+            "moving_time": 6055,
+            "elapsed_time": 6055,
+            "total_elevation_gain": 412,
+            "average_watts": 187.1,
+            "weighted_average_watts": 196,
+            "suffer_score": null
+        }]"#;
+        let activities: Vec<StravaActivity> = serde_json::from_str(json).unwrap();
+        let activity = &activities[0];
+        assert!(activity.is_virtual_ride());
+        assert!(activity.is_race());
+
+        let result = activity.to_race_result(210);
+        assert_eq!(result.event_name, "Zwift Epic Race - Eastern Eight");
+        assert_eq!(result.actual_minutes, 101);
+        assert_eq!(result.race_date, "2025-06-03");
+        assert_eq!(result.category.as_deref(), Some("D"));
+        assert_eq!(result.avg_power_w, Some(187));
+        assert_eq!(result.np_power_w, Some(196));
+        assert_eq!(result.source.as_deref(), Some("strava"));
+    }
+
+    #[test]
+    fn test_virtual_ride_types() {
+        let outdoor = StravaActivity {
+            activity_type: Some("Ride".to_string()),
+            sport_type: Some("Ride".to_string()),
+            ..Default::default()
+        };
+        assert!(!outdoor.is_virtual_ride());
+
+        let virtual_ride = StravaActivity {
+            sport_type: Some("VirtualRide".to_string()),
+            ..Default::default()
+        };
+        assert!(virtual_ride.is_virtual_ride());
+    }
+}
@@ -0,0 +1,259 @@
+//! Team event finder: races a whole group of riders can enter together
+//!
+//! Each rider's pen is the subgroup [`find_user_subgroup`] picks for their
+//! racing score. A team fits an event when everyone lands in the same pen or,
+//! when neighbouring pens are allowed, in pens at most one category apart.
+
+use crate::category::get_category_from_score;
+use crate::event_analysis::find_user_subgroup;
+use crate::event_filtering::estimate_event_duration;
+use crate::models::{EventSubGroup, ZwiftEvent};
+use crate::profile;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::str::FromStr;
+
+/// How a rider is named on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberSpec {
+    /// A rider profile whose racing score is looked up
+    Profile(String),
+    /// An explicit score, optionally named (`bob=245` or `245`)
+    Score { name: String, zwift_score: u32 },
+}
+
+impl FromStr for MemberSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some((name, score)) = s.split_once('=') {
+            let zwift_score = score
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid racing score in '{s}'"))?;
+            return Ok(MemberSpec::Score {
+                name: name.trim().to_string(),
+                zwift_score,
+            });
+        }
+        if let Ok(zwift_score) = s.parse() {
+            return Ok(MemberSpec::Score {
+                name: s.to_string(),
+                zwift_score,
+            });
+        }
+        profile::validate_name(s)?;
+        Ok(MemberSpec::Profile(s.to_string()))
+    }
+}
+
+/// A rider with a known racing score
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamMember {
+    pub name: String,
+    pub zwift_score: u32,
+}
+
+/// Where one rider starts in an event and when they should finish
+#[derive(Debug, Clone)]
+pub struct MemberPlacement<'a> {
+    pub member: &'a TeamMember,
+    pub subgroup: &'a EventSubGroup,
+    pub start: DateTime<Utc>,
+    pub estimated_minutes: Option<u32>,
+}
+
+impl MemberPlacement<'_> {
+    /// Estimated finish time, if the event's duration could be estimated
+    #[must_use]
+    pub fn finish(&self) -> Option<DateTime<Utc>> {
+        self.estimated_minutes
+            .map(|minutes| self.start + Duration::minutes(i64::from(minutes)))
+    }
+}
+
+/// An event every team member can enter
+#[derive(Debug, Clone)]
+pub struct TeamFit<'a> {
+    pub event: &'a ZwiftEvent,
+    pub placements: Vec<MemberPlacement<'a>>,
+}
+
+impl TeamFit<'_> {
+    /// Whether everyone starts in the same pen
+    #[must_use]
+    pub fn same_pen(&self) -> bool {
+        self.placements
+            .windows(2)
+            .all(|pair| pair[0].subgroup.id == pair[1].subgroup.id)
+    }
+
+    /// Time between the first and last pen start
+    #[must_use]
+    pub fn start_spread(&self) -> Duration {
+        spread(self.placements.iter().map(|p| p.start)).unwrap_or_else(Duration::zero)
+    }
+
+    /// Time between the first and last estimated finish; `None` if any is unknown
+    #[must_use]
+    pub fn finish_spread(&self) -> Option<Duration> {
+        let finishes: Option<Vec<_>> = self
+            .placements
+            .iter()
+            .map(MemberPlacement::finish)
+            .collect();
+        spread(finishes?.into_iter())
+    }
+}
+
+fn spread(times: impl Iterator<Item = DateTime<Utc>>) -> Option<Duration> {
+    let (min, max) = times.fold(None, |range: Option<(DateTime<Utc>, DateTime<Utc>)>, t| {
+        Some(range.map_or((t, t), |(min, max)| (min.min(t), max.max(t))))
+    })?;
+    Some(max - min)
+}
+
+/// Category order used to decide whether two pens are neighbours
+fn category_rank(zwift_score: u32) -> u8 {
+    match get_category_from_score(zwift_score) {
+        "E" => 0,
+        "D" => 1,
+        "C" => 2,
+        "B" => 3,
+        _ => 4,
+    }
+}
+
+/// Place every member in `event`, or `None` if the team can't ride it together
+///
+/// Without `allow_adjacent` everyone must share a pen; with it, pens may differ
+/// by one category (a C and a D rider, but not a B and a D rider).
+#[must_use]
+pub fn fit_team<'a>(
+    event: &'a ZwiftEvent,
+    members: &'a [TeamMember],
+    allow_adjacent: bool,
+) -> Option<TeamFit<'a>> {
+    if members.is_empty() {
+        return None;
+    }
+    let placements = members
+        .iter()
+        .map(|member| {
+            let subgroup = find_user_subgroup(event, member.zwift_score)?;
+            Some(MemberPlacement {
+                member,
+                subgroup,
+                start: subgroup.event_subgroup_start.unwrap_or(event.event_start),
+                estimated_minutes: estimate_event_duration(event, member.zwift_score),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let fit = TeamFit { event, placements };
+    if fit.same_pen() {
+        return Some(fit);
+    }
+    if !allow_adjacent {
+        return None;
+    }
+    let ranks = members.iter().map(|m| category_rank(m.zwift_score));
+    let (low, high) = (ranks.clone().min()?, ranks.max()?);
+    (high - low <= 1).then_some(fit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn subgroup(id: u32, name: &str, start_offset_min: i64) -> EventSubGroup {
+        EventSubGroup {
+            id,
+            name: name.to_string(),
+            route_id: None,
+            distance_in_meters: Some(20_000.0),
+            duration_in_minutes: None,
+            category_enforcement: None,
+            range_access_label: None,
+            laps: None,
+            start_location: None,
+            event_subgroup_start: Some(
+                Utc.with_ymd_and_hms(2025, 6, 1, 18, 0, 0).unwrap()
+                    + Duration::minutes(start_offset_min),
+            ),
+        }
+    }
+
+    fn event() -> ZwiftEvent {
+        ZwiftEvent {
+            id: 1,
+            name: "Team Training Race".to_string(),
+            event_start: Utc.with_ymd_and_hms(2025, 6, 1, 18, 0, 0).unwrap(),
+            event_type: "RACE".to_string(),
+            distance_in_meters: None,
+            duration_in_minutes: None,
+            duration_in_seconds: None,
+            route_id: None,
+            route: None,
+            description: None,
+            category_enforcement: true,
+            event_sub_groups: vec![
+                subgroup(10, "B", 0),
+                subgroup(11, "C", 1),
+                subgroup(12, "D", 2),
+            ],
+            sport: "CYCLING".to_string(),
+            tags: vec![],
+        }
+    }
+
+    fn member(name: &str, zwift_score: u32) -> TeamMember {
+        TeamMember {
+            name: name.to_string(),
+            zwift_score,
+        }
+    }
+
+    #[test]
+    fn test_member_spec_parsing() {
+        assert_eq!(
+            "alice".parse::<MemberSpec>().unwrap(),
+            MemberSpec::Profile("alice".to_string())
+        );
+        assert_eq!(
+            "bob=245".parse::<MemberSpec>().unwrap(),
+            MemberSpec::Score {
+                name: "bob".to_string(),
+                zwift_score: 245
+            }
+        );
+        assert!(matches!(
+            "180".parse::<MemberSpec>().unwrap(),
+            MemberSpec::Score {
+                zwift_score: 180,
+                ..
+            }
+        ));
+        assert!("bob=fast".parse::<MemberSpec>().is_err());
+    }
+
+    #[test]
+    fn test_same_pen_required_unless_adjacent_allowed() {
+        let event = event();
+        let same = [member("a", 210), member("b", 280)];
+        let fit = fit_team(&event, &same, false).unwrap();
+        assert!(fit.same_pen());
+        assert_eq!(fit.start_spread(), Duration::zero());
+
+        let neighbours = [member("a", 210), member("b", 180)];
+        assert!(fit_team(&event, &neighbours, false).is_none());
+        let fit = fit_team(&event, &neighbours, true).unwrap();
+        assert_eq!(fit.start_spread(), Duration::minutes(1));
+        assert!(fit.finish_spread().is_some());
+
+        let too_far = [member("a", 350), member("b", 180)];
+        assert!(fit_team(&event, &too_far, true).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+//! Authenticated Zwift API client for the rider's own profile and signups
+//!
+//! The public events feed needs no account, but the rider's profile, the
+//! events they have signed up for and past events' results do. Requests carry a bearer token taken from
+//! the `zwift-api-token` credential (see [`crate::credentials`]). The base URL
+//! is configurable so a local stand-in server can take Zwift's place.
+
+use crate::event_analysis::apply_description_courses;
+use crate::event_results::{ResultEntry, ResultsPayload};
+use crate::models::ZwiftEvent;
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Zwift's API host, used when no base URL is configured
+pub const DEFAULT_BASE_URL: &str = "https://us-or-rly101.zwift.com";
+
+/// Finishers requested per page of subgroup results
+pub const RESULTS_PAGE_SIZE: usize = 50;
+
+/// Most result pages read for one subgroup, in case paging is ignored
+const MAX_RESULT_PAGES: usize = 100;
+
+/// The rider's profile as returned by `/api/profiles/me`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiderProfile {
+    pub id: u64,
+    #[serde(default)]
+    pub first_name: String,
+    #[serde(default)]
+    pub last_name: String,
+    /// Weight in grams, as the API reports it
+    pub weight: Option<u32>,
+    /// Functional threshold power in watts
+    pub ftp: Option<u32>,
+    pub competition_metrics: Option<CompetitionMetrics>,
+}
+
+/// Racing score and category from the rider's profile
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompetitionMetrics {
+    pub racing_score: Option<f64>,
+    pub category: Option<String>,
+}
+
+impl RiderProfile {
+    /// Full name, for messages
+    #[must_use]
+    pub fn name(&self) -> String {
+        format!("{} {}", self.first_name, self.last_name)
+            .trim()
+            .to_string()
+    }
+
+    /// Racing score rounded to a whole number
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn racing_score(&self) -> Option<u32> {
+        self.competition_metrics
+            .as_ref()?
+            .racing_score
+            .filter(|score| *score >= 0.0)
+            .map(|score| score.round() as u32)
+    }
+
+    /// Racing category, if the profile has one
+    #[must_use]
+    pub fn category(&self) -> Option<&str> {
+        self.competition_metrics.as_ref()?.category.as_deref()
+    }
+
+    /// Weight in kilograms
+    #[must_use]
+    pub fn weight_kg(&self) -> Option<f64> {
+        self.weight
+            .filter(|grams| *grams > 0)
+            .map(|grams| f64::from(grams) / 1000.0)
+    }
+}
+
+/// Client for the rider's own data on the Zwift API
+pub struct ZwiftApiClient {
+    base_url: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl ZwiftApiClient {
+    pub fn new(base_url: &str, token: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent("Zwift Race Finder")
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            client,
+        })
+    }
+
+    /// Fetch the profile the token belongs to
+    pub async fn fetch_profile(&self) -> Result<RiderProfile> {
+        self.get("/api/profiles/me")
+            .await
+            .context("Failed to fetch Zwift profile")
+    }
+
+    /// Fetch the upcoming events the rider has signed up for
+    pub async fn fetch_signups(&self, profile_id: u64) -> Result<Vec<ZwiftEvent>> {
+        let mut events: Vec<ZwiftEvent> = self
+            .get(&format!("/api/profiles/{profile_id}/events/upcoming"))
+            .await
+            .context("Failed to fetch event signups")?;
+        events.iter_mut().for_each(apply_description_courses);
+        Ok(events)
+    }
+
+    /// Fetch an event by ID, including past events
+    pub async fn fetch_event(&self, event_id: u64) -> Result<ZwiftEvent> {
+        let mut event: ZwiftEvent = self
+            .get(&format!("/api/events/{event_id}"))
+            .await
+            .with_context(|| format!("Failed to fetch event {event_id}"))?;
+        apply_description_courses(&mut event);
+        Ok(event)
+    }
+
+    /// Fetch the finishers of one event subgroup
+    ///
+    /// Results are served [`RESULTS_PAGE_SIZE`] at a time; pages are read
+    /// until a short one comes back.
+    pub async fn fetch_subgroup_results(&self, subgroup_id: u32) -> Result<Vec<ResultEntry>> {
+        let mut entries = Vec::new();
+        for page in 0..MAX_RESULT_PAGES {
+            let payload: ResultsPayload = self
+                .get(&format!(
+                    "/api/race-results/entries?event_subgroup_id={subgroup_id}&start={}&limit={RESULTS_PAGE_SIZE}",
+                    page * RESULTS_PAGE_SIZE
+                ))
+                .await
+                .with_context(|| format!("Failed to fetch results for subgroup {subgroup_id}"))?;
+            let page_entries = payload.into_entries();
+            let last_page = page_entries.len() < RESULTS_PAGE_SIZE;
+            entries.extend(page_entries);
+            if last_page {
+                break;
+            }
+        }
+        // Entries don't always repeat the subgroup they were requested for
+        for entry in &mut entries {
+            entry.event_subgroup_id.get_or_insert(subgroup_id);
+        }
+        Ok(entries)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            bail!(
+                "The Zwift API rejected the access token (HTTP {})",
+                status.as_u16()
+            );
+        }
+        if !status.is_success() {
+            bail!("The Zwift API returned HTTP {}", status.as_u16());
+        }
+        Ok(response.json().await?)
+    }
+}
@@ -1,10 +1,19 @@
 // Client for importing route data from zwift-offline API
 // Maintains license boundary through HTTP API calls
 
+use crate::database::{RouteData, StartLine};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+/// Convert a signed game route ID (zwift-offline exports them as i32) to our u32 key
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn route_id_from_signed(route_id: i64) -> u32 {
+    // Wraps negative hashes the same way the game's unsigned IDs do
+    route_id as u32
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportedRoute {
     pub route_id: i64,
@@ -27,6 +36,39 @@ pub struct ExportedRoute {
     pub elevation_gain: f64,
 }
 
+impl ExportedRoute {
+    /// Route data for our database, or `None` when the export has no distance
+    ///
+    /// zwift-offline has no lead-in elevation, free-ride/meetup lead-ins or
+    /// slugs; those stay at their defaults.
+    #[must_use]
+    pub fn to_route_data(&self) -> Option<RouteData> {
+        if self.distance_km == 0.0 && self.distance_m == 0.0 {
+            return None;
+        }
+        Some(RouteData {
+            route_id: route_id_from_signed(self.route_id),
+            name: self.name.clone(),
+            distance_km: if self.distance_without_lead_in_km > 0.0 {
+                self.distance_without_lead_in_km
+            } else {
+                // For older data without separated lead-in
+                self.distance_km - self.lead_in_distance_km
+            },
+            elevation_m: self.elevation_gain.round() as u32,
+            world: self.world_name.clone(),
+            surface: self.surface.clone(),
+            lead_in_distance_km: self.lead_in_distance_km,
+            lead_in_elevation_m: 0,
+            lead_in_distance_free_ride_km: None,
+            lead_in_elevation_free_ride_m: None,
+            lead_in_distance_meetups_km: None,
+            lead_in_elevation_meetups_m: None,
+            slug: None,
+        })
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -41,6 +83,31 @@ pub struct ExportedStartLine {
     pub name: String,
     pub road: u32,
     pub time: u32,
+    /// Event start location (pen); absent for a route's default start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_location: Option<u32>,
+    /// Whether the route rides towards increasing road time; absent when
+    /// the export doesn't say
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward: Option<bool>,
+}
+
+impl ExportedStartLine {
+    /// Start line for our database; the hash is the signed route ID as text
+    #[must_use]
+    pub fn to_start_line(&self) -> Option<StartLine> {
+        let route_id = self.hash.trim().parse::<i64>().ok()?;
+        Some(StartLine {
+            route_id: route_id_from_signed(route_id),
+            start_location: self
+                .start_location
+                .unwrap_or(crate::lead_in::DEFAULT_START_LOCATION),
+            name: self.name.clone(),
+            road: self.road,
+            road_time: self.time,
+            forward: self.forward,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
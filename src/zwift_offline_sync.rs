@@ -0,0 +1,339 @@
+//! Live sync of routes, start lines and events from a zwift-offline server
+//!
+//! [`fetch_snapshot`] pulls everything over HTTP, [`diff_routes`] compares the
+//! routes with the local database and [`apply_snapshot`] writes them. The diff
+//! is computed before anything is written so callers can show it first.
+
+use crate::database::{Database, RouteData, RouteProvenance, RouteSource, StartLine};
+use crate::zwift_offline_client::{
+    route_id_from_signed, ExportedEvent, ExportedRoute, ExportedStartLine, ZwiftOfflineClient,
+};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// Distances closer than this (km) are treated as unchanged
+const DISTANCE_EPSILON_KM: f64 = 0.005;
+
+/// Everything fetched from one zwift-offline server
+#[derive(Debug, Default)]
+pub struct SyncSnapshot {
+    /// Routes with distance data
+    pub routes: Vec<RouteData>,
+    /// Routes the server listed without any distance data
+    pub incomplete_routes: usize,
+    pub start_lines: Vec<StartLine>,
+    pub events: Vec<ExportedEvent>,
+}
+
+impl SyncSnapshot {
+    /// Events that reference a route the server did not list
+    #[must_use]
+    pub fn events_with_unknown_routes(&self) -> Vec<&ExportedEvent> {
+        let route_ids: HashSet<u32> = self.routes.iter().map(|r| r.route_id).collect();
+        self.events
+            .iter()
+            .filter(|event| !route_ids.contains(&route_id_from_signed(event.route)))
+            .collect()
+    }
+}
+
+/// Fetch routes, start lines and events from a zwift-offline server
+pub async fn fetch_snapshot(client: &ZwiftOfflineClient) -> Result<SyncSnapshot> {
+    let exported = client.fetch_routes().await?;
+    let start_lines = client.fetch_start_lines().await?;
+    let events = client.fetch_events().await?;
+
+    let routes: Vec<RouteData> = exported
+        .iter()
+        .filter_map(ExportedRoute::to_route_data)
+        .collect();
+    Ok(SyncSnapshot {
+        incomplete_routes: exported.len() - routes.len(),
+        routes,
+        start_lines: start_lines
+            .iter()
+            .filter_map(ExportedStartLine::to_start_line)
+            .collect(),
+        events,
+    })
+}
+
+/// One value that differs between the local route and the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub local: String,
+    pub server: String,
+}
+
+/// A route whose server data differs from the local row
+#[derive(Debug, Clone)]
+pub struct RouteChange {
+    /// Route as it will be written
+    pub route: RouteData,
+    pub changes: Vec<FieldChange>,
+    /// The local row is more trusted than zwift-offline and will not be overwritten
+    pub kept: bool,
+}
+
+/// Differences between the server's routes and the local database
+#[derive(Debug, Clone, Default)]
+pub struct RouteDiff {
+    pub added: Vec<RouteData>,
+    pub changed: Vec<RouteChange>,
+    /// Routes previously synced from zwift-offline that the server no longer lists
+    pub removed: Vec<RouteData>,
+    pub unchanged: usize,
+}
+
+impl RouteDiff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Totals written by [`apply_snapshot`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub routes_written: usize,
+    /// Routes skipped because the local data is more trusted
+    pub routes_kept: usize,
+    pub start_lines: usize,
+}
+
+/// Server route filled in with what zwift-offline does not know
+///
+/// The export has no lead-in elevation, alternative lead-ins or slugs, and
+/// reports 0m elevation for many routes; local values are kept for those.
+#[must_use]
+pub fn merge_route(local: Option<&RouteData>, server: &RouteData) -> RouteData {
+    let Some(local) = local else {
+        return server.clone();
+    };
+    RouteData {
+        elevation_m: if server.elevation_m > 0 {
+            server.elevation_m
+        } else {
+            local.elevation_m
+        },
+        lead_in_elevation_m: local.lead_in_elevation_m,
+        lead_in_distance_free_ride_km: local.lead_in_distance_free_ride_km,
+        lead_in_elevation_free_ride_m: local.lead_in_elevation_free_ride_m,
+        lead_in_distance_meetups_km: local.lead_in_distance_meetups_km,
+        lead_in_elevation_meetups_m: local.lead_in_elevation_meetups_m,
+        slug: local.slug.clone(),
+        ..server.clone()
+    }
+}
+
+/// Name, distance, lead-in, elevation and surface values that differ
+fn field_changes(local: &RouteData, server: &RouteData) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    if local.name != server.name {
+        changes.push(FieldChange {
+            field: "name",
+            local: local.name.clone(),
+            server: server.name.clone(),
+        });
+    }
+    let mut km = |field, local: f64, server: f64| {
+        if (local - server).abs() >= DISTANCE_EPSILON_KM {
+            changes.push(FieldChange {
+                field,
+                local: format!("{local:.2} km"),
+                server: format!("{server:.2} km"),
+            });
+        }
+    };
+    km("distance", local.distance_km, server.distance_km);
+    km(
+        "lead-in",
+        local.lead_in_distance_km,
+        server.lead_in_distance_km,
+    );
+    if local.elevation_m != server.elevation_m {
+        changes.push(FieldChange {
+            field: "elevation",
+            local: format!("{} m", local.elevation_m),
+            server: format!("{} m", server.elevation_m),
+        });
+    }
+    if local.surface != server.surface {
+        changes.push(FieldChange {
+            field: "surface",
+            local: local.surface.clone(),
+            server: server.surface.clone(),
+        });
+    }
+    changes
+}
+
+/// Compare the server's routes with the local database without writing anything
+pub fn diff_routes(db: &Database, server_routes: &[RouteData]) -> Result<RouteDiff> {
+    let local: HashMap<u32, RouteData> = db
+        .get_all_routes()?
+        .into_iter()
+        .map(|route| (route.route_id, route))
+        .collect();
+    let zwift_offline_confidence = RouteSource::ZwiftOffline.default_confidence();
+
+    let mut diff = RouteDiff::default();
+    for server in server_routes {
+        let Some(existing) = local.get(&server.route_id) else {
+            diff.added.push(server.clone());
+            continue;
+        };
+        let route = merge_route(Some(existing), server);
+        let changes = field_changes(existing, &route);
+        if changes.is_empty() {
+            diff.unchanged += 1;
+            continue;
+        }
+        let kept = db
+            .get_route_provenance(route.route_id)?
+            .is_some_and(|provenance| provenance.confidence > zwift_offline_confidence);
+        diff.changed.push(RouteChange {
+            route,
+            changes,
+            kept,
+        });
+    }
+
+    let server_ids: HashSet<u32> = server_routes.iter().map(|r| r.route_id).collect();
+    for (route_id, route) in &local {
+        if server_ids.contains(route_id) {
+            continue;
+        }
+        let synced = db
+            .get_route_provenance(*route_id)?
+            .is_some_and(|provenance| provenance.source == RouteSource::ZwiftOffline);
+        if synced {
+            diff.removed.push(route.clone());
+        }
+    }
+
+    diff.added.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.changed.sort_by(|a, b| a.route.name.cmp(&b.route.name));
+    diff.removed.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(diff)
+}
+
+/// Write the server's routes and start lines
+///
+/// Every listed route is rewritten so its verification time is refreshed.
+/// Routes the server no longer lists are left in place, since race results
+/// may still refer to them.
+pub fn apply_snapshot(db: &Database, snapshot: &SyncSnapshot) -> Result<SyncSummary> {
+    let mut summary = SyncSummary::default();
+    for server in &snapshot.routes {
+        let local = db.get_route(server.route_id)?;
+        // get_route follows aliases; only merge with the row stored under this ID
+        let local = local.filter(|route| route.route_id == server.route_id);
+        let route = merge_route(local.as_ref(), server);
+        if db.add_route(
+            &route,
+            &RouteProvenance::verified_now(RouteSource::ZwiftOffline),
+        )? {
+            summary.routes_written += 1;
+        } else {
+            summary.routes_kept += 1;
+        }
+    }
+
+    db.save_start_lines(&snapshot.start_lines)?;
+    summary.start_lines = snapshot.start_lines.len();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(route_id: u32, name: &str, distance_km: f64, elevation_m: u32) -> RouteData {
+        RouteData {
+            route_id,
+            distance_km,
+            elevation_m,
+            name: name.to_string(),
+            world: "Watopia".to_string(),
+            surface: "road".to_string(),
+            lead_in_distance_km: 0.4,
+            lead_in_elevation_m: 5,
+            slug: Some("local-slug".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_what_the_server_lacks() {
+        let local = route(1, "Volcano Flat", 12.3, 45);
+        let mut server = route(1, "Volcano Flat", 12.4, 0);
+        server.lead_in_elevation_m = 0;
+        server.slug = None;
+
+        let merged = merge_route(Some(&local), &server);
+        assert_eq!(merged.distance_km, 12.4);
+        assert_eq!(merged.elevation_m, 45);
+        assert_eq!(merged.lead_in_elevation_m, 5);
+        assert_eq!(merged.slug.as_deref(), Some("local-slug"));
+
+        let changes = field_changes(&local, &merged);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "distance");
+        assert_eq!(changes[0].server, "12.40 km");
+    }
+
+    #[test]
+    fn test_diff_and_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        let synced = RouteProvenance::unverified(RouteSource::ZwiftOffline);
+        db.add_route(&route(10, "Gone Route", 5.0, 10), &synced)
+            .unwrap();
+        db.add_route(&route(11, "Same Route", 8.0, 20), &synced)
+            .unwrap();
+        db.add_route(&route(12, "Gravel Route", 9.0, 30), &synced)
+            .unwrap();
+
+        let mut gravel = route(12, "Gravel Route", 9.0, 30);
+        gravel.surface = "gravel".to_string();
+        let snapshot = SyncSnapshot {
+            routes: vec![
+                route(11, "Same Route", 8.0, 20),
+                gravel,
+                route(13, "New Route", 20.0, 150),
+            ],
+            start_lines: vec![StartLine {
+                route_id: 13,
+                start_location: 1,
+                name: "Watopia - New Route".to_string(),
+                road: 5,
+                road_time: 123_456,
+                forward: None,
+            }],
+            ..Default::default()
+        };
+
+        let diff = diff_routes(&db, &snapshot.routes).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].route_id, 13);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].changes[0].field, "surface");
+        assert!(!diff.changed[0].kept);
+        // Seed routes were never synced, so only the zwift-offline route counts as removed
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].route_id, 10);
+        assert_eq!(diff.unchanged, 1);
+
+        let summary = apply_snapshot(&db, &snapshot).unwrap();
+        assert_eq!(summary.routes_written, 3);
+        assert_eq!(summary.start_lines, 1);
+        assert_eq!(db.get_route(12).unwrap().unwrap().surface, "gravel");
+        assert!(db.get_route(10).unwrap().is_some());
+        assert_eq!(db.get_start_lines().unwrap(), snapshot.start_lines);
+
+        let diff = diff_routes(&db, &snapshot.routes).unwrap();
+        assert!(diff.added.is_empty() && diff.changed.is_empty());
+    }
+}
@@ -1,27 +1,31 @@
 use anyhow::Result;
-use regex::Regex;
 
-use crate::config::{FullConfig, Secrets};
 use zwift_race_finder::cache::*;
 use zwift_race_finder::category::*;
+use zwift_race_finder::config::{FullConfig, Secrets};
+use zwift_race_finder::database::{Database, RiderStats, ScoreHistoryEntry};
 use zwift_race_finder::models::*;
+use zwift_race_finder::score_estimate;
+use zwift_race_finder::zwift_api::ZwiftApiClient;
+use zwift_race_finder::zwiftpower_profile::{self, ZwiftPowerProfile};
 
 pub async fn fetch_zwiftpower_stats(secrets: &Secrets) -> Result<Option<UserStats>> {
     let debug = std::env::var("ZRF_ZP_DEBUG").is_ok();
     // Only try to fetch if we have profile ID configured
-    let (profile_id, session_id) = match (
+    let url = match (
         &secrets.zwiftpower_profile_id,
         &secrets.zwiftpower_session_id,
     ) {
-        (Some(pid), Some(sid)) => (pid, sid),
+        (Some(pid), Some(sid)) => {
+            format!("https://zwiftpower.com/profile.php?z={}&sid={}", pid, sid)
+        }
         (Some(pid), None) => {
             // Try without session ID (might work for public profiles)
-            let url = format!("https://zwiftpower.com/profile.php?z={}", pid);
             if debug {
                 eprintln!("ZP: no session ID configured, trying public profile access");
             }
             eprintln!("Note: No session ID configured, trying public profile access...");
-            return fetch_zwiftpower_public(&url).await;
+            format!("https://zwiftpower.com/profile.php?z={}", pid)
         }
         _ => {
             if debug {
@@ -31,76 +35,109 @@ pub async fn fetch_zwiftpower_stats(secrets: &Secrets) -> Result<Option<UserStat
         }
     };
 
-    let url = format!(
-        "https://zwiftpower.com/profile.php?z={}&sid={}",
-        profile_id, session_id
-    );
-
     let client = reqwest::Client::builder()
         .user_agent("Zwift Race Finder")
         .build()?;
 
-    let response = client.get(&url).send().await;
-
-    match response {
-        Ok(resp) if resp.status().is_success() => {
-            let html = resp.text().await?;
-
-            // Parse Zwift Racing Score from the HTML
-            let score_regex = Regex::new(r"(?s)Zwift Racing Score.*?(\d+)").unwrap();
-            let category_regex =
-                Regex::new(r"Category(?:[^A-Z]+)?([A-E]\\+?)").unwrap();
+    let html = match client.get(&url).send().await {
+        Ok(resp) if resp.status().is_success() => resp.text().await?,
+        // If we can't fetch from ZwiftPower, return None to use defaults
+        _ => return Ok(None),
+    };
 
+    let profile = match zwiftpower_profile::parse_profile(&html) {
+        Ok(profile) => profile,
+        Err(e) => {
             if debug {
-                eprintln!(
-                    "ZP: body has score label? {}",
-                    html.contains("Zwift Racing Score")
-                );
-                eprintln!("ZP: score regex match? {}", score_regex.is_match(&html));
-                eprintln!(
-                    "ZP: category regex match? {}",
-                    category_regex.is_match(&html)
-                );
+                eprintln!("ZP: {}", e);
             }
-
-            if let Some(score_match) = score_regex.captures(&html) {
-                let zwift_score: u32 = score_match[1].parse().unwrap_or(195);
-                let category = category_regex
-                    .captures(&html)
-                    .and_then(|caps| caps.get(1).map(|m| m.as_str().to_string()))
-                    .unwrap_or_else(|| get_category_from_score(zwift_score).to_string());
-
-                return Ok(Some(UserStats {
-                    zwift_score,
-                    category,
-                    username: "ZwiftPower".to_string(),
-                }));
-            }
-        }
-        _ => {
-            // If we can't fetch from ZwiftPower, return None to use defaults
             return Ok(None);
         }
+    };
+    if debug {
+        eprintln!(
+            "ZP: score {:?}, category {:?}, weight {:?}, FTP {:?}, {} recent results",
+            profile.zwift_score,
+            profile.category,
+            profile.weight_kg,
+            profile.ftp_w,
+            profile.recent_results.len()
+        );
     }
 
-    Ok(None)
-}
+    let Some(zwift_score) = profile.zwift_score else {
+        return Ok(None);
+    };
+    let category = profile
+        .category
+        .clone()
+        .unwrap_or_else(|| get_category_from_score(zwift_score).to_string());
+    record_score_history(&profile, zwift_score, &category, debug);
 
-async fn fetch_zwiftpower_public(url: &str) -> Result<Option<UserStats>> {
-    // Simplified version for public profile access
-    let client = reqwest::Client::builder()
-        .user_agent("Zwift Race Finder")
-        .build()?;
+    Ok(Some(UserStats {
+        zwift_score,
+        category,
+        username: "ZwiftPower".to_string(),
+        estimate: None,
+    }))
+}
 
-    match client.get(url).send().await {
-        Ok(resp) if resp.status().is_success() => {
-            // Try to parse what we can from public page
-            Ok(None) // Public pages might be limited
+/// Store a successful fetch for the active profile; failing to record never blocks the search
+fn record_score_history(
+    profile: &ZwiftPowerProfile,
+    zwift_score: u32,
+    category: &str,
+    debug: bool,
+) {
+    let entry = ScoreHistoryEntry {
+        recorded_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        zwift_score,
+        category: Some(category.to_string()),
+        weight_kg: profile.weight_kg,
+        ftp_w: profile.ftp_w,
+        source: "zwiftpower".to_string(),
+    };
+    let recorded = Database::new().and_then(|db| {
+        db.record_score(&entry)?;
+        // Keep this rider's physics stats in step with ZwiftPower
+        if let Some(weight_kg) = profile.weight_kg {
+            let height_m = db.get_rider_stats()?.map_or(1.82, |stats| stats.height_m);
+            db.save_rider_stats(&RiderStats {
+                height_m,
+                weight_kg,
+                ftp_watts: profile.ftp_w,
+            })?;
+        }
+        Ok(())
+    });
+    if let Err(e) = recorded {
+        if debug {
+            eprintln!("ZP: failed to record score history: {}", e);
         }
-        _ => Ok(None),
     }
 }
 
+/// Racing score from the authenticated Zwift API, if a token is configured
+async fn fetch_zwift_api_stats(config: &FullConfig) -> Result<Option<UserStats>> {
+    let Some(token) = config.secrets.zwift_api_token.as_deref() else {
+        return Ok(None);
+    };
+    let client = ZwiftApiClient::new(config.config.zwift_api.base_url(), token)?;
+    let rider = client.fetch_profile().await?;
+    let Some(zwift_score) = rider.racing_score() else {
+        return Ok(None);
+    };
+    Ok(Some(UserStats {
+        zwift_score,
+        category: rider.category().map_or_else(
+            || get_category_from_score(zwift_score).to_string(),
+            str::to_string,
+        ),
+        username: "Zwift".to_string(),
+        estimate: None,
+    }))
+}
+
 pub async fn get_user_stats(config: &FullConfig) -> Result<UserStats> {
     // Try to load from cache first
     if let Ok(Some(stats)) = load_cached_stats() {
@@ -114,14 +151,47 @@ pub async fn get_user_stats(config: &FullConfig) -> Result<UserStats> {
         return Ok(stats);
     }
 
-    // Use configured defaults or fallback
-    let zwift_score = config.default_zwift_score().unwrap_or(195);
-    Ok(UserStats {
-        zwift_score,
-        category: config
-            .default_category()
-            .cloned()
-            .unwrap_or_else(|| get_category_from_score(zwift_score).to_string()),
+    // Try the rider's own Zwift profile when a token is configured
+    if let Ok(Some(stats)) = fetch_zwift_api_stats(config).await {
+        let _ = save_cached_stats(&stats);
+        return Ok(stats);
+    }
+
+    Ok(fallback_stats(
+        config,
+        score_estimate::estimate_from_history,
+    ))
+}
+
+/// Stats when no service knows the rider: the configured score, then an
+/// estimate from recorded races, then the usual default
+pub fn fallback_stats(
+    config: &FullConfig,
+    estimate: impl FnOnce() -> Option<score_estimate::ScoreEstimate>,
+) -> UserStats {
+    if let Some(zwift_score) = config.default_zwift_score() {
+        return UserStats {
+            zwift_score,
+            category: config
+                .default_category()
+                .cloned()
+                .unwrap_or_else(|| get_category_from_score(zwift_score).to_string()),
+            username: "User".to_string(),
+            estimate: None,
+        };
+    }
+    if let Some(estimate) = estimate() {
+        return UserStats {
+            zwift_score: estimate.zwift_score,
+            category: estimate.category.to_string(),
+            username: "User".to_string(),
+            estimate: Some(estimate),
+        };
+    }
+    UserStats {
+        zwift_score: 195,
+        category: get_category_from_score(195).to_string(),
         username: "User".to_string(),
-    })
+        estimate: None,
+    }
 }
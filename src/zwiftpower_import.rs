@@ -0,0 +1,486 @@
+//! Import of ZwiftPower race history as race results
+//!
+//! Accepts the files a rider can save from ZwiftPower without any browser
+//! scripting:
+//! - the profile results JSON (`{"data": [...]}`, served to the profile page),
+//! - the array written by `tools/import/zwiftpower/zwiftpower_event_extractor.js`,
+//! - a saved profile page (HTML with the `#profile_results` table).
+//!
+//! Each row becomes a [`ZwiftPowerResult`]; mapping the event to a route is
+//! done by [`resolve_event_route`] using earlier results, multi-lap patterns
+//! and route names.
+
+use crate::database::{Database, RaceResult};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::Value;
+use std::path::Path;
+
+/// One race from a ZwiftPower profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ZwiftPowerResult {
+    pub event_id: Option<u64>,
+    pub event_name: String,
+    /// Race date (YYYY-MM-DD)
+    pub date: String,
+    pub category: Option<String>,
+    /// Finishing position within the category
+    pub position: Option<u32>,
+    pub field_size: Option<u32>,
+    /// Finish time in seconds
+    pub time_secs: Option<f64>,
+    pub avg_power_w: Option<u32>,
+    pub np_power_w: Option<u32>,
+    pub weight_kg: Option<f64>,
+    pub distance_km: Option<f64>,
+    /// Route name guessed by the browser extractor
+    pub route_name: Option<String>,
+}
+
+impl ZwiftPowerResult {
+    /// Build a race result on the given route, or `None` without a finish time
+    #[must_use]
+    pub fn to_race_result(&self, route_id: u32, zwift_score: u32) -> Option<RaceResult> {
+        let time_secs = self.time_secs.filter(|secs| *secs > 0.0)?;
+        Some(RaceResult {
+            route_id,
+            event_name: self.event_name.clone(),
+            actual_minutes: (time_secs / 60.0).round() as u32,
+            zwift_score,
+            race_date: self.date.clone(),
+            position: self.position,
+            field_size: self.field_size,
+            category: self.category.clone(),
+            avg_power_w: self.avg_power_w,
+            np_power_w: self.np_power_w,
+            weight_kg: self.weight_kg,
+            event_id: self.event_id,
+            source: Some("zwiftpower".to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+/// How an event was mapped to its route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteMapping {
+    /// Earlier results or a multi-lap pattern for the same event
+    KnownEvent,
+    /// Part of the event name is a route name
+    RouteName,
+}
+
+/// Read a saved ZwiftPower export, picking the format from its contents
+pub fn load_results(path: &Path) -> Result<Vec<ZwiftPowerResult>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_results(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Parse ZwiftPower JSON (profile data or extractor output) or a saved profile page
+pub fn parse_results(contents: &str) -> Result<Vec<ZwiftPowerResult>> {
+    let trimmed = contents.trim_start();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return parse_profile_html(contents);
+    }
+
+    let json: Value = serde_json::from_str(trimmed)?;
+    let rows = match &json {
+        Value::Array(rows) => rows,
+        Value::Object(map) => match map.get("data") {
+            Some(Value::Array(rows)) => rows,
+            _ => bail!("JSON has no \"data\" array of results"),
+        },
+        _ => bail!("Unexpected JSON; expected a list of results"),
+    };
+    Ok(rows.iter().filter_map(result_from_json).collect())
+}
+
+/// Map one JSON row; field names differ between the profile data and the extractor
+fn result_from_json(row: &Value) -> Option<ZwiftPowerResult> {
+    let field = |names: &[&str]| names.iter().find_map(|name| row.get(*name));
+    let text = |names: &[&str]| field(names).and_then(json_text);
+    let number = |names: &[&str]| text(names).and_then(|t| parse_number(&t));
+
+    let event_name = text(&["event_title", "event_name", "name"])?;
+    let date = match field(&["event_date", "date"])? {
+        Value::Number(ts) => DateTime::from_timestamp(ts.as_i64()?, 0)?
+            .format("%Y-%m-%d")
+            .to_string(),
+        other => normalize_date(&json_text(other)?)?,
+    };
+    let (position, field_size) = text(&["position_in_cat", "position", "pos"])
+        .map(|p| parse_position(&p))
+        .unwrap_or_default();
+
+    Some(ZwiftPowerResult {
+        event_id: text(&["zid", "event_id"]).and_then(|id| id.parse().ok()),
+        event_name,
+        date,
+        category: text(&["category"]).filter(|c| !c.is_empty()),
+        position,
+        field_size,
+        // The extractor's `estimated_minutes` is a guess from distance, not a finish time
+        time_secs: match field(&["time", "time_seconds"]) {
+            Some(Value::String(t)) => parse_duration(t),
+            Some(value) => json_text(value).and_then(|t| parse_number(&t)),
+            None => number(&["actual_minutes"]).map(|minutes| minutes * 60.0),
+        },
+        avg_power_w: number(&["avg_power"]).map(|w| w.round() as u32),
+        np_power_w: number(&["np", "normalized_power"]).map(|w| w.round() as u32),
+        weight_kg: number(&["weight", "weight_kg"]).filter(|kg| *kg > 0.0),
+        distance_km: number(&["distance_km", "distance"]).filter(|km| *km > 0.0),
+        route_name: text(&["route_name"]).filter(|name| !name.is_empty()),
+    })
+}
+
+/// Text of a JSON value; ZwiftPower wraps many values as `[value, flag]`
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(items) => items.first().and_then(json_text),
+        _ => None,
+    }
+}
+
+/// Leading number of a value such as `199w`, `86.0kg` or `#17`
+pub(crate) fn parse_number(text: &str) -> Option<f64> {
+    let re = Regex::new(r"-?\d+(?:\.\d+)?").unwrap();
+    re.find(text)?.as_str().parse().ok()
+}
+
+/// `#17`, `17` or `17/45` as (position, field size)
+fn parse_position(text: &str) -> (Option<u32>, Option<u32>) {
+    let mut parts = text.trim().trim_start_matches('#').split('/');
+    let position = parts.next().and_then(|p| p.trim().parse().ok());
+    let field_size = parts.next().and_then(|f| f.trim().parse().ok());
+    (position, field_size)
+}
+
+/// `1:02:03`, `58:12` or plain seconds
+fn parse_duration(text: &str) -> Option<f64> {
+    let parts: Vec<f64> = text
+        .trim()
+        .split(':')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [secs] => Some(*secs),
+        [mins, secs] => Some(mins * 60.0 + secs),
+        [hours, mins, secs] => Some(hours * 3600.0 + mins * 60.0 + secs),
+        _ => None,
+    }
+}
+
+/// ZwiftPower shows US dates (`5/24/25`); also accept ISO dates
+fn normalize_date(text: &str) -> Option<String> {
+    let text = text.trim();
+    ["%Y-%m-%d", "%m/%d/%y", "%m/%d/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Parse the `#profile_results` table of a saved profile page
+fn parse_profile_html(html: &str) -> Result<Vec<ZwiftPowerResult>> {
+    let document = Html::parse_document(html);
+    let table_selector = Selector::parse("table#profile_results").unwrap();
+    let Some(table) = document.select(&table_selector).next() else {
+        bail!("No #profile_results table found; save the ZwiftPower profile page");
+    };
+    Ok(parse_results_table(table))
+}
+
+/// Rows of a ZwiftPower results table
+///
+/// Columns are found by header text; tables without headers fall back to
+/// the column order used by the browser extractor.
+pub(crate) fn parse_results_table(table: ElementRef) -> Vec<ZwiftPowerResult> {
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let link_selector = Selector::parse("a[href]").unwrap();
+    let event_id_re = Regex::new(r"[?&]zid=(\d+)").unwrap();
+
+    let mut columns = HtmlColumns::default();
+    let mut results = Vec::new();
+    for row in table.select(&row_selector) {
+        let cells: Vec<ElementRef> = row.select(&cell_selector).collect();
+        if cells.is_empty() {
+            continue;
+        }
+        let texts: Vec<String> = cells.iter().map(|cell| element_text(*cell)).collect();
+        if cells.iter().all(|cell| cell.value().name() == "th") {
+            columns = HtmlColumns::from_headers(&texts);
+            continue;
+        }
+
+        let cell = |index: Option<usize>| index.and_then(|i| texts.get(i)).map(String::as_str);
+        let (Some(event_name), Some(date)) = (
+            cell(columns.event).filter(|name| !name.is_empty()),
+            cell(columns.date).and_then(normalize_date),
+        ) else {
+            continue;
+        };
+        let (position, field_size) = cell(columns.position)
+            .map(parse_position)
+            .unwrap_or_default();
+        let number = |index| cell(index).and_then(parse_number);
+
+        results.push(ZwiftPowerResult {
+            event_id: columns
+                .event
+                .and_then(|i| cells.get(i))
+                .and_then(|cell| cell.select(&link_selector).next())
+                .and_then(|link| event_id_re.captures(link.value().attr("href")?))
+                .and_then(|caps| caps[1].parse().ok()),
+            event_name: event_name.to_string(),
+            date,
+            category: cell(columns.category)
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty()),
+            position,
+            field_size,
+            time_secs: cell(columns.time).and_then(parse_duration),
+            avg_power_w: number(columns.avg_power).map(|w| w.round() as u32),
+            np_power_w: number(columns.np_power).map(|w| w.round() as u32),
+            weight_kg: number(columns.weight),
+            distance_km: number(columns.distance),
+            route_name: None,
+        });
+    }
+    results
+}
+
+/// Column positions in the profile results table
+#[derive(Debug, Clone, Copy)]
+struct HtmlColumns {
+    category: Option<usize>,
+    position: Option<usize>,
+    date: Option<usize>,
+    event: Option<usize>,
+    time: Option<usize>,
+    avg_power: Option<usize>,
+    np_power: Option<usize>,
+    weight: Option<usize>,
+    distance: Option<usize>,
+}
+
+impl Default for HtmlColumns {
+    /// Layout documented in `zwiftpower_event_extractor.js` (no time column)
+    fn default() -> Self {
+        Self {
+            category: Some(0),
+            position: Some(1),
+            date: Some(2),
+            event: Some(3),
+            time: None,
+            avg_power: Some(9),
+            np_power: Some(10),
+            weight: Some(12),
+            distance: Some(15),
+        }
+    }
+}
+
+impl HtmlColumns {
+    fn from_headers(headers: &[String]) -> Self {
+        let find = |keys: &[&str]| {
+            headers.iter().position(|header| {
+                let header = header.to_lowercase();
+                keys.iter()
+                    .any(|key| header == *key || header.starts_with(&format!("{key} ")))
+            })
+        };
+        let defaults = Self::default();
+        Self {
+            category: find(&["cat", "category"]).or(defaults.category),
+            position: find(&["pos", "position", "#"]).or(defaults.position),
+            date: find(&["date"]).or(defaults.date),
+            event: find(&["event", "race", "name"]).or(defaults.event),
+            time: find(&["time"]),
+            avg_power: find(&["avg", "avg power"]).or(defaults.avg_power),
+            np_power: find(&["np", "normalized power"]).or(defaults.np_power),
+            weight: find(&["weight"]).or(defaults.weight),
+            distance: find(&["distance", "dist"]).or(defaults.distance),
+        }
+    }
+}
+
+/// Visible text of an element with whitespace collapsed
+pub(crate) fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Find the route an event ran on
+///
+/// Tries, in order: earlier results for the same event name, multi-lap event
+/// patterns, then route names matching `route_hint`, the whole event name or
+/// one of its ` - `, `|` or `:` separated parts. Route names match loosely
+/// ("Volcano Circ. x3"), but only when one route clearly fits. Alias route IDs
+/// resolve to their canonical route. Returns `(route_id, laps, how)`.
+pub fn resolve_event_route(
+    db: &Database,
+    event_name: &str,
+    route_hint: Option<&str>,
+) -> Result<Option<(u32, u32, RouteMapping)>> {
+    let canonical = |route_id: u32| -> Result<Option<u32>> {
+        Ok(db.get_route(route_id)?.map(|route| route.route_id))
+    };
+
+    if let Some((route_id, laps)) = db.find_route_for_event(event_name)? {
+        if let Some(route_id) = canonical(route_id)? {
+            return Ok(Some((route_id, laps, RouteMapping::KnownEvent)));
+        }
+    }
+
+    let without_category = Regex::new(r"\s*\((?:Cat\s*)?[A-E]\+?\)\s*$").unwrap();
+    let name = without_category.replace(event_name, "");
+    let parts = route_hint
+        .into_iter()
+        .chain(std::iter::once(name.as_ref()))
+        .chain(name.split([':', '|']))
+        .chain(name.split(" - "));
+    for part in parts {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if let Some(route) = db.get_route_by_name(part)? {
+            return Ok(Some((route.route_id, 1, RouteMapping::RouteName)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile_json() {
+        let json = r#"{"data": [
+            {"zid": "4512345", "event_date": 1748100000, "event_title": "2025 SISU Pinkki - Stage 5",
+             "category": "D", "pos": 40, "position_in_cat": 17, "time": [6895.2, 0],
+             "avg_power": [167, 0], "np": [182, 0], "weight": ["86.0", 0]},
+            {"zid": "4512399", "event_date": 1748200000, "event_title": "DNS race",
+             "category": "D", "position_in_cat": 0, "time": [0, 0]}
+        ]}"#;
+        let results = parse_results(json).unwrap();
+        assert_eq!(results.len(), 2);
+
+        let first = &results[0];
+        assert_eq!(first.event_id, Some(4_512_345));
+        assert_eq!(first.date, "2025-05-24");
+        assert_eq!(first.position, Some(17));
+        assert_eq!(first.weight_kg, Some(86.0));
+
+        let result = first.to_race_result(42, 210).unwrap();
+        assert_eq!(result.actual_minutes, 115);
+        assert_eq!(result.category.as_deref(), Some("D"));
+        assert_eq!(result.np_power_w, Some(182));
+        assert_eq!(result.source.as_deref(), Some("zwiftpower"));
+
+        // No finish time means no usable result
+        assert!(results[1].to_race_result(42, 210).is_none());
+    }
+
+    #[test]
+    fn test_parse_extractor_json() {
+        let json = r##"[{"date": "2025-05-24", "event_name": "3R Volcano Flat Race",
+            "event_id": "4512345", "category": "C", "position": "#5/32",
+            "avg_power": "199w", "normalized_power": "210w", "weight": 85.5,
+            "distance_km": 36.6, "time": "1:02:30"}]"##;
+        let results = parse_results(json).unwrap();
+        let result = &results[0];
+        assert_eq!((result.position, result.field_size), (Some(5), Some(32)));
+        assert_eq!(result.avg_power_w, Some(199));
+        assert_eq!(result.time_secs, Some(3750.0));
+        assert_eq!(result.distance_km, Some(36.6));
+
+        // The profile extractor only estimates the time; that must not become a result
+        let json = r#"[{"date": "2025-05-24", "event_name": "2025 SISU Pinkki - Stage 5",
+            "event_id": 4512345, "category": "D", "position": 17, "weight_kg": 86.0,
+            "distance_km": 56, "estimated_minutes": 112, "route_name": "Castle to Castle"}]"#;
+        let result = &parse_results(json).unwrap()[0];
+        assert_eq!(result.time_secs, None);
+        assert_eq!(result.weight_kg, Some(86.0));
+        assert_eq!(result.route_name.as_deref(), Some("Castle to Castle"));
+        assert!(result.to_race_result(1, 210).is_none());
+    }
+
+    #[test]
+    fn test_parse_profile_html() {
+        let html = r#"<html><body>
+            <table id="profile_results" class="table">
+              <thead><tr><th>Cat</th><th>Pos</th><th>Date</th><th>Event</th><th>Time</th>
+                <th>Avg</th><th>NP</th><th>Weight</th><th>Distance</th></tr></thead>
+              <tbody>
+                <tr><td><span>D</span></td><td>#17</td><td>5/24/25</td>
+                  <td><a href="events.php?zid=4512345">2025 SISU Pinkki &amp; Friends</a></td>
+                  <td>1:54:55</td><td>167w</td><td>182w</td><td>86.0kg</td><td>58km</td></tr>
+              </tbody>
+            </table></body></html>"#;
+        let results = parse_results(html).unwrap();
+        assert_eq!(results.len(), 1);
+
+        let result = &results[0];
+        assert_eq!(result.event_name, "2025 SISU Pinkki & Friends");
+        assert_eq!(result.event_id, Some(4_512_345));
+        assert_eq!(result.date, "2025-05-24");
+        assert_eq!(result.category.as_deref(), Some("D"));
+        assert_eq!(result.position, Some(17));
+        assert_eq!(result.time_secs, Some(6895.0));
+        assert_eq!(result.np_power_w, Some(182));
+        assert_eq!(result.distance_km, Some(58.0));
+
+        assert!(parse_results("<html>login required</html>").is_err());
+    }
+
+    #[test]
+    fn test_resolve_event_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("races.db")).unwrap();
+        db.add_multi_lap_pattern("Crit Club Dolphin", 2_698_009_951, 4, None)
+            .unwrap();
+
+        // Multi-lap pattern contained in the event name
+        let (route_id, laps, how) = resolve_event_route(&db, "Crit Club Dolphin (C)", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (route_id, laps, how),
+            (2_698_009_951, 4, RouteMapping::KnownEvent)
+        );
+
+        // A route name inside the event name
+        let (route_id, laps, how) = resolve_event_route(&db, "Stage 3: Castle to Castle (D)", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (route_id, laps, how),
+            (3_742_187_716, 1, RouteMapping::RouteName)
+        );
+
+        // A misspelt route name still maps, even without separators
+        let (route_id, _, how) = resolve_event_route(&db, "Castle to Casle Sprint (D)", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!((route_id, how), (3_742_187_716, RouteMapping::RouteName));
+
+        assert!(resolve_event_route(&db, "Mystery Race", None)
+            .unwrap()
+            .is_none());
+        let (route_id, _, _) = resolve_event_route(&db, "Mystery Race", Some("Bell Lap"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(route_id, 1_258_415_487);
+    }
+}
@@ -0,0 +1,146 @@
+//! ZwiftPower profile page parsing
+//!
+//! The rider's details are read from the label/value rows of the profile page
+//! (`<th>Category</th><td>C</td>`, or `<dt>`/`<dd>` pairs), and recent races from
+//! its `#profile_results` table. Values are only taken from cells next to their
+//! label, so numbers elsewhere on the page (team names, season years, best
+//! efforts) are never mistaken for the racing score.
+
+use crate::zwiftpower_import::{element_text, parse_number, parse_results_table, ZwiftPowerResult};
+use anyhow::{bail, Result};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+/// What a ZwiftPower profile page says about the rider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ZwiftPowerProfile {
+    pub zwift_score: Option<u32>,
+    /// Category shown on the profile (A+, A, B, C, D or E)
+    pub category: Option<String>,
+    pub weight_kg: Option<f64>,
+    pub ftp_w: Option<u32>,
+    /// Most recent races first, as listed on the page
+    pub recent_results: Vec<ZwiftPowerResult>,
+}
+
+/// Parse a profile page
+///
+/// Fails when the page has neither rider details nor results, which is what
+/// ZwiftPower serves when the session has expired.
+pub fn parse_profile(html: &str) -> Result<ZwiftPowerProfile> {
+    let document = Html::parse_document(html);
+    let mut profile = ZwiftPowerProfile::default();
+
+    for (label, value) in labelled_values(&document) {
+        let text = element_text(value);
+        match label.to_lowercase().trim_end_matches(':') {
+            "zwift racing score" | "racing score" | "zrs" => {
+                profile.zwift_score = profile.zwift_score.or_else(|| parse_score(&text));
+            }
+            "category" | "cat" => {
+                profile.category = profile.category.take().or_else(|| parse_category(&text));
+            }
+            "weight" => {
+                profile.weight_kg = profile.weight_kg.or_else(|| parse_number(&text));
+            }
+            "ftp" | "zftp" => {
+                profile.ftp_w = profile
+                    .ftp_w
+                    .or_else(|| parse_number(&text).map(|w| w.round() as u32));
+            }
+            _ => {}
+        }
+    }
+
+    let results_selector = Selector::parse("table#profile_results").unwrap();
+    if let Some(table) = document.select(&results_selector).next() {
+        profile.recent_results = parse_results_table(table);
+    }
+
+    if profile == ZwiftPowerProfile::default() {
+        bail!("Not a ZwiftPower profile page; the session ID may have expired");
+    }
+    Ok(profile)
+}
+
+/// Label and value cells from table rows and definition lists
+///
+/// Rows of the results table are skipped; its "Cat" column is not the rider's category.
+fn labelled_values(document: &Html) -> Vec<(String, ElementRef<'_>)> {
+    let row_selector = Selector::parse("tr:not(#profile_results tr)").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let term_selector = Selector::parse("dt").unwrap();
+
+    let mut pairs = Vec::new();
+    for row in document.select(&row_selector) {
+        let cells: Vec<ElementRef> = row.select(&cell_selector).collect();
+        if let [label, value, ..] = cells.as_slice() {
+            pairs.push((element_text(*label), *value));
+        }
+    }
+    for term in document.select(&term_selector) {
+        let definition = term
+            .next_siblings()
+            .find_map(ElementRef::wrap)
+            .filter(|sibling| sibling.value().name() == "dd");
+        if let Some(definition) = definition {
+            pairs.push((element_text(term), definition));
+        }
+    }
+    pairs
+}
+
+/// First whole number in a score cell, e.g. `412 (max 90 days: 431)`
+fn parse_score(text: &str) -> Option<u32> {
+    let re = Regex::new(r"\b(\d{1,4})\b").unwrap();
+    re.captures(text)?[1].parse().ok()
+}
+
+/// Category letter in a category cell, e.g. `C` or `Category A+`
+fn parse_category(text: &str) -> Option<String> {
+    let re = Regex::new(r"\b([A-E])(\+?)(?:\s|$)").unwrap();
+    let caps = re.captures(text)?;
+    Some(format!("{}{}", &caps[1], &caps[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_saved_profile_page() {
+        let profile =
+            parse_profile(include_str!("../tests/fixtures/zwiftpower/profile.html")).unwrap();
+        assert_eq!(profile.zwift_score, Some(412));
+        assert_eq!(profile.category.as_deref(), Some("C"));
+        assert_eq!(profile.weight_kg, Some(75.4));
+        assert_eq!(profile.ftp_w, Some(262));
+
+        assert_eq!(profile.recent_results.len(), 2);
+        let latest = &profile.recent_results[0];
+        assert_eq!(latest.event_id, Some(4_823_301));
+        assert_eq!(
+            (latest.position, latest.time_secs),
+            (Some(12), Some(3492.0))
+        );
+        assert_eq!(profile.recent_results[1].event_id, Some(4_810_077));
+    }
+
+    #[test]
+    fn test_login_page_is_not_a_profile() {
+        // The old regex read "Zwift Racing Score 2025" on this page as a score of 2025
+        let html = include_str!("../tests/fixtures/zwiftpower/profile_login.html");
+        assert!(parse_profile(html).is_err());
+    }
+
+    #[test]
+    fn test_definition_list_layout() {
+        let html = r"<dl><dt>Racing Score:</dt><dd>389</dd><dt>Category:</dt><dd>A+</dd>
+            <dt>zFTP</dt><dd>331 W</dd></dl>";
+        let profile = parse_profile(html).unwrap();
+        assert_eq!(profile.zwift_score, Some(389));
+        assert_eq!(profile.category.as_deref(), Some("A+"));
+        assert_eq!(profile.ftp_w, Some(331));
+        assert_eq!(profile.weight_kg, None);
+    }
+}
@@ -10,9 +10,9 @@ use zwift_race_finder::config::Config;
 fn test_default_config() {
     let config = Config::default();
 
-    // Test defaults structure - these have default values
-    assert_eq!(config.defaults.zwift_score, Some(195));
-    assert_eq!(config.defaults.category, Some("D".to_string()));
+    // Test defaults structure - the score and category are left to be looked up
+    assert_eq!(config.defaults.zwift_score, None);
+    assert_eq!(config.defaults.category, None);
     assert_eq!(config.defaults.weight_kg, Some(86.0));
     assert_eq!(config.defaults.height_m, Some(1.82));
     assert!(config.defaults.ftp_watts.is_none());
@@ -23,6 +23,37 @@ fn test_default_config() {
     assert_eq!(config.preferences.default_days, Some(1));
 }
 
+#[test]
+fn test_named_profile_replaces_defaults() {
+    let toml_content = r#"
+[defaults]
+zwift_score = 250
+weight_kg = 80.0
+
+[profiles.alice]
+zwiftpower_profile_id = "424242"
+
+[profiles.alice.defaults]
+zwift_score = 310
+category = "C"
+"#;
+
+    let mut config: Config = toml::from_str(toml_content).unwrap();
+    assert_eq!(
+        config.profiles["alice"].zwiftpower_profile_id.as_deref(),
+        Some("424242")
+    );
+
+    config.select_profile("default");
+    assert_eq!(config.defaults.zwift_score, Some(250));
+
+    // Alice doesn't inherit the default rider's weight, nor the built-in one
+    config.select_profile("alice");
+    assert_eq!(config.defaults.zwift_score, Some(310));
+    assert_eq!(config.defaults.category, Some("C".to_string()));
+    assert_eq!(config.defaults.weight_kg, None);
+}
+
 #[test]
 fn test_config_from_toml() {
     let toml_content = r#"
@@ -111,7 +142,7 @@ fn test_empty_config() {
     let config: Config = toml::from_str("").unwrap();
 
     // All fields should use defaults from Default trait
-    assert_eq!(config.defaults.zwift_score, Some(195)); // Default value
+    assert_eq!(config.defaults.zwift_score, None); // Looked up, not defaulted
     assert_eq!(config.preferences.default_duration, Some(120)); // Default value
 }
 
@@ -128,7 +159,8 @@ fn test_invalid_config_handling() {
 
     // Should fall back to defaults on error
     let config = Config::load().unwrap_or_default();
-    assert_eq!(config.defaults.zwift_score, Some(195)); // Default value
+    assert_eq!(config.defaults.zwift_score, None); // Default value
+    assert_eq!(config.preferences.default_duration, Some(120));
 
     env::set_current_dir(original_dir).unwrap();
 }
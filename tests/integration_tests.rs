@@ -158,6 +158,34 @@ mod output_format_tests {
         }
     }
 
+    #[test]
+    fn test_invalid_output_format() {
+        let (stdout, stderr, success) = run_command(&["--format", "xml"]);
+
+        assert!(!success);
+        assert!(stdout.is_empty());
+        assert!(stderr.contains("unknown output format 'xml'"));
+    }
+
+    #[test]
+    fn test_ics_reminder_requires_ics() {
+        let (_, stderr, success) = run_command(&["--ics-reminder", "15"]);
+
+        assert!(!success);
+        assert!(stderr.contains("--ics <FILE>"));
+    }
+
+    #[test]
+    fn test_watch_options() {
+        let (_, stderr, success) = run_command(&["--webhook", "http://127.0.0.1:9/hook"]);
+        assert!(!success);
+        assert!(stderr.contains("--watch <MINUTES>"));
+
+        let (_, stderr, success) = run_command(&["--watch", "0"]);
+        assert!(!success);
+        assert!(stderr.contains("invalid value '0'"));
+    }
+
     // Route discovery integration tests
     // (Moved from route_discovery.rs to avoid module import issues)
 
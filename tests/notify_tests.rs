@@ -0,0 +1,138 @@
+//! Watch-mode notification tests against a local stand-in webhook
+
+use chrono::{DateTime, Utc};
+use mockito::Matcher;
+use serde_json::json;
+use zwift_race_finder::event_export::{event_url, EventRecord};
+use zwift_race_finder::notify::{notification_title, Channel, Notifier};
+
+fn record(id: u64, name: &str) -> EventRecord {
+    let start: DateTime<Utc> = "2026-10-24T09:00:00Z".parse().unwrap();
+    EventRecord {
+        id,
+        name: name.to_string(),
+        event_type: "RACE".to_string(),
+        start,
+        url: event_url(id),
+        estimated_minutes: Some(55),
+        estimated_end: Some(start + chrono::Duration::minutes(55)),
+        distance_km: Some(21.5),
+        elevation_m: None,
+        route: None,
+        subgroup: None,
+        tags: vec!["ranked".to_string()],
+    }
+}
+
+#[tokio::test]
+async fn test_webhook_receives_new_events() {
+    let mut server = mockito::Server::new_async().await;
+    let hook = server
+        .mock("POST", "/hooks/races")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::PartialJson(json!({
+                "schema_version": 1,
+                "events": [
+                    {"id": 4_567_890, "name": "Saturday Crit", "url": "https://www.zwift.com/events/view/4567890"},
+                    {"id": 4_567_891, "name": "Sunday Climb"}
+                ]
+            })),
+            Matcher::Regex("\"text\":\"2 new matching events\\\\n".to_string()),
+        ]))
+        .with_status(204)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let events = vec![
+        record(4_567_890, "Saturday Crit"),
+        record(4_567_891, "Sunday Climb"),
+    ];
+    let notifier = Notifier::new(None, Some(format!("{}/hooks/races", server.url())));
+    notifier.notify(&events).await.unwrap();
+    hook.assert_async().await;
+
+    // Nothing new, nothing sent
+    notifier.notify(&[]).await.unwrap();
+    hook.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_failing_webhook_is_reported() {
+    let mut server = mockito::Server::new_async().await;
+    let _hook = server
+        .mock("POST", "/hooks/races")
+        .with_status(500)
+        .create_async()
+        .await;
+
+    let notifier = Notifier::new(None, Some(format!("{}/hooks/races", server.url())));
+    let error = notifier
+        .notify(&[record(1, "Crit")])
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("webhook"), "{error}");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_notification_command_gets_title_and_body() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("notify.sh");
+    let log = dir.path().join("args.txt");
+    std::fs::write(
+        &script,
+        format!("#!/bin/sh\nprintf '%s|' \"$@\" > {}\n", log.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let events = [record(1, "Crit")];
+    let command = format!("{} --urgency=low", script.display());
+    Notifier::new(Some(command), None)
+        .notify(&events)
+        .await
+        .unwrap();
+    let args = std::fs::read_to_string(&log).unwrap();
+    assert!(args.starts_with(&format!("--urgency=low|{}|", notification_title(&events))));
+    assert!(args.contains("Crit, ~00:55|"));
+
+    let failing = Notifier::new(Some("false".to_string()), None);
+    assert!(failing.notify(&events).await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_channels_are_delivered_separately() {
+    let mut server = mockito::Server::new_async().await;
+    let hook = server
+        .mock("POST", "/hooks/races")
+        .with_status(204)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let notifier = Notifier::new(
+        Some("false".to_string()),
+        Some(format!("{}/hooks/races", server.url())),
+    );
+    assert_eq!(notifier.channels(), [Channel::Command, Channel::Webhook]);
+
+    // Retrying the failed command doesn't post to the webhook again
+    let events = [record(1, "Crit")];
+    notifier.deliver(Channel::Webhook, &events).await.unwrap();
+    assert!(notifier.deliver(Channel::Command, &events).await.is_err());
+    assert!(notifier.deliver(Channel::Command, &events).await.is_err());
+    hook.assert_async().await;
+
+    // Unconfigured channels and empty batches are no-ops
+    Notifier::default()
+        .deliver(Channel::Webhook, &events)
+        .await
+        .unwrap();
+    notifier.deliver(Channel::Command, &[]).await.unwrap();
+}
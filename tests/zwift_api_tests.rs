@@ -0,0 +1,175 @@
+//! Authenticated Zwift API client tests against a local stand-in server
+
+use serde_json::json;
+use zwift_race_finder::event_results;
+use zwift_race_finder::zwift_api::ZwiftApiClient;
+
+#[tokio::test]
+async fn test_profile_and_signups_from_stand_in_server() {
+    let mut server = mockito::Server::new_async().await;
+    let _profile = server
+        .mock("GET", "/api/profiles/me")
+        .match_header("authorization", "Bearer secret-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "id": 1_106_548,
+                "firstName": "Jack",
+                "lastName": "C",
+                "weight": 86_000,
+                "ftp": 250,
+                "competitionMetrics": {"racingScore": 194.6, "category": "D"}
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let _signups = server
+        .mock("GET", "/api/profiles/1106548/events/upcoming")
+        .match_header("authorization", "Bearer secret-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!([{
+                "id": 4_567_890,
+                "name": "Stage 1: Downtown Dolphin Race",
+                "eventStart": "2026-10-19T17:00:00Z",
+                "eventType": "RACE",
+                "routeId": 2_698_009_951_u32,
+                "distanceInMeters": 23_300.0,
+                "eventSubgroups": [{"id": 1, "name": "D", "laps": 1}]
+            }])
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client = ZwiftApiClient::new(&format!("{}/", server.url()), "secret-token").unwrap();
+    let rider = client.fetch_profile().await.unwrap();
+    assert_eq!(rider.name(), "Jack C");
+    assert_eq!(rider.racing_score(), Some(195));
+    assert_eq!(rider.category(), Some("D"));
+    assert_eq!(rider.weight_kg(), Some(86.0));
+    assert_eq!(rider.ftp, Some(250));
+
+    let signups = client.fetch_signups(rider.id).await.unwrap();
+    assert_eq!(signups.len(), 1);
+    assert_eq!(signups[0].route_id, Some(2_698_009_951));
+    assert_eq!(signups[0].event_sub_groups[0].laps, Some(1));
+}
+
+#[tokio::test]
+async fn test_rejected_token_is_reported() {
+    let mut server = mockito::Server::new_async().await;
+    let _profile = server
+        .mock("GET", "/api/profiles/me")
+        .with_status(401)
+        .create_async()
+        .await;
+
+    let client = ZwiftApiClient::new(&server.url(), "expired").unwrap();
+    let err = client.fetch_profile().await.unwrap_err();
+    assert!(format!("{err:#}").contains("rejected the access token"));
+}
+
+#[tokio::test]
+async fn test_profile_without_racing_score() {
+    let mut server = mockito::Server::new_async().await;
+    let _profile = server
+        .mock("GET", "/api/profiles/me")
+        .with_status(200)
+        .with_body(json!({"id": 42, "firstName": "New"}).to_string())
+        .create_async()
+        .await;
+
+    let client = ZwiftApiClient::new(&server.url(), "token").unwrap();
+    let rider = client.fetch_profile().await.unwrap();
+    assert_eq!(rider.racing_score(), None);
+    assert_eq!(rider.weight_kg(), None);
+    assert_eq!(rider.name(), "New");
+}
+
+#[tokio::test]
+async fn test_event_and_subgroup_results_from_stand_in_server() {
+    let mut server = mockito::Server::new_async().await;
+    let _event = server
+        .mock("GET", "/api/events/4567890")
+        .with_status(200)
+        .with_body(
+            json!({
+                "id": 4_567_890,
+                "name": "Stage 1: Downtown Dolphin Race",
+                "eventStart": "2026-10-12T17:00:00Z",
+                "eventType": "RACE",
+                "eventSubgroups": [{"id": 7, "name": "D", "routeId": 2_698_009_951_u32, "laps": 2}]
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let _results = server
+        .mock("GET", "/api/race-results/entries")
+        .match_query(mockito::Matcher::UrlEncoded(
+            "event_subgroup_id".into(),
+            "7".into(),
+        ))
+        .with_status(200)
+        .with_body(
+            json!({"entries": [
+                {"profileId": 9, "activityData": {"durationInMilliseconds": 2_500_000}},
+                {"profileId": 1_106_548, "activityData": {"durationInMilliseconds": 2_712_000}}
+            ]})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client = ZwiftApiClient::new(&server.url(), "token").unwrap();
+    let event = client.fetch_event(4_567_890).await.unwrap();
+    let entries = client.fetch_subgroup_results(7).await.unwrap();
+    let finish = event_results::find_rider_finish(&entries, 1_106_548).unwrap();
+    assert_eq!(finish.subgroup_id, Some(7));
+    assert_eq!((finish.position, finish.field_size), (2, 2));
+
+    let captured = event_results::capture_result(&event, &finish, 195).unwrap();
+    assert_eq!(captured.result.route_id, 2_698_009_951);
+    assert_eq!(captured.laps, 2);
+    assert_eq!(captured.result.actual_minutes, 45);
+}
+
+#[tokio::test]
+async fn test_subgroup_results_follow_pages() {
+    use mockito::Matcher;
+    use zwift_race_finder::zwift_api::RESULTS_PAGE_SIZE;
+
+    let mut server = mockito::Server::new_async().await;
+    let entry = |id: usize| json!({"profileId": id, "activityData": {"durationInMilliseconds": 2_000_000 + id}});
+    let first: Vec<_> = (1..=RESULTS_PAGE_SIZE).map(entry).collect();
+    let _first = server
+        .mock("GET", "/api/race-results/entries")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("event_subgroup_id".into(), "7".into()),
+            Matcher::UrlEncoded("start".into(), "0".into()),
+        ]))
+        .with_status(200)
+        .with_body(json!({ "entries": first }).to_string())
+        .create_async()
+        .await;
+    let _second = server
+        .mock("GET", "/api/race-results/entries")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("event_subgroup_id".into(), "7".into()),
+            Matcher::UrlEncoded("start".into(), RESULTS_PAGE_SIZE.to_string()),
+        ]))
+        .with_status(200)
+        .with_body(json!({ "entries": [entry(1_106_548)] }).to_string())
+        .create_async()
+        .await;
+
+    let client = ZwiftApiClient::new(&server.url(), "token").unwrap();
+    let entries = client.fetch_subgroup_results(7).await.unwrap();
+    assert_eq!(entries.len(), RESULTS_PAGE_SIZE + 1);
+    let finish = event_results::find_rider_finish(&entries, 1_106_548).unwrap();
+    assert_eq!(finish.position, RESULTS_PAGE_SIZE as u32 + 1);
+}
@@ -0,0 +1,193 @@
+//! zwift-offline sync tests against a local stand-in server
+
+use mockito::{Mock, ServerGuard};
+use serde_json::{json, Value};
+use zwift_race_finder::database::{Database, RouteProvenance, RouteSource};
+use zwift_race_finder::zwift_offline_client::ZwiftOfflineClient;
+use zwift_race_finder::zwift_offline_sync::{apply_snapshot, diff_routes, fetch_snapshot};
+
+async fn mock_export(server: &mut ServerGuard, endpoint: &str, body: Value) -> Mock {
+    server
+        .mock("GET", format!("/api/export/{endpoint}").as_str())
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(body.to_string())
+        .create_async()
+        .await
+}
+
+#[tokio::test]
+async fn test_sync_from_stand_in_server() {
+    let mut server = mockito::Server::new_async().await;
+    let _routes = mock_export(
+        &mut server,
+        "routes",
+        json!({
+            "count": 3,
+            "source": "zwift-offline",
+            "routes": [
+                {
+                    "route_id": 2_698_009_951_i64 - 4_294_967_296,
+                    "name": "Crit City - Downtown Dolphin",
+                    "distance_m": 23_300.0,
+                    "distance_km": 23.3,
+                    "distance_without_lead_in_km": 22.9,
+                    "lead_in_distance_km": 0.4,
+                    "world_name": "Crit City",
+                    "sport": 0,
+                    "surface": "road",
+                    "elevation_gain": 0
+                },
+                {
+                    "route_id": 12345,
+                    "name": "Watopia - New Loop",
+                    "distance_m": 10_000.0,
+                    "distance_km": 10.0,
+                    "distance_without_lead_in_km": 9.5,
+                    "lead_in_distance_km": 0.5,
+                    "world_name": "Watopia",
+                    "sport": 0,
+                    "surface": "gravel",
+                    "elevation_gain": 88.4
+                },
+                {
+                    "route_id": 777,
+                    "name": "Bologna - Incomplete",
+                    "distance_m": 0,
+                    "distance_km": 0,
+                    "world_name": "Bologna",
+                    "sport": 0
+                }
+            ]
+        }),
+    )
+    .await;
+    let _start_lines = mock_export(
+        &mut server,
+        "start_lines",
+        json!({
+            "count": 1,
+            "source": "zwift-offline",
+            "start_lines": [{"hash": "-1596957345", "name": "Crit City - Downtown Dolphin", "road": 0, "time": 628_605}]
+        }),
+    )
+    .await;
+    let _events = mock_export(
+        &mut server,
+        "events",
+        json!({
+            "count": 2,
+            "source": "zwift-offline",
+            "events": [
+                {"name": "New Loop Race", "route": 12345, "distance": 10_000.0, "course": 6, "sport": 0},
+                {"name": "Mystery Event", "route": 999, "distance": 5_000.0, "course": 6, "sport": 0}
+            ]
+        }),
+    )
+    .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db = Database::open(&dir.path().join("races.db")).unwrap();
+    let client = ZwiftOfflineClient::new(&server.url()).unwrap();
+    let snapshot = fetch_snapshot(&client).await.unwrap();
+    assert_eq!(snapshot.routes.len(), 2);
+    assert_eq!(snapshot.incomplete_routes, 1);
+    assert_eq!(snapshot.start_lines[0].route_id, 2_698_009_951);
+    assert_eq!(snapshot.events_with_unknown_routes().len(), 1);
+
+    // Downtown Dolphin is seeded at 22.9 km with 80 m; the server renames it and adds lead-in
+    let diff = diff_routes(&db, &snapshot.routes).unwrap();
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].route_id, 12345);
+    assert_eq!(diff.added[0].elevation_m, 88);
+    assert_eq!(diff.changed.len(), 1);
+    let fields: Vec<&str> = diff.changed[0].changes.iter().map(|c| c.field).collect();
+    assert_eq!(fields, ["name", "lead-in"]);
+    assert!(diff.removed.is_empty());
+
+    apply_snapshot(&db, &snapshot).unwrap();
+    let dolphin = db.get_route(2_698_009_951).unwrap().unwrap();
+    assert_eq!(dolphin.elevation_m, 80);
+    assert_eq!(dolphin.lead_in_distance_km, 0.4);
+    let provenance = db.get_route_provenance(12345).unwrap().unwrap();
+    assert_eq!(provenance.source, RouteSource::ZwiftOffline);
+    assert!(provenance.verified_at.is_some());
+    assert_eq!(db.get_start_lines().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_sync_reports_removed_and_trusted_routes() {
+    let mut server = mockito::Server::new_async().await;
+    let _routes = mock_export(
+        &mut server,
+        "routes",
+        json!({
+            "count": 1,
+            "source": "zwift-offline",
+            "routes": [{
+                "route_id": 3_742_187_716_i64 - 4_294_967_296,
+                "name": "Makuri Islands - Castle to Castle",
+                "distance_m": 25_000.0,
+                "distance_km": 25.0,
+                "world_name": "Makuri Islands",
+                "sport": 0,
+                "elevation_gain": 170
+            }]
+        }),
+    )
+    .await;
+    let empty = |key: &str| json!({"count": 0, "source": "zwift-offline", key: []});
+    let _start_lines = mock_export(&mut server, "start_lines", empty("start_lines")).await;
+    let _events = mock_export(&mut server, "events", empty("events")).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db = Database::open(&dir.path().join("races.db")).unwrap();
+    let mut gone = db.get_route(1_258_415_487).unwrap().unwrap();
+    gone.route_id = 4242;
+    db.add_route(
+        &gone,
+        &RouteProvenance::unverified(RouteSource::ZwiftOffline),
+    )
+    .unwrap();
+    let mut castle = db.get_route(3_742_187_716).unwrap().unwrap();
+    castle.distance_km = 24.6;
+    db.add_route(
+        &castle,
+        &RouteProvenance {
+            confidence: 1.0,
+            ..RouteProvenance::unverified(RouteSource::WhatsOnZwift)
+        },
+    )
+    .unwrap();
+
+    let client = ZwiftOfflineClient::new(&server.url()).unwrap();
+    let snapshot = fetch_snapshot(&client).await.unwrap();
+    let diff = diff_routes(&db, &snapshot.routes).unwrap();
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].route_id, 4242);
+    assert_eq!(diff.changed.len(), 1);
+    assert!(diff.changed[0].kept);
+
+    let summary = apply_snapshot(&db, &snapshot).unwrap();
+    assert_eq!(summary.routes_kept, 1);
+    assert_eq!(
+        db.get_route(3_742_187_716).unwrap().unwrap().distance_km,
+        24.6
+    );
+    // Removed routes stay, since results may still refer to them
+    assert!(db.get_route(4242).unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_sync_fails_cleanly_when_server_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let _routes = server
+        .mock("GET", "/api/export/routes")
+        .with_status(500)
+        .with_body("boom")
+        .create_async()
+        .await;
+
+    let client = ZwiftOfflineClient::new(&server.url()).unwrap();
+    assert!(fetch_snapshot(&client).await.is_err());
+}